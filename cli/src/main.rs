@@ -0,0 +1,49 @@
+//! Single entry point for the simulation scenarios: `sim <scenario> [options]`.
+//!
+//! Every scenario accepts the options of [`RunArgs`] and shares the exit codes
+//! documented in `physics_core::cli`.
+
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use physics_core::cli::{execute, RunArgs};
+
+#[derive(Parser)]
+#[command(name = "sim", about = "Run one of the simulation scenarios")]
+struct Cli {
+    #[command(subcommand)]
+    scenario: Scenario,
+}
+
+#[derive(Subcommand)]
+enum Scenario {
+    /// Scalar field lattice thermalized at a Hawking temperature (tbath)
+    Lattice(RunArgs),
+    /// Photon to neutrino conversion through torsion (singularity)
+    Torsion(RunArgs),
+    /// Quark-gluon plasma fluid under a gravitational-wave metric (collider)
+    QgpFluid(RunArgs),
+    /// Photon and axion fields through the cosmic expansion history (dirac)
+    Cosmology(RunArgs),
+    /// Photon diffusion and axion conversion around recombination (unify)
+    Recombination(RunArgs),
+    /// Species mixing through a Hecke R-matrix (color-algebra)
+    Hecke(RunArgs),
+    /// Pair production in a gamma-ray-burst fireball (branch)
+    Grb(RunArgs),
+    /// Carnot cycle of a monoatomic ideal gas (carnot-visuals)
+    Carnot(RunArgs),
+}
+
+fn main() -> ExitCode {
+    match Cli::parse().scenario {
+        Scenario::Lattice(args) => execute(&args, tbath::run),
+        Scenario::Torsion(args) => execute(&args, singularity::run),
+        Scenario::QgpFluid(args) => execute(&args, collider::run),
+        Scenario::Cosmology(args) => execute(&args, dirac::run),
+        Scenario::Recombination(args) => execute(&args, unify::run),
+        Scenario::Hecke(args) => execute(&args, color_algebra::run),
+        Scenario::Grb(args) => execute(&args, branch::run),
+        Scenario::Carnot(args) => execute(&args, carnot_visuals::run),
+    }
+}
@@ -0,0 +1,246 @@
+use physics_core::config::{non_negative, positive, total_time_for, GridConfig, RunConfig, Validate};
+use physics_core::eos::EosSettings;
+use physics_core::error::{check_finite, SimError};
+use physics_core::gw::{GwBackground, GwSettings, Mode};
+use physics_core::io::CsvWriter;
+use physics_core::diffusion::{CgSettings, DiffusionScheme, Diffuser};
+use physics_core::ledger::Conservation;
+use physics_core::species::{Mixture, Reaction, Species, Splitting};
+use physics_core::stability::Stability;
+use physics_core::Boundaries;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+
+// Run parameters. Defaults reproduce the original constants; pass a TOML or
+// JSON file with `--config` to override them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    grid: GridConfig,   // Lattice parameters (3D space), spatial step (m)
+    dt: f64,            // Time step (s)
+    total_time: f64,    // Longer simulation time (s)
+
+    // Magnetic field scale
+    b_0: f64,           // 1 Tesla baseline (placeholder)
+
+    // QCD-like parameters
+    eos: EosSettings,   // p(ε) driving the expansion sink
+    epsilon_crit: f64,  // centre and width of the QGP fraction
+    delta: f64,
+
+    // Axion-photon and neutrino parameters (refined to smaller couplings)
+    g_a_gamma: f64,     // much smaller coupling
+    gamma_a: f64,       // reduced axion decay rate
+
+    // Neutrino parameters (weaker interactions)
+    d_nu: f64,          // reduced neutrino diffusion
+    lambda_nu: f64,     // extremely small energy sink rate
+
+    // Gravitational-wave background at LIGO-like scale: strain ~ 10^-21 at
+    // 100 Hz and 200 Hz, wavelengths of thousands of kilometres.
+    gw: GwSettings,
+
+    // Diffusion coefficients (keep photons/energy/axions modest)
+    d_ph: f64,
+    d_e: f64,
+    d_a: f64,
+
+    // Initial conditions in a QGP-like regime with large energy density
+    epsilon_init: f64,  // J/m^3
+    photon_init: f64,   // photons/m^3
+    axion_init: f64,    // axions/m^3
+    neutrino_init: f64, // neutrinos/m^3
+
+    energy_bc: Boundaries,
+    photon_bc: Boundaries,
+    axion_bc: Boundaries,
+    neutrino_bc: Boundaries,
+    energy_scheme: DiffusionScheme,
+    photon_scheme: DiffusionScheme,
+    axion_scheme: DiffusionScheme,
+    neutrino_scheme: DiffusionScheme,
+    splitting: Splitting,
+    conservation: Conservation,
+    stability: Stability,
+    cg: CgSettings,
+    out_dir: PathBuf,
+    results_file: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            grid: GridConfig::new(40, 40, 40, 1.0),
+            dt: 0.1,
+            total_time: 1000.0,
+            b_0: 1.0, //1.0 * MU_PRIMED.powi(64);
+            eos: EosSettings::PowerLaw { coefficient: 0.33, exponent: 1.022, epsilon_crit: 1e6 }, // exponent was 1.2
+            epsilon_crit: 1e6,
+            delta: 1e5,
+            g_a_gamma: 1e-14,
+            gamma_a: 1e-9,
+            d_nu: 1e-4,
+            lambda_nu: 1e-14,
+            gw: GwSettings {
+                modes: vec![
+                    Mode { plus: 1e-21, frequency: 100.0, ..Mode::default() },
+                    Mode { plus: 5e-22, frequency: 200.0, phase: -0.5 * PI, ..Mode::default() },
+                ],
+                chirps: Vec::new(),
+            },
+            d_ph: 0.1,
+            d_e: 0.01,
+            d_a: 0.01,
+            epsilon_init: 1e7,
+            photon_init: 1e10,
+            axion_init: 1e-2,
+            neutrino_init: 0.86,
+            energy_bc: Boundaries::periodic(),
+            photon_bc: Boundaries::periodic(),
+            axion_bc: Boundaries::periodic(),
+            neutrino_bc: Boundaries::periodic(),
+            energy_scheme: DiffusionScheme::Explicit,
+            photon_scheme: DiffusionScheme::Explicit,
+            axion_scheme: DiffusionScheme::Explicit,
+            neutrino_scheme: DiffusionScheme::Explicit,
+            splitting: Splitting::Lie,
+            conservation: Conservation::default(),
+            stability: Stability::default(),
+            cg: CgSettings::default(),
+            out_dir: PathBuf::from("."),
+            results_file: "fluid_lattice.csv".into(),
+        }
+    }
+}
+
+impl Validate for Config {
+    fn validate(&self) -> Result<(), String> {
+        positive("dt", self.dt)?;
+        positive("total_time", self.total_time)?;
+        non_negative("b_0", self.b_0)?;
+        self.eos.validate()?;
+        positive("epsilon_crit", self.epsilon_crit)?;
+        positive("delta", self.delta)?;
+        non_negative("g_a_gamma", self.g_a_gamma)?;
+        non_negative("gamma_a", self.gamma_a)?;
+        non_negative("d_nu", self.d_nu)?;
+        non_negative("lambda_nu", self.lambda_nu)?;
+        self.gw.validate()?;
+        non_negative("d_ph", self.d_ph)?;
+        non_negative("d_e", self.d_e)?;
+        non_negative("d_a", self.d_a)?;
+        non_negative("epsilon_init", self.epsilon_init)?;
+        non_negative("photon_init", self.photon_init)?;
+        non_negative("axion_init", self.axion_init)?;
+        non_negative("neutrino_init", self.neutrino_init)?;
+        self.conservation.validate()?;
+        self.stability.validate()?;
+        self.cg.validate()?;
+        self.grid.validate()
+    }
+}
+
+impl RunConfig for Config {
+    fn out_dir(&self) -> &Path {
+        &self.out_dir
+    }
+
+    fn set_out_dir(&mut self, dir: PathBuf) {
+        self.out_dir = dir;
+    }
+
+    fn set_steps(&mut self, steps: usize) {
+        self.total_time = total_time_for(steps, self.dt);
+    }
+}
+
+// Species of the fluid mixture, in the order they are added to it.
+const ENERGY: usize = 0;
+const PHOTON: usize = 1;
+const AXION: usize = 2;
+const NEUTRINO: usize = 3;
+
+// QGP fraction
+fn qgp_fraction(cfg: &Config, epsilon: f64) -> f64 {
+    0.5 * (1.0 + ((epsilon - cfg.epsilon_crit) / cfg.delta).tanh())
+}
+
+/// Evolve the fluid lattice for `total_time`, writing the lattice averages
+/// to `results_file` in `out_dir`.
+pub fn run(cfg: &Config) -> Result<(), SimError> {
+    let dt = cfg.dt;
+    let steps = (cfg.total_time / dt) as usize;
+
+    // The waves speed diffusion up by at most this much along their axes.
+    let gw = GwBackground::new(&cfg.gw);
+    let alpha_max = 1.0 + gw.max_strain();
+    let dx = cfg.grid.dx;
+    let energy = Diffuser::new("energy", cfg.energy_scheme, cfg.d_e, dt, &cfg.cg)
+        .with_max_scale(alpha_max)
+        .checked(dx, &cfg.stability)?;
+    let photons = Diffuser::new("photon", cfg.photon_scheme, cfg.d_ph, dt, &cfg.cg)
+        .with_max_scale(alpha_max)
+        .checked(dx, &cfg.stability)?;
+    let axions = Diffuser::new("axion", cfg.axion_scheme, cfg.d_a, dt, &cfg.cg)
+        .with_max_scale(alpha_max)
+        .checked(dx, &cfg.stability)?;
+    let neutrinos = Diffuser::new("neutrino", cfg.neutrino_scheme, cfg.d_nu, dt, &cfg.cg)
+        .with_max_scale(alpha_max)
+        .checked(dx, &cfg.stability)?;
+
+    let eos = cfg.eos.build()?;
+
+    let mut fluid = Mixture::new(cfg.splitting);
+    fluid.add(Species::new("energy", cfg.grid.filled(cfg.epsilon_init)).with_diffusion(cfg.energy_bc, energy));
+    fluid.add(Species::new("photon", cfg.grid.filled(cfg.photon_init)).with_diffusion(cfg.photon_bc, photons));
+    fluid.add(Species::new("axion", cfg.grid.filled(cfg.axion_init)).with_diffusion(cfg.axion_bc, axions));
+    fluid.add(Species::new("neutrino", cfg.grid.filled(cfg.neutrino_init)).with_diffusion(cfg.neutrino_bc, neutrinos));
+
+    if cfg.conservation.enabled {
+        let path = cfg.out_dir.join(&cfg.conservation.file);
+        let groups: [(&str, &[usize]); 2] = [("number", &[PHOTON, AXION, NEUTRINO]), ("energy", &[ENERGY])];
+        fluid.track(path, &groups, cfg.conservation.tolerance)?;
+    }
+
+    let reactions = [
+        // Axion-photon coupling
+        Reaction::new("axion-photon coupling", |n, _| cfg.g_a_gamma * n[AXION] * cfg.b_0.powi(2)).produces(PHOTON),
+        Reaction::new("axion decay", |n, _| cfg.gamma_a * n[AXION]).consumes(AXION),
+        // Neutrino energy sink
+        Reaction::new("neutrino energy sink", |n, _| cfg.lambda_nu * n[NEUTRINO]).consumes(ENERGY),
+        // QCD-driven sink (mimic expansion)
+        Reaction::new("QCD expansion sink", |n, _| eos.pressure(n[ENERGY]) * 1e-3).consumes(ENERGY),
+    ];
+
+    let results_path = cfg.out_dir.join(&cfg.results_file);
+    let mut file = CsvWriter::create(
+        &results_path,
+        &["time(s)", "avg_energy(J/m^3)", "avg_photon(m^-3)", "avg_axion(m^-3)", "avg_neutrino(m^-3)", "avg_qgp_fraction"],
+    )?;
+
+    for step in 0..steps {
+        let t = step as f64 * dt;
+
+        // Diffusion runs on the perturbed metric.
+        let h = gw.perturbation(t, &fluid[ENERGY].density);
+        fluid.step_in_metric(dt, &reactions, &h)?;
+
+        let avg_e = fluid[ENERGY].density.mean();
+        let avg_ph = fluid[PHOTON].density.mean();
+        let avg_a = fluid[AXION].density.mean();
+        let avg_nu = fluid[NEUTRINO].density.mean();
+        let avg_qgp = qgp_fraction(cfg, avg_e);
+
+        file.row(&[t, avg_e, avg_ph, avg_a, avg_nu, avg_qgp])?;
+        for s in fluid.species() {
+            check_finite(&format!("{} density", s.name), step, &s.density)?;
+        }
+        fluid.end_step(step, t)?;
+    }
+    file.finish()?;
+    fluid.close_ledger()?;
+
+    println!("Fluid lattice simulation completed. Results in {}", results_path.display());
+    Ok(())
+}
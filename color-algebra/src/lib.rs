@@ -0,0 +1,297 @@
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+use physics_core::config::{at_least, non_negative, positive, GridConfig, RunConfig, Validate};
+use physics_core::eos::EosSettings;
+use physics_core::error::{check_finite, SimError};
+use physics_core::gw::{GwBackground, GwSettings, Mode};
+use physics_core::io::CsvWriter;
+use physics_core::diffusion::{CgSettings, DiffusionScheme, Diffuser};
+use physics_core::ledger::{Conservation, Process};
+use physics_core::species::{Mixture, Reaction, Species, Splitting};
+use physics_core::stability::Stability;
+use physics_core::Boundaries;
+use serde::{Deserialize, Serialize};
+
+//----------------------------------------------
+// RUN CONFIGURATION
+//----------------------------------------------
+// Every tunable below used to be a `const`; the defaults keep those values.
+// Pass a TOML or JSON file with `--config` to override any of them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // UPDATED COUPLING CONSTANTS
+    // Reduced couplings for stability and more realistic scales:
+    g_a_gamma: f64,                // Much smaller axion-photon coupling
+    photon_to_neutrino_coeff: f64, // Reduced photon->neutrino conversion
+
+    // HECKE AND YANG-BAXTER PARAMETERS
+    q: f64,                        // Bring q closer to 1 for minimal deformation
+
+    // SIMULATION PARAMETERS
+    grid: GridConfig,              // Keep the same spatial resolution (0.5 fm)
+    dt: f64,                       // Use a smaller timestep for numerical stability (half the original)
+    steps: usize,
+
+    // Reduced initial densities to avoid immediate runaway
+    photon_init: f64,
+    axion_init: f64,
+    neutrino_init: f64,
+
+    // Energy scale remains the same, but we rely on reduced couplings for stability
+    energy_init: f64,
+    eos: EosSettings,
+
+    // Reduced diffusion coefficients for stability
+    d_ph: f64,
+    d_ax: f64,
+    d_nu: f64,
+    d_e: f64,
+
+    // Slightly reduced sink/expansion terms
+    lambda_nu: f64,
+    alpha_expansion: f64,
+
+    // Gravitational wave parameters unchanged (very small effect anyway)
+    gw: GwSettings,
+
+    photon_bc: Boundaries,
+    axion_bc: Boundaries,
+    neutrino_bc: Boundaries,
+    energy_bc: Boundaries,
+    photon_scheme: DiffusionScheme,
+    axion_scheme: DiffusionScheme,
+    neutrino_scheme: DiffusionScheme,
+    energy_scheme: DiffusionScheme,
+    splitting: Splitting,
+    conservation: Conservation,
+    stability: Stability,
+    cg: CgSettings,
+    out_dir: PathBuf,
+    results_file: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            g_a_gamma: 1e-12,
+            photon_to_neutrino_coeff: 1e-12,
+            q: 1.001,
+            grid: GridConfig::new(20, 20, 20, 0.5e-15),
+            dt: 1.22e-15,
+            steps: 100,
+            photon_init: 1e30,
+            axion_init: 1e26,
+            neutrino_init: 1e20,
+            energy_init: 3.2e35,
+            eos: EosSettings::Crossover { epsilon_crit: 1.6e35, delta: 0.2e35, c_s2_qgp: 1.0 / 3.0, c_s2_hadron: 0.15 },
+            d_ph: 1e-4,
+            d_ax: 1e-4,
+            d_nu: 1e-4,
+            d_e: 1e-4,
+            lambda_nu: 1e-6,
+            alpha_expansion: 1e-6,
+            gw: GwSettings {
+                modes: vec![Mode { plus: 1e-21, frequency: 1e3, phase: -0.5 * PI, ..Mode::default() }],
+                chirps: Vec::new(),
+            },
+            photon_bc: Boundaries::neumann(),
+            axion_bc: Boundaries::neumann(),
+            neutrino_bc: Boundaries::neumann(),
+            energy_bc: Boundaries::neumann(),
+            photon_scheme: DiffusionScheme::BackwardEuler,
+            axion_scheme: DiffusionScheme::BackwardEuler,
+            neutrino_scheme: DiffusionScheme::BackwardEuler,
+            energy_scheme: DiffusionScheme::BackwardEuler,
+            splitting: Splitting::Lie,
+            conservation: Conservation::default(),
+            stability: Stability::default(),
+            cg: CgSettings::default(),
+            out_dir: PathBuf::from("."),
+            results_file: "results.csv".into(),
+        }
+    }
+}
+
+impl Validate for Config {
+    fn validate(&self) -> Result<(), String> {
+        non_negative("g_a_gamma", self.g_a_gamma)?;
+        non_negative("photon_to_neutrino_coeff", self.photon_to_neutrino_coeff)?;
+        positive("q", self.q)?;
+        positive("dt", self.dt)?;
+        at_least("steps", self.steps, 1)?;
+        non_negative("photon_init", self.photon_init)?;
+        non_negative("axion_init", self.axion_init)?;
+        non_negative("neutrino_init", self.neutrino_init)?;
+        non_negative("energy_init", self.energy_init)?;
+        self.eos.validate()?;
+        non_negative("d_ph", self.d_ph)?;
+        non_negative("d_ax", self.d_ax)?;
+        non_negative("d_nu", self.d_nu)?;
+        non_negative("d_e", self.d_e)?;
+        non_negative("lambda_nu", self.lambda_nu)?;
+        non_negative("alpha_expansion", self.alpha_expansion)?;
+        self.gw.validate()?;
+        self.conservation.validate()?;
+        self.stability.validate()?;
+        self.cg.validate()?;
+        self.grid.validate()
+    }
+}
+
+impl RunConfig for Config {
+    fn out_dir(&self) -> &Path {
+        &self.out_dir
+    }
+
+    fn set_out_dir(&mut self, dir: PathBuf) {
+        self.out_dir = dir;
+    }
+
+    fn set_steps(&mut self, steps: usize) {
+        self.steps = steps;
+    }
+}
+
+//----------------------------------------------
+// NONLINEAR SATURATION FUNCTIONS
+//----------------------------------------------
+// Introduce saturation to prevent runaway growth
+fn axion_photon_conversion(cfg: &Config, n_ax: f64, n_ph: f64) -> f64 {
+    let saturation = 1.0 + (n_ph / 1e33);
+    (cfg.g_a_gamma * n_ax) / saturation
+}
+
+fn photon_neutrino_conversion(cfg: &Config, n_ph: f64) -> f64 {
+    let saturation = 1.0 + (n_ph / 1e33);
+    (cfg.photon_to_neutrino_coeff * n_ph) / saturation
+}
+
+//----------------------------------------------
+// FIELD STRUCTURE
+//----------------------------------------------
+// Indices into the species mixture, in the order the species are added.
+const PHOTON: usize = 0;
+const AXION: usize = 1;
+const NEUTRINO: usize = 2;
+const ENERGY: usize = 3;
+
+//----------------------------------------------
+// HECKE R-MATRIX APPLICATION
+//----------------------------------------------
+fn apply_hecke_r_matrix(fields: &mut Mixture, q: f64) {
+    // Following the same logic, just with milder q and lambda
+    let (nx, ny, nz) = fields[PHOTON].density.dims();
+    for z in 0..nz {
+        for y in 0..ny {
+            for x in 0..(nx-1) {
+                let i = fields[PHOTON].density.idx(x,y,z);
+                let j = fields[PHOTON].density.idx(x+1,y,z);
+
+                let ph_i = fields[PHOTON].density[i];
+                let ax_i = fields[AXION].density[i];
+                let ph_j = fields[PHOTON].density[j];
+                let ax_j = fields[AXION].density[j];
+
+                let qm = q.powf(-0.5);
+                let qp = q.powf(0.5);
+
+                // Same heuristic R-matrix step
+                let ph_i_new = 0.5*(ph_i*qm + ax_j);
+                let ax_i_new = 0.5*(ax_i*qp + ph_j);
+                let ph_j_new = 0.5*(ph_j*qm + ax_i);
+                let ax_j_new = 0.5*(ax_j*qp + ph_i);
+
+                fields[PHOTON].density[i] = ph_i_new;
+                fields[AXION].density[i] = ax_i_new;
+                fields[PHOTON].density[j] = ph_j_new;
+                fields[AXION].density[j] = ax_j_new;
+            }
+        }
+    }
+}
+
+//----------------------------------------------
+// MAIN TIME EVOLUTION
+//----------------------------------------------
+/// Evolve the fields for `steps` steps, mixing species through the Hecke
+/// R-matrix, and write the lattice averages to `results_file` in `out_dir`.
+pub fn run(cfg: &Config) -> Result<(), SimError> {
+    let dt = cfg.dt;
+
+    // The wave speeds diffusion up by at most this much along its axes.
+    let gw = GwBackground::new(&cfg.gw);
+    let alpha_max = 1.0 + gw.max_strain();
+    let dx = cfg.grid.dx;
+    let photons = Diffuser::new("photon", cfg.photon_scheme, cfg.d_ph, dt, &cfg.cg)
+        .with_max_scale(alpha_max)
+        .checked(dx, &cfg.stability)?;
+    let axions = Diffuser::new("axion", cfg.axion_scheme, cfg.d_ax, dt, &cfg.cg)
+        .with_max_scale(alpha_max)
+        .checked(dx, &cfg.stability)?;
+    let neutrinos = Diffuser::new("neutrino", cfg.neutrino_scheme, cfg.d_nu, dt, &cfg.cg)
+        .with_max_scale(alpha_max)
+        .checked(dx, &cfg.stability)?;
+    let energy = Diffuser::new("energy", cfg.energy_scheme, cfg.d_e, dt, &cfg.cg)
+        .with_max_scale(alpha_max)
+        .checked(dx, &cfg.stability)?;
+
+    let eos = cfg.eos.build()?;
+
+    let mut field = Mixture::new(cfg.splitting);
+    field.add(Species::new("photon", cfg.grid.filled(cfg.photon_init)).with_diffusion(cfg.photon_bc, photons));
+    field.add(Species::new("axion", cfg.grid.filled(cfg.axion_init)).with_diffusion(cfg.axion_bc, axions));
+    field.add(Species::new("neutrino", cfg.grid.filled(cfg.neutrino_init)).with_diffusion(cfg.neutrino_bc, neutrinos));
+    field.add(Species::new("energy", cfg.grid.filled(cfg.energy_init)).with_diffusion(cfg.energy_bc, energy));
+
+    if cfg.conservation.enabled {
+        let path = cfg.out_dir.join(&cfg.conservation.file);
+        let groups: [(&str, &[usize]); 2] = [("number", &[PHOTON, AXION, NEUTRINO]), ("energy", &[ENERGY])];
+        field.track(path, &groups, cfg.conservation.tolerance)?;
+    }
+
+    // Use saturation functions
+    let reactions = [
+        Reaction::new("axion → photon", |n, _| axion_photon_conversion(cfg, n[AXION], n[PHOTON]))
+            .consumes(AXION)
+            .produces(PHOTON),
+        Reaction::new("photon → neutrino", |n, _| photon_neutrino_conversion(cfg, n[PHOTON]))
+            .consumes(PHOTON)
+            .produces(NEUTRINO),
+        Reaction::new("neutrino energy sink", |n, _| cfg.lambda_nu * n[NEUTRINO]).consumes(ENERGY),
+        Reaction::new("expansion", |n, _| eos.pressure(n[ENERGY]) * cfg.alpha_expansion).consumes(ENERGY),
+    ];
+
+    let results_path = cfg.out_dir.join(&cfg.results_file);
+    let mut file = CsvWriter::create(
+        &results_path,
+        &["time(s)", "avg_photon_density", "avg_axion_density", "avg_neutrino_density", "avg_energy_density"],
+    )?;
+
+    for step in 0..cfg.steps {
+        let t = step as f64 * dt;
+
+        // Diffusion runs on the metric perturbed by the gravitational wave
+        let h = gw.perturbation(t, &field[ENERGY].density);
+        field.step_in_metric(dt, &reactions, &h)?;
+
+        // Apply the modified Hecke R-matrix step
+        field.apply(Process::RMatrix, |m| apply_hecke_r_matrix(m, cfg.q));
+
+        let avg_photon = field[PHOTON].density.mean();
+        let avg_axion = field[AXION].density.mean();
+        let avg_neutrino = field[NEUTRINO].density.mean();
+        let avg_energy = field[ENERGY].density.mean();
+
+        file.row(&[t, avg_photon, avg_axion, avg_neutrino, avg_energy])?;
+        for s in field.species() {
+            check_finite(&format!("{} density", s.name), step, &s.density)?;
+        }
+        field.end_step(step, t)?;
+    }
+    file.finish()?;
+    field.close_ledger()?;
+
+    println!("Simulation complete. Results saved to {}", results_path.display());
+    Ok(())
+}
@@ -14,15 +14,11 @@ with open(results_file, 'r') as f:
     reader = csv.reader(f)
     header = next(reader)  # skip header
     for row in reader:
-        # time(s), scale_factor, a, avg_photon(m^-3), avg_axion(m^-3)
-        # According to the code, columns are: t, a, avg_ph, avg_ax
-        # The file actually: "time(s),scale_factor,a,avg_photon(m^-3),avg_axion(m^-3)"
-        # Here 'scale_factor' and 'a' are redundant (the same), but we will just use one.
-        
+        # time(s), scale_factor, redshift, hubble(s^-1), avg_photon(m^-3), avg_axion(m^-3)
         t = float(row[0])
         a_val = float(row[1])  # scale_factor
-        ph = float(row[3])
-        ax = float(row[4])
+        ph = float(row[4])
+        ax = float(row[5])
         
         time.append(t)
         scale_factor.append(a_val)
@@ -0,0 +1,217 @@
+use std::path::{Path, PathBuf};
+
+use physics_core::config::{non_negative, positive, total_time_for, GridConfig, RunConfig, Validate};
+use physics_core::error::{check_finite, SimError};
+use physics_core::constants::si::{C, HBAR};
+use physics_core::cosmology::{redshift, Background, CosmologySettings};
+use physics_core::io::CsvWriter;
+use physics_core::diffusion::{CgSettings, DiffusionScheme, Diffuser};
+use physics_core::frw::FrwClock;
+use physics_core::stability::Stability;
+use physics_core::{Boundaries, Grid3};
+use serde::{Deserialize, Serialize};
+
+// Run parameters; the defaults are the values this simulation has always
+// used. Pass a TOML or JSON file with `--config` to override them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    cosmology: CosmologySettings, // expansion history
+    n_gamma_0: f64,      // Photon number density today (m^-3)
+    b0: f64,             // primordial B-field upper limit (Tesla)
+    g_agamma: f64,       // Axion-photon coupling upper limit (J^-1 approx)
+    a_init: f64,         // scale factor at the start of the run
+    dt: f64,             // s (large time step to simulate cosmic evolution)
+    total_time: f64,     // s
+    grid: GridConfig,    // box size and grid (comoving m, physical today)
+    photon_bc: Boundaries,
+    photon_scheme: DiffusionScheme,
+    stability: Stability,
+    cg: CgSettings,
+    out_dir: PathBuf,
+    results_file: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            cosmology: CosmologySettings::default(),
+            n_gamma_0: 4.11e8,
+            b0: 1e-9,
+            g_agamma: 1e-20,
+            a_init: 1e-3, // z~999
+            dt: 1e11,
+            total_time: 4.35e17, // ~13.8 Gyr in seconds
+            grid: GridConfig::new(20, 20, 20, 1.0),
+            photon_bc: Boundaries::periodic(),
+            photon_scheme: DiffusionScheme::Explicit,
+            stability: Stability::default(),
+            cg: CgSettings::default(),
+            out_dir: PathBuf::from("."),
+            results_file: "cosmic_evolution.csv".into(),
+        }
+    }
+}
+
+impl Validate for Config {
+    fn validate(&self) -> Result<(), String> {
+        self.cosmology.validate()?;
+        non_negative("n_gamma_0", self.n_gamma_0)?;
+        non_negative("b0", self.b0)?;
+        non_negative("g_agamma", self.g_agamma)?;
+        positive("a_init", self.a_init)?;
+        positive("dt", self.dt)?;
+        positive("total_time", self.total_time)?;
+        self.stability.validate()?;
+        self.cg.validate()?;
+        self.grid.validate()
+    }
+}
+
+impl RunConfig for Config {
+    fn out_dir(&self) -> &Path {
+        &self.out_dir
+    }
+
+    fn set_out_dir(&mut self, dir: PathBuf) {
+        self.out_dir = dir;
+    }
+
+    fn set_steps(&mut self, steps: usize) {
+        self.total_time = total_time_for(steps, self.dt);
+    }
+}
+
+/// Seconds per gigayear (Julian years).
+const GYR: f64 = 3.15576e16;
+
+// Comoving number densities a^3 n (m^-3 today), so expansion alone leaves
+// them unchanged.
+struct Field3D {
+    photons: Grid3<f64>,
+    photon_bc: Boundaries,
+    axions: Grid3<f64>,
+}
+
+impl Field3D {
+    fn new(grid: &GridConfig, photon_bc: Boundaries, ph_init: f64) -> Self {
+        Field3D {
+            photons: grid.filled(ph_init),
+            photon_bc,
+            axions: grid.filled(0.0),
+        }
+    }
+}
+
+// Axion conversion rate:
+fn axion_rate(cfg: &Config, a: f64, dx: f64) -> f64 {
+    // B(t) = B0*(a0/a)^2 with a0=1 today
+    let b = cfg.b0/(a*a);
+    // On scale L = dx, P ~ (g_{aγ} B L / (ħc))^2 per segment of coherence
+    let p = (cfg.g_agamma*b*dx/(HBAR*C)).powi(2);
+    // rate ~ p*c/L to get transitions per second:
+    p*C/dx
+}
+
+// Photon diffusion coefficient (scaled):
+// n_e ~ depends on reionization, etc., assume n_e negligible today?
+// For demonstration, take n_e ~ small. Realistically, after reionization:
+// n_e ~ 0.5 * n_baryon ~ 0.5 * (current baryon density ~0.22/m^3) ~0.1/m^3 (very rough)
+// This is extremely small, meaning scattering negligible today.
+// We'll set D=0 for modern era, just to show the form:
+fn diffusion_coefficient(_a: f64) -> f64 {
+    // For realistic present universe, photon free path is huge, D large.
+    // On small scale, negligible. Just return 0 here.
+    0.0
+}
+
+/// Evolve the photon and axion fields through the expansion history up to
+/// `total_time`, writing the lattice averages to `results_file` in `out_dir`.
+pub fn run(cfg: &Config) -> Result<(), SimError> {
+    let dt = cfg.dt;
+
+    // The expansion history is integrated once up front; the run only looks
+    // a(t) up, starting from a ~ 1e-3 (z~999).
+    let background = Background::new(&cfg.cosmology)?;
+    let [omega_r, omega_m, omega_k, omega_l] = background.omegas();
+    println!(
+        "Background: Ω_r = {:.3e}, Ω_m = {:.3}, Ω_k = {:.3}, Ω_Λ = {:.3}, age {:.3} Gyr",
+        omega_r,
+        omega_m,
+        omega_k,
+        omega_l,
+        background.age() / GYR
+    );
+    let t_start = background.time(cfg.a_init);
+    println!("Starting at z = {:.1}, t = {:.3e} s", redshift(cfg.a_init), t_start);
+    let mut clock = FrwClock::new(background, t_start, dt);
+    let mut t = 0.0;
+
+    // Photon number density at scale factor a: n_gamma = N_GAMMA_0 / a^3,
+    // i.e. a comoving density of N_GAMMA_0
+    let mut field = Field3D::new(&cfg.grid, cfg.photon_bc, cfg.n_gamma_0);
+
+    // The physical coefficient acts as D/a^2 on the comoving grid, largest at
+    // the start since a only grows.
+    let photons = Diffuser::new("photon", cfg.photon_scheme, diffusion_coefficient(cfg.a_init), dt, &cfg.cg)
+        .with_max_scale(1.0 / (cfg.a_init * cfg.a_init))
+        .checked(field.photons.spacing(), &cfg.stability)?;
+
+    let results_path = cfg.out_dir.join(&cfg.results_file);
+    let mut file = CsvWriter::create(
+        &results_path,
+        &["time(s)", "scale_factor", "redshift", "hubble(s^-1)", "avg_photon(m^-3)", "avg_axion(m^-3)"],
+    )?;
+
+    let mut step = 0;
+    while t < cfg.total_time {
+        step += 1;
+        let frw = clock.advance(step)?;
+        t += dt;
+        let a = frw.a_end;
+
+        // Update fields: the conversion length is one cell, a*dx physically.
+        let ax_rate = axion_rate(cfg, frw.a_mid, frw.physical_length(field.photons.spacing()));
+        let diff_ph = photons.increment_scaled(&field.photons, &field.photon_bc, frw.diffusion_scale())?;
+
+        // Evolve photon and axion fields:
+        let mut new_photons = field.photons.clone();
+        let mut new_axions = field.axions.clone();
+        let (nx, ny, nz) = field.photons.dims();
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    let idx = field.photons.idx(x,y,z);
+                    let n_ph = field.photons[idx];
+                    let n_ax = field.axions[idx];
+
+                    // Diffusion (small scale - likely negligible now)
+                    let dn_ph_diff = diff_ph[idx];
+
+                    // Axion production, linear in the photons, so the same
+                    // on comoving densities. Integrated exactly over the
+                    // step, dn_ax = n_ph * (1 - exp(-axion_rate * DT)), as
+                    // the rate early on is far above 1/DT.
+                    let dn_ax = -n_ph * (-ax_rate * dt).exp_m1();
+
+                    new_photons[idx] = n_ph + dn_ph_diff - dn_ax;
+                    new_axions[idx] = n_ax + dn_ax;
+                }
+            }
+        }
+
+        field.photons = new_photons;
+        field.photon_bc.apply_sponge(&mut field.photons, dt);
+        field.axions = new_axions;
+
+        let avg_ph = frw.physical_density(field.photons.mean());
+        let avg_ax = frw.physical_density(field.axions.mean());
+        file.row(&[t, a, redshift(a), clock.expansion().hubble(a), avg_ph, avg_ax])?;
+        check_finite("photon density", step, &field.photons)?;
+        check_finite("axion density", step, &field.axions)?;
+    }
+    file.finish()?;
+
+    println!("Simulation completed. Results in {}", results_path.display());
+    Ok(())
+}
@@ -1,67 +1,113 @@
-use std::io::Write;
-use lazy_static::lazy_static;
+use physics_core::cli;
+use physics_core::config::{non_negative, positive, total_time_for, GridConfig, RunConfig, Validate};
+use physics_core::error::{check_finite, SimError};
+use physics_core::constants::si::{C, G, H, K_B};
+use physics_core::io::CsvWriter;
+use physics_core::diffusion::{CgSettings, DiffusionScheme, Diffuser};
+use physics_core::stability::Stability;
+use physics_core::{Boundaries, Grid3};
+use serde::{Deserialize, Serialize};
 use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
-const C: f64 = 2.99792458e8;   // m/s
-const H: f64 = 6.62607015e-34; // J·s
-const K_B: f64 = 1.380649e-23; // J/K
-const G: f64 = 6.67430e-11;    // m^3 kg^-1 s^-2
 const TWO: f64 = 2.0;
 
-// Grid parameters
-const NX: usize = 50;
-const NY: usize = 50;
-const NZ: usize = 50;
-const DX: f64 = 0.1;      // spatial step in meters
-const DT: f64 = 1e-3;     // time step in seconds
+// Run parameters; defaults are the original constants. Pass a TOML or JSON
+// file with `--config` to override them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    grid: GridConfig,              // spatial step in meters
+    dt: f64,                       // time step in seconds
+    total_time: f64,               // s
+    lambda_qcd: f64,               // placeholder QCD scale
+    t_cmb: f64,                    // K
+    b_field: f64,                  // Tesla as placeholder
+    mass_bh: f64,                  // kg, roughly solar mass
+    dimensionless_diffusion: f64,
+    photon_bc: Boundaries,
+    photon_scheme: DiffusionScheme,
+    stability: Stability,
+    cg: CgSettings,
+    out_dir: PathBuf,
+    results_file: String,
+}
 
-lazy_static! {
-    static ref LAMBDA_QCD: f64 = TWO.powi(28); // placeholder QCD scale
-    static ref T_CMB: f64 = 2.7255 * 400.0; // K
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            grid: GridConfig::new(50, 50, 50, 0.1),
+            dt: 1e-3,
+            total_time: 0.1 + (1.0 + C.powi(4)).sqrt() * 0.0000000000000000000125,
+            lambda_qcd: TWO.powi(28),
+            t_cmb: 2.7255 * 400.0,
+            b_field: 1.0 * 4.0 * PI * PI * PI * PI * PI * PI * PI * PI * PI * PI * PI * 10e-10,
+            mass_bh: 1.0e30,
+            dimensionless_diffusion: 1e-3,
+            photon_bc: Boundaries::neumann(),
+            photon_scheme: DiffusionScheme::BackwardEuler,
+            stability: Stability::default(),
+            cg: CgSettings::default(),
+            out_dir: PathBuf::from("."),
+            results_file: "3d_sim_output.csv".into(),
+        }
+    }
 }
 
-struct Field3D {
-    photons: Vec<f64>,
-    exotic: Vec<f64>,
+impl Validate for Config {
+    fn validate(&self) -> Result<(), String> {
+        positive("dt", self.dt)?;
+        positive("total_time", self.total_time)?;
+        non_negative("lambda_qcd", self.lambda_qcd)?;
+        positive("t_cmb", self.t_cmb)?;
+        non_negative("b_field", self.b_field)?;
+        positive("mass_bh", self.mass_bh)?;
+        non_negative("dimensionless_diffusion", self.dimensionless_diffusion)?;
+        self.stability.validate()?;
+        self.cg.validate()?;
+        self.grid.validate()
+    }
 }
 
-impl Field3D {
-    fn new(nx: usize, ny: usize, nz: usize, photon_init: f64) -> Self {
-        let size = nx * ny * nz;
-        Field3D {
-            photons: vec![photon_init; size],
-            exotic: vec![0.0; size],
-        }
+impl RunConfig for Config {
+    fn out_dir(&self) -> &Path {
+        &self.out_dir
     }
 
-    fn idx(&self, x: usize, y: usize, z: usize) -> usize {
-        x + NX * (y + NY * z)
+    fn set_out_dir(&mut self, dir: PathBuf) {
+        self.out_dir = dir;
     }
 
-    // Simple finite difference Laplacian in 3D
-    fn laplacian(&self, arr: &Vec<f64>, x: usize, y: usize, z: usize) -> f64 {
-        let idx = self.idx(x,y,z);
-        let val = arr[idx];
+    fn set_steps(&mut self, steps: usize) {
+        self.total_time = total_time_for(steps, self.dt);
+    }
+}
 
-        let xm = if x > 0 { arr[self.idx(x-1,y,z)] } else { val };
-        let xp = if x < NX-1 { arr[self.idx(x+1,y,z)] } else { val };
-        let ym = if y > 0 { arr[self.idx(x,y-1,z)] } else { val };
-        let yp = if y < NY-1 { arr[self.idx(x,y+1,z)] } else { val };
-        let zm = if z > 0 { arr[self.idx(x,y,z-1)] } else { val };
-        let zp = if z < NZ-1 { arr[self.idx(x,y,z+1)] } else { val };
+struct Field3D {
+    photons: Grid3<f64>,
+    photon_bc: Boundaries,
+    exotic: Grid3<f64>,
+}
 
-        (xp + xm + yp + ym + zp + zm - 6.0*val)/(DX*DX)
+impl Field3D {
+    fn new(grid: &GridConfig, photon_bc: Boundaries, photon_init: f64) -> Self {
+        Field3D {
+            photons: grid.filled(photon_init),
+            photon_bc,
+            exotic: grid.filled(0.0),
+        }
     }
 }
 
 // Hypothetical QCD correction
-fn qcd_correction(n_photon: f64) -> f64 {
-    (n_photon.sqrt()) * *LAMBDA_QCD
+fn qcd_correction(lambda_qcd: f64, n_photon: f64) -> f64 {
+    (n_photon.sqrt()) * lambda_qcd
 }
 
 // Hypothetical exotic matter rate
-fn exotic_matter_rate(n_photon: f64) -> f64 {
-    let correction = qcd_correction(n_photon);
+fn exotic_matter_rate(lambda_qcd: f64, n_photon: f64) -> f64 {
+    let correction = qcd_correction(lambda_qcd, n_photon);
     1e-35 * correction
 }
 
@@ -72,11 +118,10 @@ fn magnetic_attenuation_rate(b_field: f64, n_photon: f64) -> f64 {
 }
 
 // Planck distribution approximation for CMB photon number density
-fn planck_number_density(T: f64) -> f64 {
+fn planck_number_density(t: f64) -> f64 {
     let h = H;
     let kb = K_B;
     let c = C;
-    let t = T;
 
     let nu_min = 1.0e7;
     let nu_max = 1.0e15;
@@ -97,8 +142,7 @@ fn planck_number_density(T: f64) -> f64 {
 // Introduce scaling relevant to black hole holography scenarios:
 // Let's consider a black hole mass scale for dimensionless parameters
 // (For a real scenario, choose a mass of interest. Here, just an example.)
-fn characteristic_scales() -> (f64, f64, f64) {
-    let mass_bh = 1.0e30; // kg, roughly solar mass
+fn characteristic_scales(mass_bh: f64) -> (f64, f64, f64) {
     let r_s = 2.0 * G * mass_bh / (C*C); // Schwarzschild radius
     let char_time = r_s / C; // characteristic time scale
     (r_s, char_time, mass_bh)
@@ -110,53 +154,63 @@ fn metric_factor(_x: usize, _y: usize, _z: usize) -> f64 {
     1.0
 }
 
-fn main() {
-    let n_photon_init = planck_number_density(*T_CMB);
+fn main() -> ExitCode {
+    cli::main(env!("CARGO_BIN_NAME"), run)
+}
+
+fn run(cfg: &Config) -> Result<(), SimError> {
+    let dt = cfg.dt;
+
+    let n_photon_init = planck_number_density(cfg.t_cmb);
     println!("Initial CMB photon number density: {} photons/m^3", n_photon_init);
 
     // Initialize fields
-    let mut field = Field3D::new(NX, NY, NZ, n_photon_init);
+    let mut field = Field3D::new(&cfg.grid, cfg.photon_bc, n_photon_init);
 
-    let b_field = 1.0 * 4.0 * PI * PI * PI * PI * PI * PI * PI * PI * PI * PI * PI * 10e-10; // Tesla as placeholder
+    let b_field = cfg.b_field;
 
     // Compute characteristic scales:
-    let (r_s, char_time, _mass_bh) = characteristic_scales();
+    let (r_s, char_time, _mass_bh) = characteristic_scales(cfg.mass_bh);
 
-    // Dimensionless diffusion coefficient:
-    let dimensionless_diffusion = 1e-3;
     // Physical D in m^2/s (scaling with black hole radius and time):
-    let D = dimensionless_diffusion * (r_s * r_s / char_time);
+    let d_coef = cfg.dimensionless_diffusion * (r_s * r_s / char_time);
+    // metric_factor is 1 everywhere, so it does not tighten the limit.
+    let photons = Diffuser::new("photon", cfg.photon_scheme, d_coef, dt, &cfg.cg)
+        .checked(field.photons.spacing(), &cfg.stability)?;
 
     // Open file for output
-    let mut file = std::fs::File::create("3d_sim_output.csv").unwrap();
-    writeln!(file, "time(s), average_n_photon(m^-3), average_n_exotic(m^-3)").unwrap();
+    let results_path = cfg.out_dir.join(&cfg.results_file);
+    let mut file = CsvWriter::create(
+        &results_path,
+        &["time(s)", "average_n_photon(m^-3)", "average_n_exotic(m^-3)"],
+    )?;
 
-    let total_time = 0.1 + (1.0 + C.powi(4)).sqrt() * 0.0000000000000000000125;
-    let steps = (total_time/DT) as usize;
+    let steps = (cfg.total_time/dt) as usize;
 
     for step in 0..steps {
         let mut new_photons = field.photons.clone();
         let mut new_exotic = field.exotic.clone();
+        let diff_ph = photons.increment(&field.photons, &field.photon_bc)?;
 
-        for z in 0..NZ {
-            for y in 0..NY {
-                for x in 0..NX {
-                    let idx = field.idx(x,y,z);
+        let (nx, ny, nz) = field.photons.dims();
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    let idx = field.photons.idx(x,y,z);
                     let n_ph = field.photons[idx];
                     let n_ex = field.exotic[idx];
 
                     let alpha_g = metric_factor(x,y,z);
 
-                    let lap = field.laplacian(&field.photons, x, y, z);
-                    let gamma_B = magnetic_attenuation_rate(b_field, n_ph) * alpha_g;
+                    let gamma_b = magnetic_attenuation_rate(b_field, n_ph) * alpha_g;
 
                     // Photon evolution incorporating geometric factor:
-                    let dn_ph = (D * lap - gamma_B) * alpha_g;
-                    let new_n_ph = n_ph + dn_ph * DT;
+                    let dn_ph = (diff_ph[idx] - gamma_b * dt) * alpha_g;
+                    let new_n_ph = n_ph + dn_ph;
 
                     // Exotic matter formation rate scaled by geometry
-                    let dn_ex = exotic_matter_rate(n_ph) * alpha_g;
-                    let new_n_ex = n_ex + dn_ex * DT;
+                    let dn_ex = exotic_matter_rate(cfg.lambda_qcd, n_ph) * alpha_g;
+                    let new_n_ex = n_ex + dn_ex * dt;
 
                     new_photons[idx] = new_n_ph;
                     new_exotic[idx] = new_n_ex;
@@ -165,15 +219,20 @@ fn main() {
         }
 
         field.photons = new_photons;
+        field.photon_bc.apply_sponge(&mut field.photons, dt);
         field.exotic = new_exotic;
 
         // Compute averages for output:
-        let avg_n_ph = field.photons.iter().sum::<f64>()/(NX*NY*NZ) as f64;
-        let avg_n_ex = field.exotic.iter().sum::<f64>()/(NX*NY*NZ) as f64;
+        let avg_n_ph = field.photons.mean();
+        let avg_n_ex = field.exotic.mean();
 
-        let time = step as f64 * DT;
-        writeln!(file, "{},{},{}", time, avg_n_ph, avg_n_ex).unwrap();
+        let time = step as f64 * dt;
+        file.row(&[time, avg_n_ph, avg_n_ex])?;
+        check_finite("photon density", step, &field.photons)?;
+        check_finite("exotic density", step, &field.exotic)?;
     }
+    file.finish()?;
 
-    println!("3D simulation completed. Results in 3d_sim_output.csv");
+    println!("3D simulation completed. Results in {}", results_path.display());
+    Ok(())
 }
@@ -13,7 +13,7 @@ with open(results_file, 'r') as f:
     reader = csv.reader(f)
     header = next(reader)  # skip the header line
     for row in reader:
-        # According to the code: time(s), avg_energy(J/m^3), avg_photon(m^-3)
+        # According to the code: time(fm/c), avg_energy(GeV/fm^3), avg_photon(fm^-3), ...
         t = float(row[0])
         e = float(row[1])
         ph = float(row[2])
@@ -31,8 +31,8 @@ avg_photon = np.array(avg_photon)
 ########################################
 plt.figure(figsize=(8,5))
 plt.plot(time, avg_energy, color='tab:blue')
-plt.xlabel('Time (s)')
-plt.ylabel('Average Energy Density (J/m^3)')
+plt.xlabel('Time (fm/c)')
+plt.ylabel('Average Energy Density (GeV/fm^3)')
 plt.title('Evolution of Energy Density in the QCD-like Fluid')
 plt.grid(True)
 plt.savefig('energy_evolution.png', dpi=300)
@@ -43,8 +43,8 @@ plt.show()
 ########################################
 plt.figure(figsize=(8,5))
 plt.plot(time, avg_photon, color='tab:green')
-plt.xlabel('Time (s)')
-plt.ylabel('Average Photon Density (m^-3)')
+plt.xlabel('Time (fm/c)')
+plt.ylabel('Average Photon Density (fm^-3)')
 plt.title('Evolution of Photon Density in the Fluid')
 plt.grid(True)
 plt.yscale('log')
@@ -58,8 +58,8 @@ plt.show()
 ratio = avg_photon / avg_energy
 plt.figure(figsize=(8,5))
 plt.plot(time, ratio, color='tab:red')
-plt.xlabel('Time (s)')
-plt.ylabel('Photon Density / Energy Density (m^-3 / J·m^-3)')
+plt.xlabel('Time (fm/c)')
+plt.ylabel('Photon Density / Energy Density (GeV^-1)')
 plt.title('Photon-to-Energy Density Ratio Over Time')
 plt.yscale('log')
 plt.grid(True)
@@ -68,9 +68,9 @@ plt.show()
 
 ########################################
 # Interpretation:
-# This is a toy analogy. The energy density and photon density are evolving under
-# the influence of a metric factor variation (akin to GWs) and QCD-like EoS 
-# diffusion. The curves should show how these fields respond over time.
+# A fireball of extra energy density expands under ideal relativistic hydro
+# with a QCD-like EoS, carrying and diffusing the photons. The averages are over
+# the fluid-frame densities, so they change as the flow speeds up and settles.
 
 # In a real setup, adjusting parameters and initial conditions might reveal 
 # complex behavior, anisotropies, or fluctuations that can be compared to 
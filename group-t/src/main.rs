@@ -1,135 +1,173 @@
-use std::f64::consts::PI;
-use std::io::Write;
-
-// Physical constants
-const C: f64 = 2.99792458e8;
-const G: f64 = 6.67430e-11;
-const HBAR: f64 = 1.054571817e-34;
-const K_B: f64 = 1.380649e-23;
-
-// Parameters for the lattice box (4D plane)
-const NX: usize = 20;
-const NY: usize = 20;
-const NZ: usize = 20;
-const DX: f64 = 1.0;   // m
-const DT: f64 = 0.10;  // s, artificially small for a lab analog
-const TOTAL_TIME: f64 = 0.1 * 1e3; // 10s total simulation time
-
-// Hypothetical EoS parameters for a QCD-like fluid:
-fn qcd_pressure(epsilon: f64) -> f64 {
-    // Simple linear EoS p = c_s^2 * epsilon
-    const qcd_steps: usize = 89;
-    let mut partial: f64 = 1.022;
-    for x in 0..qcd_steps {
-        partial *= (1.0e1/137.0)
+use physics_core::cli;
+use physics_core::config::{non_negative, positive, total_time_for, GridConfig, RunConfig, Validate};
+use physics_core::error::{check_finite, SimError};
+use physics_core::io::CsvWriter;
+use physics_core::diffusion::{CgSettings, DiffusionScheme, Diffuser};
+use physics_core::eos::EosSettings;
+use physics_core::stability::{flux_substeps, Stability};
+use physics_core::{Boundaries, Grid3};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+mod hydro;
+
+use hydro::{Fluid, Primitive, ENERGY, PHOTONS};
+
+// Parameters for the lattice box, in units with c = 1: lengths in fm, times
+// in fm/c, energies in GeV. Pass a TOML or JSON file with `--config` to
+// override them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    grid: GridConfig,     // fm
+    dt: f64,              // fm/c
+    total_time: f64,      // fm/c
+    eos: EosSettings,     // p(ε) of the QCD-like fluid
+    epsilon_init: f64,    // GeV/fm^3, background energy density
+    epsilon_peak: f64,    // GeV/fm^3, excess at the centre of the fireball
+    fireball_width: f64,  // fm, Gaussian width of the excess
+    photon_init: f64,     // photons/fm^3 in the fluid frame
+    d_ph: f64,            // fm, photon diffusion on top of the flow
+    fluid_bc: Boundaries, // periodic, or neumann for outflow
+    photon_bc: Boundaries,
+    photon_scheme: DiffusionScheme,
+    stability: Stability,
+    cg: CgSettings,
+    out_dir: PathBuf,
+    results_file: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            grid: GridConfig::new(20, 20, 20, 1.0),
+            dt: 0.10,
+            total_time: 10.0,
+            eos: EosSettings::default(),
+            epsilon_init: 1.0,
+            epsilon_peak: 10.0,
+            fireball_width: 2.0,
+            photon_init: 1.0,
+            d_ph: 0.1,
+            fluid_bc: Boundaries::periodic(),
+            photon_bc: Boundaries::periodic(),
+            photon_scheme: DiffusionScheme::Explicit,
+            stability: Stability::default(),
+            cg: CgSettings::default(),
+            out_dir: PathBuf::from("."),
+            results_file: "fluid_lattice.csv".into(),
+        }
     }
-    let c_s2 = B_0 * (1.0/3.0 + 0.1/0.3 * (x as f64 / NX as f64).cos());
-    // let c_s2 = 1.0 ; // 1.6436 * partial * 10e89; //1.0 / 3.0; // radiation-like equation of state as a stand-in
-    c_s2 * epsilon
 }
 
-struct FluidField {
-    energy: Vec<f64>,
-    // For simplicity, store a velocity field
-    vx: Vec<f64>,
-    vy: Vec<f64>,
-    vz: Vec<f64>,
-    // Photon-like scalar field
-    photon_density: Vec<f64>,
+impl Validate for Config {
+    fn validate(&self) -> Result<(), String> {
+        positive("dt", self.dt)?;
+        positive("total_time", self.total_time)?;
+        self.eos.validate()?;
+        positive("epsilon_init", self.epsilon_init)?;
+        non_negative("epsilon_peak", self.epsilon_peak)?;
+        positive("fireball_width", self.fireball_width)?;
+        non_negative("photon_init", self.photon_init)?;
+        non_negative("d_ph", self.d_ph)?;
+        self.fluid_bc.validate()?;
+        Fluid::check_boundaries(&self.fluid_bc)?;
+        self.photon_bc.validate()?;
+        self.stability.validate()?;
+        self.cg.validate()?;
+        self.grid.validate()
+    }
 }
 
-impl FluidField {
-    fn new(epsilon_init: f64, photon_init: f64) -> Self {
-        let size = NX*NY*NZ;
-        FluidField {
-            energy: vec![epsilon_init; size],
-            vx: vec![0.0; size],
-            vy: vec![0.0; size],
-            vz: vec![0.0; size],
-            photon_density: vec![photon_init; size],
-        }
+impl RunConfig for Config {
+    fn out_dir(&self) -> &Path {
+        &self.out_dir
     }
-    
-    fn idx(&self, x: usize, y: usize, z: usize) -> usize {
-        x + NX*(y + NY*z)
+
+    fn set_out_dir(&mut self, dir: PathBuf) {
+        self.out_dir = dir;
     }
 
-    fn laplacian(&self, arr: &Vec<f64>, x: usize, y: usize, z: usize) -> f64 {
-        let val = arr[self.idx(x,y,z)];
-        let xp = arr[self.idx((x+1)%NX, y, z)];
-        let xm = arr[self.idx((x+NX-1)%NX, y, z)];
-        let yp = arr[self.idx(x, (y+1)%NY, z)];
-        let ym = arr[self.idx(x, (y+NY-1)%NY, z)];
-        let zp = arr[self.idx(x, y, (z+1)%NZ)];
-        let zm = arr[self.idx(x, y, (z+NZ-1)%NZ)];
-        (xp+xm+yp+ym+zp+zm - 6.0*val)/(DX*DX)
+    fn set_steps(&mut self, steps: usize) {
+        self.total_time = total_time_for(steps, self.dt);
     }
 }
 
-// A simple function simulating a "gravitational wave" metric factor variation
-fn metric_factor(t: f64, x: f64) -> f64 {
-    // A small oscillation in the metric
-    // g_ii = a^2(t)*(1 + h cos(omega t - kx))
-    let h = 1e-4 * (omega_1 * t - k_1 * x).cos() + 1e-5 * (omega_2 * t - k_2 * x).sin();
-    let omega = 2.0 * PI * 100.0; // Higher frequency
-    let k = 2.0*PI/10.0; // wavelength 10 m
-    1.0 + h*(omega*t - k*x).cos()
+/// A fluid at rest with a Gaussian fireball of extra energy density in the
+/// middle of the box.
+fn initial_state(cfg: &Config) -> Grid3<Primitive> {
+    let grid = cfg.grid.filled(0.0);
+    let (nx, ny, nz) = grid.dims();
+    let (first, last) = (grid.position(0, 0, 0), grid.position(nx - 1, ny - 1, nz - 1));
+    let center = [0, 1, 2].map(|a| 0.5 * (first[a] + last[a]));
+    Grid3::from_fn(nx, ny, nz, |x, y, z| {
+        let r = grid.position(x, y, z);
+        let r2: f64 = (0..3).map(|a| (r[a] - center[a]).powi(2)).sum();
+        let epsilon = cfg.epsilon_init + cfg.epsilon_peak * (-r2 / (2.0 * cfg.fireball_width.powi(2))).exp();
+        Primitive { epsilon, v: [0.0; 3], n: cfg.photon_init }
+    })
+    .with_spacing(cfg.grid.dx)
 }
 
-fn main() {
-    let epsilon_init = 1e5; // J/m^3, arbitrary energy density
-    let photon_init = 1e10; // photons/m^3, arbitrary
-    let mut field = FluidField::new(epsilon_init, photon_init);
+fn main() -> ExitCode {
+    cli::main(env!("CARGO_BIN_NAME"), run)
+}
 
-    let mut file = std::fs::File::create("fluid_lattice.csv").unwrap();
-    writeln!(file, "time(s),avg_energy(J/m^3),avg_photon(m^-3)").unwrap();
+fn run(cfg: &Config) -> Result<(), SimError> {
+    let dt = cfg.dt;
+    let eos = cfg.eos.build()?;
 
-    let steps = (TOTAL_TIME/DT) as usize;
+    let mut fluid = Fluid::new(initial_state(cfg), cfg.fluid_bc, eos.as_ref());
+    let dx = cfg.grid.dx;
+    // Characteristic speeds never exceed c = 1.
+    let substeps = flux_substeps("fluid", 1.0, dt, dx, &cfg.stability)?;
+    let photons = Diffuser::new("photon", cfg.photon_scheme, cfg.d_ph, dt, &cfg.cg).checked(dx, &cfg.stability)?;
+
+    let results_path = cfg.out_dir.join(&cfg.results_file);
+    let mut file = CsvWriter::create(
+        &results_path,
+        &["time(fm/c)", "avg_energy(GeV/fm^3)", "avg_photon(fm^-3)", "total_energy(GeV)", "max_velocity(c)"],
+    )?;
+
+    let steps = (cfg.total_time/dt) as usize;
+    let initial = fluid.totals();
 
     for step in 0..steps {
-        let t = step as f64 * DT;
+        let t = step as f64 * dt;
         // Evolve the fluid:
-        let mut new_energy = field.energy.clone();
-        let mut new_photons = field.photon_density.clone();
-
-        for z in 0..NZ {
-            for y in 0..NY {
-                for x in 0..NX {
-                    let idx = field.idx(x,y,z);
-                    let e = field.energy[idx];
-                    let p = qcd_pressure(e);
-
-                    // Simple diffusion-like update for photons:
-                    let lap_ph = field.laplacian(&field.photon_density, x,y,z);
-                    let D_ph = 0.1; // chosen small diffusion coefficient
-                    let n_ph = field.photon_density[idx];
-
-                    // Include metric factor:
-                    let alpha = metric_factor(t, x as f64 * DX);
-
-                    // Update photon density (as if slightly affected by metric change)
-                    // dn_ph/dt ~ D_ph * lap(n_ph)*alpha
-                    let dn_ph = D_ph * lap_ph * DT * alpha;
-
-                    // Energy density might be slightly modulated:
-                    // In reality, you'd solve full fluid eq. Here we do a toy model:
-                    let lap_e = field.laplacian(&field.energy, x,y,z);
-                    let D_e = 0.01;
-                    let de = D_e*lap_e*DT*alpha;
-
-                    new_photons[idx] = n_ph + dn_ph;
-                    new_energy[idx] = e + de - p*1e-3*DT; // a trivial source/sink term to mimic expansion
-                }
-            }
+        for _ in 0..substeps {
+            fluid.step(dt / substeps as f64, eos.as_ref());
         }
 
-        field.energy = new_energy;
-        field.photon_density = new_photons;
-
-        let avg_e = field.energy.iter().sum::<f64>()/(NX*NY*NZ) as f64;
-        let avg_ph = field.photon_density.iter().sum::<f64>()/(NX*NY*NZ) as f64;
-        writeln!(file, "{},{},{}", t, avg_e, avg_ph).unwrap();
+        // Photons diffuse relative to the flow that carries them
+        let mut lab_photons = fluid.conserved.map(|u| u[PHOTONS]);
+        let dn = photons.increment(&lab_photons, &cfg.photon_bc)?;
+        for (n, dn) in lab_photons.iter_mut().zip(dn.iter()) {
+            *n += dn;
+        }
+        cfg.photon_bc.apply_sponge(&mut lab_photons, dt);
+        for (u, n) in fluid.conserved.iter_mut().zip(lab_photons.iter()) {
+            u[PHOTONS] = *n;
+        }
+        fluid.update_primitives(eos.as_ref());
+
+        let energy = fluid.primitive.map(|p| p.epsilon);
+        let photon_density = fluid.primitive.map(|p| p.n);
+        let max_v = fluid.primitive.iter().map(|p| p.speed_squared().sqrt()).fold(0.0, f64::max);
+        let total_energy = fluid.totals()[ENERGY];
+        file.row(&[t, energy.mean(), photon_density.mean(), total_energy, max_v])?;
+        check_finite("energy density", step, &energy)?;
+        check_finite("photon density", step, &photon_density)?;
     }
-
-    println!("Fluid lattice simulation completed. Results in fluid_lattice.csv");
+    file.finish()?;
+
+    let last = fluid.totals();
+    println!(
+        "conservation: total energy drift {:.3e}, photon number drift {:.3e}",
+        (last[ENERGY] - initial[ENERGY]) / initial[ENERGY],
+        (last[PHOTONS] - initial[PHOTONS]) / initial[PHOTONS].max(f64::MIN_POSITIVE)
+    );
+    println!("Fluid lattice simulation completed. Results in {}", results_path.display());
+    Ok(())
 }
@@ -0,0 +1,284 @@
+//! Boundary conditions for the finite-difference stencils.
+//!
+//! A stencil that reaches past the edge of the grid asks the field's
+//! [`Boundaries`] for a ghost value on that face. Every face can carry its
+//! own [`BoundaryCondition`], so e.g. a box can be periodic in x and y and
+//! closed in z.
+//!
+//! In a config file a field's boundaries are either one condition for every
+//! face, e.g. `photon_bc = "neumann"`, or a table naming all six faces:
+//!
+//! ```toml
+//! [photon_bc]
+//! x_min = "periodic"
+//! x_max = "periodic"
+//! y_min = "periodic"
+//! y_max = "periodic"
+//! z_min = { dirichlet = 0.0 }
+//! z_max = { absorbing = { width = 4, rate = 1e3 } }
+//! ```
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::grid::Grid3;
+
+/// What happens on one face of the grid.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoundaryCondition {
+    /// Wrap around to the opposite face. The opposite face must be periodic too.
+    Periodic,
+    /// Zero normal gradient: the ghost cell copies the edge cell.
+    Neumann,
+    /// Fixed value held in the ghost cell.
+    Dirichlet(f64),
+    /// Outflow face with a sponge layer of `width` cells in which the field
+    /// is damped at up to `rate` (1/s), ramping quadratically from zero at
+    /// the inner edge of the layer to `rate` at the face.
+    Absorbing { width: usize, rate: f64 },
+}
+
+/// One of the six faces of a 3D grid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Face {
+    XMin,
+    XMax,
+    YMin,
+    YMax,
+    ZMin,
+    ZMax,
+}
+
+impl Face {
+    pub const ALL: [Face; 6] = [Face::XMin, Face::XMax, Face::YMin, Face::YMax, Face::ZMin, Face::ZMax];
+
+    pub fn opposite(self) -> Face {
+        match self {
+            Face::XMin => Face::XMax,
+            Face::XMax => Face::XMin,
+            Face::YMin => Face::YMax,
+            Face::YMax => Face::YMin,
+            Face::ZMin => Face::ZMax,
+            Face::ZMax => Face::ZMin,
+        }
+    }
+
+    fn slot(self) -> usize {
+        self as usize
+    }
+}
+
+impl fmt::Display for Face {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Face::XMin => "x-min",
+            Face::XMax => "x-max",
+            Face::YMin => "y-min",
+            Face::YMax => "y-max",
+            Face::ZMin => "z-min",
+            Face::ZMax => "z-max",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Boundary conditions for all six faces of one field.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "BoundariesRepr", into = "BoundariesRepr")]
+pub struct Boundaries {
+    faces: [BoundaryCondition; 6],
+}
+
+impl Boundaries {
+    /// The same condition on every face.
+    pub fn uniform(bc: BoundaryCondition) -> Self {
+        Boundaries { faces: [bc; 6] }
+    }
+
+    pub fn periodic() -> Self {
+        Boundaries::uniform(BoundaryCondition::Periodic)
+    }
+
+    pub fn neumann() -> Self {
+        Boundaries::uniform(BoundaryCondition::Neumann)
+    }
+
+    /// Replace the condition on `face`.
+    pub fn with_face(mut self, face: Face, bc: BoundaryCondition) -> Self {
+        self.faces[face.slot()] = bc;
+        self
+    }
+
+    pub fn face(&self, face: Face) -> BoundaryCondition {
+        self.faces[face.slot()]
+    }
+
+    /// The same conditions with every Dirichlet value set to zero, which
+    /// makes the stencils linear in the field.
+    pub fn homogeneous(mut self) -> Self {
+        for bc in self.faces.iter_mut() {
+            if let BoundaryCondition::Dirichlet(_) = bc {
+                *bc = BoundaryCondition::Dirichlet(0.0);
+            }
+        }
+        self
+    }
+
+    /// Check that periodic faces come in opposite pairs and that sponge
+    /// layers are well formed.
+    pub fn validate(&self) -> Result<(), String> {
+        for face in Face::ALL {
+            match self.face(face) {
+                BoundaryCondition::Periodic if self.face(face.opposite()) != BoundaryCondition::Periodic => {
+                    return Err(format!("{} face is periodic but {} face is not", face, face.opposite()));
+                }
+                BoundaryCondition::Absorbing { width, rate } if width == 0 || rate.is_nan() || rate < 0.0 => {
+                    return Err(format!("{} face: absorbing layer needs width > 0 and rate >= 0", face));
+                }
+                BoundaryCondition::Dirichlet(v) if !v.is_finite() => {
+                    return Err(format!("{} face: Dirichlet value must be finite", face));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Value seen by a stencil at cell `(x, y, z)` offset by one step along
+    /// `axis` (0, 1, 2) in direction `dir` (+1 or -1). Inside the grid this
+    /// is the neighbour itself; past the edge it is the face's ghost value.
+    pub fn neighbor(&self, f: &Grid3<f64>, x: usize, y: usize, z: usize, axis: usize, dir: isize) -> f64 {
+        match self.neighbor_cell(f.dims(), x, y, z, axis, dir) {
+            Ok(cell) => f[cell],
+            Err(v) => v,
+        }
+    }
+
+    /// The cell whose value [`Boundaries::neighbor`] reads on a grid with
+    /// `dims`: the neighbour inside the grid, the wrapped cell on a periodic
+    /// face, the cell itself on a closed one. A Dirichlet face has no cell
+    /// and gives its fixed value as the `Err`.
+    pub fn neighbor_cell(
+        &self,
+        dims: (usize, usize, usize),
+        x: usize,
+        y: usize,
+        z: usize,
+        axis: usize,
+        dir: isize,
+    ) -> Result<(usize, usize, usize), f64> {
+        let n = [dims.0, dims.1, dims.2][axis];
+        let i = [x, y, z][axis];
+        let at = |j: usize| match axis {
+            0 => (j, y, z),
+            1 => (x, j, z),
+            _ => (x, y, j),
+        };
+        let face = match (axis, dir > 0) {
+            (0, false) => Face::XMin,
+            (0, true) => Face::XMax,
+            (1, false) => Face::YMin,
+            (1, true) => Face::YMax,
+            (_, false) => Face::ZMin,
+            (_, true) => Face::ZMax,
+        };
+        let inside = if dir > 0 { i + 1 < n } else { i > 0 };
+        if inside {
+            return Ok(at(if dir > 0 { i + 1 } else { i - 1 }));
+        }
+        match self.face(face) {
+            BoundaryCondition::Periodic => Ok(at(if dir > 0 { 0 } else { n - 1 })),
+            BoundaryCondition::Neumann | BoundaryCondition::Absorbing { .. } => Ok(at(i)),
+            BoundaryCondition::Dirichlet(v) => Err(v),
+        }
+    }
+
+    /// Sponge damping rate (1/s) at cell `(x, y, z)` of a grid with `dims`:
+    /// the largest contribution from any absorbing face, zero elsewhere.
+    pub fn damping(&self, dims: (usize, usize, usize), x: usize, y: usize, z: usize) -> f64 {
+        let (nx, ny, nz) = dims;
+        // Distance in cells from each face, in `Face::ALL` order.
+        let depth = [x, nx - 1 - x, y, ny - 1 - y, z, nz - 1 - z];
+        let mut sigma: f64 = 0.0;
+        for face in Face::ALL {
+            if let BoundaryCondition::Absorbing { width, rate } = self.face(face) {
+                let d = depth[face.slot()];
+                if d < width {
+                    let s = (width - d) as f64 / width as f64;
+                    sigma = sigma.max(rate * s * s);
+                }
+            }
+        }
+        sigma
+    }
+
+    /// True if any face carries a sponge layer.
+    pub fn has_sponge(&self) -> bool {
+        self.faces.iter().any(|bc| matches!(bc, BoundaryCondition::Absorbing { .. }))
+    }
+
+    /// Damp `f` inside the sponge layers over a step `dt`. Uses the exact
+    /// decay `exp(-sigma dt)`, so it is stable for any step size.
+    pub fn apply_sponge(&self, f: &mut Grid3<f64>, dt: f64) {
+        if !self.has_sponge() {
+            return;
+        }
+        let dims = f.dims();
+        for i in 0..f.len() {
+            let (x, y, z) = f.coords(i);
+            let sigma = self.damping(dims, x, y, z);
+            if sigma > 0.0 {
+                f[i] *= (-sigma * dt).exp();
+            }
+        }
+    }
+}
+
+impl Default for Boundaries {
+    fn default() -> Self {
+        Boundaries::periodic()
+    }
+}
+
+/// Config-file form of [`Boundaries`].
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum BoundariesRepr {
+    Uniform(BoundaryCondition),
+    Faces {
+        x_min: BoundaryCondition,
+        x_max: BoundaryCondition,
+        y_min: BoundaryCondition,
+        y_max: BoundaryCondition,
+        z_min: BoundaryCondition,
+        z_max: BoundaryCondition,
+    },
+}
+
+impl TryFrom<BoundariesRepr> for Boundaries {
+    type Error = String;
+
+    fn try_from(repr: BoundariesRepr) -> Result<Self, String> {
+        let b = match repr {
+            BoundariesRepr::Uniform(bc) => Boundaries::uniform(bc),
+            BoundariesRepr::Faces { x_min, x_max, y_min, y_max, z_min, z_max } => Boundaries {
+                faces: [x_min, x_max, y_min, y_max, z_min, z_max],
+            },
+        };
+        b.validate()?;
+        Ok(b)
+    }
+}
+
+impl From<Boundaries> for BoundariesRepr {
+    fn from(b: Boundaries) -> Self {
+        let [x_min, x_max, y_min, y_max, z_min, z_max] = b.faces;
+        if b.faces.iter().all(|bc| *bc == x_min) {
+            BoundariesRepr::Uniform(x_min)
+        } else {
+            BoundariesRepr::Faces { x_min, x_max, y_min, y_max, z_min, z_max }
+        }
+    }
+}
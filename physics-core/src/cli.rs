@@ -0,0 +1,103 @@
+//! Command-line handling shared by the standalone binaries and `sim`.
+//!
+//! Every scenario takes the same options (`--config`, `--out-dir`, `--steps`,
+//! `--seed`, `--resume`), reports errors as a single `error: ...` line on stderr and
+//! exits with one of the codes below. Command-line values take precedence
+//! over the config file, which takes precedence over the built-in defaults.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Args, FromArgMatches};
+
+use crate::config::{self, ConfigError, RunConfig, EFFECTIVE_CONFIG};
+use crate::error::SimError;
+
+/// The run started but failed (I/O, numerical blow-up, ...).
+pub const EXIT_FAILURE: u8 = 1;
+/// Bad arguments or configuration; nothing was run. Matches clap's own
+/// exit status for usage errors.
+pub const EXIT_USAGE: u8 = 2;
+
+// Options common to every scenario. (A doc comment here would become the
+// `about` text of every command these are flattened into.)
+#[derive(Args, Clone, Debug, Default)]
+pub struct RunArgs {
+    /// TOML or JSON file overriding the scenario's default parameters
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Directory for the outputs and the echoed effective config
+    #[arg(long, value_name = "DIR")]
+    pub out_dir: Option<PathBuf>,
+
+    /// Number of time steps (sweeps for the lattice)
+    #[arg(long, value_name = "N")]
+    pub steps: Option<usize>,
+
+    /// Seed for the random number generator
+    #[arg(long, value_name = "SEED")]
+    pub seed: Option<u64>,
+
+    /// Checkpoint to continue the run from
+    #[arg(long, value_name = "FILE")]
+    pub resume: Option<PathBuf>,
+}
+
+impl RunArgs {
+    /// Load the config file (or the defaults), apply the command-line
+    /// overrides and validate the result.
+    pub fn resolve<T: RunConfig>(&self) -> Result<T, ConfigError> {
+        let mut cfg = match &self.config {
+            Some(path) => config::load(path)?,
+            None => T::default(),
+        };
+        if let Some(dir) = &self.out_dir {
+            cfg.set_out_dir(dir.clone());
+        }
+        if let Some(steps) = self.steps {
+            cfg.set_steps(steps);
+        }
+        if let Some(seed) = self.seed {
+            cfg.set_seed(seed).map_err(|e| ConfigError::Invalid(format!("--seed: {}", e)))?;
+        }
+        if let Some(path) = &self.resume {
+            cfg.set_resume(path.clone()).map_err(|e| ConfigError::Invalid(format!("--resume: {}", e)))?;
+        }
+        cfg.validate().map_err(ConfigError::Invalid)?;
+        Ok(cfg)
+    }
+}
+
+/// Resolve the config, echo it into the output directory and hand it to
+/// `run`, turning the outcome into an exit code.
+pub fn execute<T: RunConfig>(args: &RunArgs, run: impl FnOnce(&T) -> Result<(), SimError>) -> ExitCode {
+    match args.resolve().map_err(SimError::from).and_then(|cfg: T| {
+        let path = cfg.out_dir().join(EFFECTIVE_CONFIG);
+        config::echo(&cfg, cfg.out_dir()).map_err(SimError::io(path))?;
+        run(&cfg)
+    }) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(exit_code(&e))
+        }
+    }
+}
+
+/// [`EXIT_USAGE`] for errors caught before any stepping (configuration,
+/// refused unstable setups), [`EXIT_FAILURE`] otherwise.
+pub fn exit_code(e: &SimError) -> u8 {
+    match e {
+        SimError::Config(_) | SimError::Unstable { .. } => EXIT_USAGE,
+        _ => EXIT_FAILURE,
+    }
+}
+
+/// `main` for a standalone scenario binary: parse [`RunArgs`] from the
+/// command line and [`execute`] `run`.
+pub fn main<T: RunConfig>(name: &'static str, run: impl FnOnce(&T) -> Result<(), SimError>) -> ExitCode {
+    let command = RunArgs::augment_args(clap::Command::new(name));
+    let args = RunArgs::from_arg_matches(&command.get_matches()).unwrap_or_else(|e| e.exit());
+    execute(&args, run)
+}
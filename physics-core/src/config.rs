@@ -0,0 +1,162 @@
+//! Run configuration files.
+//!
+//! Each binary describes its tunable parameters as a `serde` struct whose
+//! `Default` is the set of constants it used to hard-code. A run reads an
+//! optional TOML or JSON file over those defaults, validates the result and
+//! writes the effective configuration next to its output, so the output
+//! directory alone is enough to reproduce the run.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::grid::Grid3;
+
+/// File name of the echoed configuration inside the output directory.
+/// JSON is used because it prints floats like `1e-7` rather than spelling
+/// out every zero; either format loads back with [`load`].
+pub const EFFECTIVE_CONFIG: &str = "effective_config.json";
+
+/// Parameter checks run after loading, before any simulation work.
+pub trait Validate {
+    /// Return a message naming the first offending parameter.
+    fn validate(&self) -> Result<(), String>;
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, String),
+    UnknownFormat(PathBuf),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "cannot read config {}: {}", path.display(), e),
+            ConfigError::Parse(path, e) => write!(f, "cannot parse config {}: {}", path.display(), e),
+            ConfigError::UnknownFormat(path) => {
+                write!(f, "config {} must end in .toml or .json", path.display())
+            }
+            ConfigError::Invalid(msg) => write!(f, "invalid config: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Parse `path` as TOML or JSON (chosen by extension). Keys missing from the
+/// file keep their defaults when `T` is marked `#[serde(default)]`.
+pub fn load<T: DeserializeOwned>(path: &Path) -> Result<T, ConfigError> {
+    let text = fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_path_buf(), e))?;
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str(&text).map_err(|e| ConfigError::Parse(path.to_path_buf(), e.to_string())),
+        Some("json") => {
+            serde_json::from_str(&text).map_err(|e| ConfigError::Parse(path.to_path_buf(), e.to_string()))
+        }
+        _ => Err(ConfigError::UnknownFormat(path.to_path_buf())),
+    }
+}
+
+/// Create `out_dir` and write `cfg` to [`EFFECTIVE_CONFIG`] inside it.
+pub fn echo<T: Serialize>(cfg: &T, out_dir: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(out_dir)?;
+    let mut text = serde_json::to_string_pretty(cfg)?;
+    text.push('\n');
+    fs::write(out_dir.join(EFFECTIVE_CONFIG), text)
+}
+
+/// A scenario's configuration, with the knobs every run exposes on the
+/// command line (see [`crate::cli::RunArgs`]).
+pub trait RunConfig: DeserializeOwned + Serialize + Default + Validate {
+    /// Directory receiving the outputs and the echoed config.
+    fn out_dir(&self) -> &Path;
+
+    fn set_out_dir(&mut self, dir: PathBuf);
+
+    /// Run for `steps` time steps (sweeps, for Monte Carlo scenarios).
+    fn set_steps(&mut self, steps: usize);
+
+    /// Seed the scenario's random number generator. Deterministic scenarios
+    /// keep this default, which rejects the seed.
+    fn set_seed(&mut self, _seed: u64) -> Result<(), String> {
+        Err("this scenario is deterministic and takes no seed".into())
+    }
+
+    /// Continue from the checkpoint at `path`. Scenarios without
+    /// checkpoints keep this default, which rejects it.
+    fn set_resume(&mut self, _path: PathBuf) -> Result<(), String> {
+        Err("this scenario writes no checkpoints to resume from".into())
+    }
+}
+
+/// The smallest `total_time` for which the usual `(total_time / dt) as usize`
+/// step count comes out as exactly `steps`.
+pub fn total_time_for(steps: usize, dt: f64) -> f64 {
+    let mut total = steps as f64 * dt;
+    while ((total / dt) as usize) < steps {
+        total = total.next_up();
+    }
+    total
+}
+
+/// Lattice shape and cell spacing.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GridConfig {
+    pub nx: usize,
+    pub ny: usize,
+    pub nz: usize,
+    pub dx: f64,
+}
+
+impl GridConfig {
+    pub fn new(nx: usize, ny: usize, nz: usize, dx: f64) -> Self {
+        GridConfig { nx, ny, nz, dx }
+    }
+
+    /// A grid of this shape and spacing with every cell set to `value`.
+    pub fn filled(&self, value: f64) -> Grid3<f64> {
+        Grid3::filled(self.nx, self.ny, self.nz, value).with_spacing(self.dx)
+    }
+}
+
+impl Validate for GridConfig {
+    fn validate(&self) -> Result<(), String> {
+        at_least("grid.nx", self.nx, 1)?;
+        at_least("grid.ny", self.ny, 1)?;
+        at_least("grid.nz", self.nz, 1)?;
+        positive("grid.dx", self.dx)
+    }
+}
+
+/// `Err` naming `name` unless `value` is finite and strictly positive.
+pub fn positive(name: &str, value: f64) -> Result<(), String> {
+    if value.is_finite() && value > 0.0 {
+        Ok(())
+    } else {
+        Err(format!("{} must be a positive finite number, got {}", name, value))
+    }
+}
+
+/// `Err` naming `name` unless `value` is finite and not negative.
+pub fn non_negative(name: &str, value: f64) -> Result<(), String> {
+    if value.is_finite() && value >= 0.0 {
+        Ok(())
+    } else {
+        Err(format!("{} must be a non-negative finite number, got {}", name, value))
+    }
+}
+
+/// `Err` naming `name` unless `value` is at least `min`.
+pub fn at_least(name: &str, value: usize, min: usize) -> Result<(), String> {
+    if value >= min {
+        Ok(())
+    } else {
+        Err(format!("{} must be at least {}, got {}", name, min, value))
+    }
+}
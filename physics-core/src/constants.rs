@@ -0,0 +1,49 @@
+//! Physical constants (CODATA 2018), grouped by unit system.
+//!
+//! Every simulation used to carry its own copy of `C`, `HBAR`, `G`, ... and
+//! the copies disagreed (`C = 3.0e8` in one place, `3.0e10` cm/s in another).
+//! Import from the module that matches the units the scenario works in and
+//! never mix modules inside one formula.
+
+use std::f64::consts::PI;
+
+/// SI units: metres, kilograms, seconds, kelvin, coulombs.
+pub mod si {
+    use super::PI;
+
+    pub const C: f64 = 2.99792458e8; // speed of light (m/s), exact
+    pub const H: f64 = 6.62607015e-34; // Planck constant (J·s), exact
+    pub const HBAR: f64 = H / (2.0 * PI); // reduced Planck constant (J·s)
+    pub const K_B: f64 = 1.380649e-23; // Boltzmann constant (J/K), exact
+    pub const G: f64 = 6.67430e-11; // gravitational constant (m^3 kg^-1 s^-2)
+    pub const E_CHARGE: f64 = 1.602176634e-19; // elementary charge (C), exact
+    pub const M_E: f64 = 9.1093837015e-31; // electron mass (kg)
+    pub const R_E: f64 = 2.8179403262e-15; // classical electron radius (m)
+    pub const SIGMA_T: f64 = 6.6524587321e-29; // Thomson cross section (m^2)
+    pub const ALPHA: f64 = 7.2973525693e-3; // fine structure constant (dimensionless)
+    pub const MU0: f64 = 1.25663706212e-6; // vacuum permeability (N/A^2)
+    pub const EPS0: f64 = 8.8541878128e-12; // vacuum permittivity (F/m)
+    pub const MPC: f64 = 3.085677581491367e22; // megaparsec (m)
+    pub const M_SUN: f64 = 1.98847e30; // solar mass (kg), IAU 2015 nominal GM_sun / G
+}
+
+/// Gaussian CGS units: centimetres, grams, seconds, ergs.
+pub mod cgs {
+    pub const C: f64 = 2.99792458e10; // speed of light (cm/s)
+    pub const R_E: f64 = 2.8179403262e-13; // classical electron radius (cm)
+    pub const SIGMA_T: f64 = 6.6524587321e-25; // Thomson cross section (cm^2)
+}
+
+/// Natural units with ħ = c = k_B = 1; energies, masses and temperatures in GeV.
+pub mod natural {
+    pub const M_PL_REDUCED: f64 = 2.43532e18; // reduced Planck mass (8πG)^(-1/2) (GeV)
+    pub const M_E: f64 = 0.510998950e-3; // electron mass (GeV)
+}
+
+/// Conversion factors between energy units.
+pub mod convert {
+    pub const KEV_TO_MEV: f64 = 1e-3;
+    pub const MEV_TO_ERG: f64 = 1.602176634e-6;
+    pub const MEV_TO_J: f64 = 1.602176634e-13;
+    pub const M_E_C2_MEV: f64 = 0.510998950; // electron rest energy (MeV)
+}
@@ -0,0 +1,456 @@
+//! Homogeneous FRW backgrounds: the expansion history a(t) of a ΛCDM
+//! universe.
+//!
+//! The Friedmann equation with radiation (photons and massless neutrinos),
+//! matter, curvature and a cosmological constant,
+//!
+//! ```text
+//! H²(a) = H₀² (Ω_r a⁻⁴ + Ω_m a⁻³ + Ω_k a⁻² + Ω_Λ),   Ω_Λ = 1 − Ω_r − Ω_m − Ω_k,
+//! ```
+//!
+//! is integrated once, with an adaptive [`DormandPrince`], for the cosmic
+//! time t(a) = ∫ da / (a H) and the conformal time η(a) = ∫ da / (a² H)
+//! from the big bang. The variable of integration is u = √a, in which both
+//! integrands stay finite at a = 0 whether radiation or matter dominates
+//! there. A universe of Λ alone has no big bang: it is de Sitter,
+//! a = e^(H₀ t), with t = 0 today. The results are tabulated about every 0.01 in ln a from 10⁻¹⁰
+//! to 10³ and interpolated by cubic Hermite polynomials on their exact
+//! slopes, so a run can ask for a(t), t(a), H and distances at any time
+//! instead of stepping the scale factor itself. Ω_r follows from the CMB temperature
+//! and N_eff:
+//!
+//! ```text
+//! Ω_γ = (π²/15) (k_B T)⁴ / (ħc)³ / (3 H₀² c² / 8πG),   Ω_r = Ω_γ (1 + (7/8) (4/11)^(4/3) N_eff)
+//! ```
+
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{non_negative, positive, ConfigError, Validate};
+use crate::constants::si::{C, G, HBAR, K_B, MPC};
+use crate::error::SimError;
+use crate::interpolate::CubicHermite;
+use crate::ode::{DormandPrince, OdeSettings};
+
+/// Smallest and largest tabulated scale factor.
+const A_MIN: f64 = 1e-10;
+const A_MAX: f64 = 1e3;
+/// Approximate table spacing in ln a.
+const LN_A_STEP: f64 = 0.01;
+
+/// The `cosmology` table of a scenario config. The defaults are Planck
+/// 2018 with a flat geometry.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CosmologySettings {
+    pub hubble_km_s_mpc: f64, // H₀ (km/s/Mpc)
+    pub omega_m: f64,         // matter today, baryons and cold dark matter
+    pub omega_k: f64,         // curvature today; positive is open
+    pub t_cmb: f64,           // CMB temperature today (K)
+    pub n_eff: f64,           // effective number of massless neutrino species
+    /// Relative tolerance of the background integration.
+    pub rtol: f64,
+}
+
+impl Default for CosmologySettings {
+    fn default() -> Self {
+        CosmologySettings { hubble_km_s_mpc: 67.4, omega_m: 0.315, omega_k: 0.0, t_cmb: 2.7255, n_eff: 3.046, rtol: 1e-10 }
+    }
+}
+
+impl CosmologySettings {
+    /// H₀ (1/s).
+    pub fn h_0(&self) -> f64 {
+        self.hubble_km_s_mpc * 1e3 / MPC
+    }
+
+    /// Photons today.
+    pub fn omega_gamma(&self) -> f64 {
+        let energy_density = PI * PI / 15.0 * (K_B * self.t_cmb).powi(4) / (HBAR * C).powi(3);
+        let critical = 3.0 * self.h_0().powi(2) * C * C / (8.0 * PI * G);
+        energy_density / critical
+    }
+
+    /// Photons and massless neutrinos today.
+    pub fn omega_r(&self) -> f64 {
+        self.omega_gamma() * (1.0 + 7.0 / 8.0 * (4.0f64 / 11.0).powf(4.0 / 3.0) * self.n_eff)
+    }
+
+    /// The cosmological constant, closing the budget.
+    pub fn omega_lambda(&self) -> f64 {
+        1.0 - self.omega_r() - self.omega_m - self.omega_k
+    }
+}
+
+impl Validate for CosmologySettings {
+    fn validate(&self) -> Result<(), String> {
+        positive("cosmology.hubble_km_s_mpc", self.hubble_km_s_mpc)?;
+        non_negative("cosmology.omega_m", self.omega_m)?;
+        if !self.omega_k.is_finite() {
+            return Err(format!("cosmology.omega_k must be finite, got {}", self.omega_k));
+        }
+        non_negative("cosmology.t_cmb", self.t_cmb)?;
+        non_negative("cosmology.n_eff", self.n_eff)?;
+        positive("cosmology.rtol", self.rtol)?;
+        let (r, m, k, l) = (self.omega_r(), self.omega_m, self.omega_k, self.omega_lambda());
+        // a⁴ H² / H₀² must stay positive: no turnaround or bounce in the
+        // tabulated range.
+        let turnaround = (0..=1300).map(|j| A_MIN * 10f64.powf(0.01 * j as f64)).find(|&a| r + a * (m + a * (k + a * a * l)) <= 0.0);
+        if let Some(a) = turnaround {
+            return Err(format!(
+                "cosmology with omega_k = {} and omega_lambda = {:.4} stops expanding near a = {:.3e}; \
+                 only ever-expanding backgrounds are supported",
+                k, l, a
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// a(z) = 1 / (1 + z).
+pub fn scale_factor_at(z: f64) -> f64 {
+    1.0 / (1.0 + z)
+}
+
+/// z(a) = 1/a − 1.
+pub fn redshift(a: f64) -> f64 {
+    1.0 / a - 1.0
+}
+
+/// H(a) for H₀ = `h_0` and Ω_r, Ω_m, Ω_k, Ω_Λ = `omegas`.
+fn hubble_rate(h_0: f64, omegas: [f64; 4], a: f64) -> f64 {
+    let [r, m, k, l] = omegas;
+    h_0 * (r + a * (m + a * (k + a * a * l))).sqrt() / (a * a)
+}
+
+/// −d ln H / d ln a at `a`: the n of a local H ∝ a^(−n), 2 for radiation,
+/// 3/2 for matter, 1 for curvature and 0 for Λ.
+fn hubble_power(omegas: [f64; 4], a: f64) -> f64 {
+    let [r, m, k, l] = omegas;
+    let q = r + a * (m + a * (k + a * a * l));
+    let dq = a * (m + a * (2.0 * k + 4.0 * a * a * l));
+    2.0 - 0.5 * dq / q
+}
+
+/// ∫₀ˣ e^(n x') dx', the growth of t or η over `x` in ln a when
+/// H ∝ a^(−n) or a H ∝ a^(−n).
+fn power_integral(n: f64, x: f64) -> f64 {
+    if n.abs() < 1e-12 {
+        x
+    } else {
+        (n * x).exp_m1() / n
+    }
+}
+
+/// A tabulated expansion history built from [`CosmologySettings`].
+#[derive(Clone, Debug)]
+pub struct Background {
+    h_0: f64,
+    omegas: [f64; 4],
+    /// t and η against ln a.
+    time: CubicHermite,
+    conformal: CubicHermite,
+    /// ln a against t.
+    log_a: CubicHermite,
+}
+
+impl Background {
+    /// Integrate the Friedmann equation for `settings`, which must have
+    /// passed validation.
+    pub fn new(settings: &CosmologySettings) -> Result<Self, SimError> {
+        let h_0 = settings.h_0();
+        let omegas = [settings.omega_r(), settings.omega_m, settings.omega_k, settings.omega_lambda()];
+        let [r, m, k, l] = omegas;
+        let ode = OdeSettings { rtol: settings.rtol, atol: 0.0, initial_step: 0.0, max_steps: 100_000 };
+        let mut integrator = DormandPrince::new("cosmological background", &ode);
+        // d(t, η)/du with u = √a: dt = 2u³ du / (H₀ √q), dη = 2u du / (H₀ √q)
+        // and q = a⁴ H² / H₀².
+        let rhs = |u: f64, _: &[f64], dydu: &mut [f64]| {
+            let a = u * u;
+            let q = r + a * (m + a * (k + a * a * l));
+            if q > 0.0 {
+                dydu[0] = 2.0 * u * a / (h_0 * q.sqrt());
+                dydu[1] = 2.0 * u / (h_0 * q.sqrt());
+            } else {
+                // u = 0 without radiation: the matter-dominated limits.
+                dydu[0] = 0.0;
+                dydu[1] = 2.0 / (h_0 * m.sqrt());
+            }
+        };
+
+        let span = (A_MAX / A_MIN).ln();
+        let nodes = (span / LN_A_STEP).round() as usize + 1;
+        let step = span / (nodes - 1) as f64;
+        let mut ln_a = Vec::with_capacity(nodes);
+        let mut times = Vec::with_capacity(nodes);
+        let mut conformal = Vec::with_capacity(nodes);
+        // Both integrals start at the big bang, a = 0. Without radiation or
+        // matter η diverges there, and without curvature too so does t: the
+        // table then starts at A_MIN on the leading behaviour, a ∝ t with
+        // η = ln a / (H₀ √Ω_k) for an empty open universe, and a = e^(H₀ t)
+        // for de Sitter, whose clock reads 0 today.
+        let de_sitter = r + m <= 0.0 && k <= 0.0;
+        let (mut u, mut y) = if r + m > 0.0 {
+            (0.0, [0.0, 0.0])
+        } else if k > 0.0 {
+            (A_MIN.sqrt(), [A_MIN / (h_0 * k.sqrt()), A_MIN.ln() / (h_0 * k.sqrt())])
+        } else {
+            (A_MIN.sqrt(), [A_MIN.ln() / (h_0 * l.sqrt()), 0.0])
+        };
+        for j in 0..nodes {
+            let x = A_MIN.ln() + j as f64 * step;
+            let next = (0.5 * x).exp();
+            integrator.integrate(rhs, u, next, &mut y, |_| Ok(()))?;
+            u = next;
+            ln_a.push(x);
+            times.push(y[0]);
+            conformal.push(y[1]);
+        }
+        if de_sitter {
+            // η = −1 / (a H₀) converges in the future instead, and spans 13
+            // decades over the table: integrate it backwards from A_MAX, in
+            // v = −u, so that the relative tolerance follows its magnitude.
+            let mut backward = DormandPrince::new("cosmological background", &ode);
+            let rhs = |v: f64, _: &[f64], dydv: &mut [f64]| dydv[0] = -2.0 / (h_0 * l.sqrt() * v * v * v).abs();
+            let mut eta = [-1.0 / (A_MAX * h_0 * l.sqrt())];
+            let mut v = -(0.5 * ln_a[nodes - 1]).exp();
+            for j in (0..nodes).rev() {
+                let next = -(0.5 * ln_a[j]).exp();
+                backward.integrate(rhs, v, next, &mut eta, |_| Ok(()))?;
+                v = next;
+                conformal[j] = eta[0];
+            }
+        }
+
+        let h: Vec<f64> = ln_a.iter().map(|&x| hubble_rate(h_0, omegas, x.exp())).collect();
+        let dt: Vec<f64> = h.iter().map(|h| 1.0 / h).collect();
+        let deta: Vec<f64> = ln_a.iter().zip(&h).map(|(x, h)| 1.0 / (x.exp() * h)).collect();
+        // Only a background that stops expanding could break the ordering,
+        // and validation rules those out.
+        let table = |x: Vec<f64>, y: Vec<f64>, slopes: Vec<f64>| {
+            CubicHermite::new(x, y, slopes)
+                .map_err(|e| SimError::Config(ConfigError::Invalid(format!("cosmological background: {}", e))))
+        };
+        Ok(Background {
+            h_0,
+            omegas,
+            time: table(ln_a.clone(), times.clone(), dt)?,
+            conformal: table(ln_a.clone(), conformal, deta)?,
+            log_a: table(times, ln_a, h)?,
+        })
+    }
+
+    /// H₀ (1/s).
+    pub fn h_0(&self) -> f64 {
+        self.h_0
+    }
+
+    /// Ω_r, Ω_m, Ω_k and Ω_Λ today.
+    pub fn omegas(&self) -> [f64; 4] {
+        self.omegas
+    }
+
+    /// H(a) (1/s).
+    pub fn hubble(&self, a: f64) -> f64 {
+        hubble_rate(self.h_0, self.omegas, a)
+    }
+
+    /// H at redshift `z`.
+    pub fn hubble_at(&self, z: f64) -> f64 {
+        self.hubble(scale_factor_at(z))
+    }
+
+    /// Cosmic time at scale factor `a` (s), since the big bang if there was
+    /// one. Outside the table H ∝ a^(−n) continues with the n of either
+    /// end, a power law a ∝ t^(1/n) or, for n = 0, exponential growth.
+    pub fn time(&self, a: f64) -> f64 {
+        let x = a.ln();
+        let (lo, hi) = self.time.domain();
+        let end = x.clamp(lo, hi);
+        let h = self.hubble(end.exp());
+        self.time.eval(end) + power_integral(hubble_power(self.omegas, end.exp()), x - end) / h
+    }
+
+    /// Cosmic time at redshift `z`.
+    pub fn time_at(&self, z: f64) -> f64 {
+        self.time(scale_factor_at(z))
+    }
+
+    /// The age of the universe today, t(a = 1). A universe of Λ alone has
+    /// no big bang and its clock reads 0 today.
+    pub fn age(&self) -> f64 {
+        self.time(1.0)
+    }
+
+    /// The scale factor at cosmic time `t` (s), the inverse of
+    /// [`Background::time`]; 0 at and before a big bang.
+    pub fn scale_factor(&self, t: f64) -> f64 {
+        let [r, m, k, _] = self.omegas;
+        let big_bang = r + m > 0.0 || k > 0.0;
+        if big_bang && t <= 0.0 {
+            return 0.0;
+        }
+        let (lo, hi) = self.log_a.domain();
+        let end = t.clamp(lo, hi);
+        let ln_a = self.log_a.eval(end);
+        if t == end {
+            return ln_a.exp();
+        }
+        let n = hubble_power(self.omegas, ln_a.exp());
+        let growth = self.hubble(ln_a.exp()) * (t - end);
+        if n.abs() < 1e-12 {
+            (ln_a + growth).exp()
+        } else if 1.0 + n * growth > 0.0 {
+            (ln_a + (n * growth).ln_1p() / n).exp()
+        } else {
+            0.0
+        }
+    }
+
+    /// Conformal time η = ∫ dt / a at scale factor `a` (s), since the big
+    /// bang if η converges there, and extrapolated like
+    /// [`Background::time`].
+    pub fn conformal_time(&self, a: f64) -> f64 {
+        let x = a.ln();
+        let (lo, hi) = self.conformal.domain();
+        let end = x.clamp(lo, hi);
+        let a_h = end.exp() * self.hubble(end.exp());
+        self.conformal.eval(end) + power_integral(hubble_power(self.omegas, end.exp()) - 1.0, x - end) / a_h
+    }
+
+    /// Line-of-sight comoving distance χ = c (η₀ − η) to redshift `z` (m).
+    pub fn comoving_distance(&self, z: f64) -> f64 {
+        C * (self.conformal_time(1.0) - self.conformal_time(scale_factor_at(z)))
+    }
+
+    /// Transverse comoving distance to redshift `z` (m): χ in a flat
+    /// universe, bent by the curvature otherwise.
+    pub fn transverse_comoving_distance(&self, z: f64) -> f64 {
+        let chi = self.comoving_distance(z);
+        let hubble_distance = C / self.h_0;
+        let omega_k = self.omegas[2];
+        let root = omega_k.abs().sqrt();
+        if omega_k > 0.0 {
+            hubble_distance / root * (root * chi / hubble_distance).sinh()
+        } else if omega_k < 0.0 {
+            hubble_distance / root * (root * chi / hubble_distance).sin()
+        } else {
+            chi
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Seconds per gigayear (Julian years).
+    const GYR: f64 = 3.15576e16;
+
+    fn background(settings: CosmologySettings) -> Background {
+        settings.validate().unwrap();
+        Background::new(&settings).unwrap()
+    }
+
+    fn assert_close(what: &str, got: f64, want: f64, tolerance: f64) {
+        assert!((got - want).abs() <= tolerance * want.abs(), "{}: {:e}, expected {:e}", what, got, want);
+    }
+
+    /// Scale factors inside and beyond both ends of the table.
+    const SCALE_FACTORS: [f64; 8] = [1e-12, 1e-8, 1e-4, 0.1, 1.0, 10.0, 500.0, 5e3];
+
+    #[test]
+    fn matter_only_is_einstein_de_sitter() {
+        let bg = background(CosmologySettings { omega_m: 1.0, t_cmb: 0.0, ..Default::default() });
+        let h_0 = bg.h_0();
+        // a = (3 H₀ t / 2)^(2/3), η = 2 √a / H₀.
+        assert_close("age", bg.age(), 2.0 / (3.0 * h_0), 1e-9);
+        for a in SCALE_FACTORS {
+            let t = 2.0 / (3.0 * h_0) * a.powf(1.5);
+            assert_close("t(a)", bg.time(a), t, 1e-9);
+            assert_close("a(t)", bg.scale_factor(t), a, 1e-9);
+            assert_close("η(a)", bg.conformal_time(a), 2.0 * a.sqrt() / h_0, 1e-9);
+            assert_close("H(a)", bg.hubble(a), h_0 * a.powf(-1.5), 1e-12);
+        }
+        assert_eq!(bg.scale_factor(0.0), 0.0);
+    }
+
+    #[test]
+    fn radiation_only_grows_as_the_square_root() {
+        // Photons and neutrinos at the temperature that makes Ω_r = 1.
+        let t_cmb = 2.7255 / CosmologySettings::default().omega_r().powf(0.25);
+        let bg = background(CosmologySettings { omega_m: 0.0, t_cmb, ..Default::default() });
+        let h_0 = bg.h_0();
+        assert_close("Ω_r", bg.omegas()[0], 1.0, 1e-12);
+        // a = (2 H₀ t)^(1/2), η = a / H₀. Rounding leaves an Ω_Λ ~ 1e-16,
+        // which matters only well past a = 10.
+        assert_close("age", bg.age(), 0.5 / h_0, 1e-9);
+        for a in &SCALE_FACTORS[..6] {
+            let t = a * a / (2.0 * h_0);
+            assert_close("t(a)", bg.time(*a), t, 1e-8);
+            assert_close("a(t)", bg.scale_factor(t), *a, 1e-8);
+            assert_close("η(a)", bg.conformal_time(*a), a / h_0, 1e-8);
+        }
+    }
+
+    #[test]
+    fn lambda_only_is_de_sitter() {
+        let bg = background(CosmologySettings { omega_m: 0.0, t_cmb: 0.0, ..Default::default() });
+        let h_0 = bg.h_0();
+        assert_eq!(bg.omegas(), [0.0, 0.0, 0.0, 1.0]);
+        // a = e^(H₀ t) with t = 0 today, η = −1 / (a H₀).
+        assert!(bg.age().abs() <= 1e-9 / h_0, "age {:e}", bg.age());
+        for a in SCALE_FACTORS {
+            let t = a.ln() / h_0;
+            assert!((bg.time(a) - t).abs() <= 1e-9 * (1.0 / h_0 + t.abs()), "t({}) = {:e}, expected {:e}", a, bg.time(a), t);
+            assert_close("a(t)", bg.scale_factor(t), a, 1e-8);
+            assert_close("η(a)", bg.conformal_time(a), -1.0 / (a * h_0), 1e-9);
+            assert_close("H(a)", bg.hubble(a), h_0, 1e-15);
+        }
+        for dt in [0.1, 1.0, 8.0] {
+            let t = bg.time(2.0);
+            assert_close("e-folding", bg.scale_factor(t + dt / h_0), 2.0 * f64::exp(dt), 1e-8);
+        }
+    }
+
+    #[test]
+    fn late_lambda_domination_approaches_de_sitter() {
+        let bg = background(CosmologySettings { omega_m: 1e-9, t_cmb: 0.0, ..Default::default() });
+        let t = bg.time(10.0);
+        for dt in [0.1, 1.0, 8.0] {
+            assert_close("e-folding", bg.scale_factor(t + dt / bg.h_0()), 10.0 * f64::exp(dt), 1e-8);
+        }
+    }
+
+    #[test]
+    fn planck_background() {
+        let bg = background(CosmologySettings::default());
+        assert_close("age (Gyr)", bg.age() / GYR, 13.79, 1e-3);
+        assert_close("a(t(a))", bg.scale_factor(bg.time(1e-3)), 1e-3, 1e-9);
+        // Flat: the transverse distance is the line-of-sight one.
+        assert_eq!(bg.transverse_comoving_distance(2.0), bg.comoving_distance(2.0));
+        // Distance to the last-scattering surface, ~14 Gpc.
+        assert_close("χ(1100) (Mpc)", bg.comoving_distance(1100.0) / MPC, 13_870.0, 5e-3);
+    }
+
+    #[test]
+    fn empty_open_universe_is_milne() {
+        let bg = background(CosmologySettings { omega_m: 0.0, t_cmb: 0.0, omega_k: 1.0, ..Default::default() });
+        let h_0 = bg.h_0();
+        // a = H₀ t.
+        assert_close("age", bg.age(), 1.0 / h_0, 1e-9);
+        for a in SCALE_FACTORS {
+            assert_close("t(a)", bg.time(a), a / h_0, 1e-9);
+            assert_close("a(t)", bg.scale_factor(a / h_0), a, 1e-9);
+        }
+    }
+
+    #[test]
+    fn recollapsing_backgrounds_are_rejected() {
+        let closed = CosmologySettings { omega_k: -1.0, omega_m: 2.5, ..Default::default() };
+        assert!(closed.validate().unwrap_err().contains("stops expanding"));
+        let negative_lambda = CosmologySettings { omega_m: 0.0, t_cmb: 0.0, omega_k: 1.5, ..Default::default() };
+        assert!(negative_lambda.validate().is_err());
+    }
+}
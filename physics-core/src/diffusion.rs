@@ -0,0 +1,417 @@
+//! Time integration of diffusion ∂f/∂t = d ∇²f on the 7-point Laplacian.
+//!
+//! Each diffusing field picks a [`DiffusionScheme`] in its config, e.g.
+//! `photon_scheme = "crank_nicolson"`. The explicit scheme is forward Euler,
+//! substepped as needed to respect [`crate::stability::DIFFUSION_LIMIT`].
+//! The implicit schemes are stable for any step and solve
+//!
+//! ```text
+//! (1 - θ dt d ∇²) f' = (1 + (1 - θ) dt d ∇²) f
+//! ```
+//!
+//! with θ = 1 (backward Euler) or θ = 1/2 (Crank–Nicolson). The system is
+//! symmetric positive definite for every boundary condition, and is solved
+//! matrix-free by conjugate gradient with a Jacobi preconditioner to the
+//! tolerance of the config's [`CgSettings`]. Dirichlet faces make ∇² affine;
+//! their constant part is moved to the right-hand side.
+//!
+//! On a spatial metric δ_ij + h_ij perturbed by a transverse-traceless h
+//! (see [`crate::gw`]), the operator becomes ∂_i((δ_ij − h_ij) ∂_j f) to
+//! first order; [`Diffuser::increment_in_metric`] adds the h term, forward
+//! Euler over the step, to the increment of the flat scheme.
+//!
+//! On the comoving grid of an expanding background (see [`crate::frw`]) the
+//! physical coefficient d acts as d / a²; [`Diffuser::increment_scaled`]
+//! takes that factor step by step.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::boundary::Boundaries;
+use crate::config::{at_least, positive, Validate};
+use crate::error::SimError;
+use crate::grid::Grid3;
+use crate::stability::{diffusion_number, diffusion_substeps, Stability};
+use crate::stencil::{diffusion_increment, laplacian, tensor_laplacian, Tensor};
+
+/// Time integrator for one diffusing field.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffusionScheme {
+    /// Forward Euler, substepped to stay within the stability limit.
+    #[default]
+    Explicit,
+    /// Backward Euler: first order in time, damps every mode.
+    BackwardEuler,
+    /// Crank–Nicolson: second order in time, but lets the shortest
+    /// wavelengths flip sign from step to step when D·dt/dx² is large.
+    CrankNicolson,
+}
+
+impl DiffusionScheme {
+    /// Weight θ of the new time level, `None` for the explicit scheme.
+    fn theta(self) -> Option<f64> {
+        match self {
+            DiffusionScheme::Explicit => None,
+            DiffusionScheme::BackwardEuler => Some(1.0),
+            DiffusionScheme::CrankNicolson => Some(0.5),
+        }
+    }
+}
+
+impl fmt::Display for DiffusionScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DiffusionScheme::Explicit => "explicit",
+            DiffusionScheme::BackwardEuler => "backward Euler",
+            DiffusionScheme::CrankNicolson => "Crank–Nicolson",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The `cg` table of a scenario config: when the conjugate-gradient solves
+/// behind the implicit schemes stop.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CgSettings {
+    /// Converged once the residual norm is below this fraction of the norm
+    /// of the right-hand side.
+    pub rtol: f64,
+    /// Give up with an error after this many iterations.
+    pub max_iter: usize,
+}
+
+impl Default for CgSettings {
+    fn default() -> Self {
+        CgSettings { rtol: 1e-10, max_iter: 500 }
+    }
+}
+
+impl Validate for CgSettings {
+    fn validate(&self) -> Result<(), String> {
+        positive("cg.rtol", self.rtol)?;
+        at_least("cg.max_iter", self.max_iter, 1)
+    }
+}
+
+/// Diffusion of one field with a fixed coefficient, scheme and time step.
+///
+/// ```ignore
+/// let photons = Diffuser::new("photon", cfg.photon_scheme, cfg.d_ph, dt, &cfg.cg)
+///     .checked(dx, &cfg.stability)?;
+/// for step in 0..steps {
+///     let diff_ph = photons.increment(&field.photons, &field.photon_bc)?;
+///     ...
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Diffuser {
+    field: String,
+    scheme: DiffusionScheme,
+    d: f64,
+    dt: f64,
+    max_scale: f64,
+    substeps: usize,
+    cg: CgSettings,
+}
+
+impl Diffuser {
+    /// Diffuse `field` (a name for the log and errors) with coefficient `d`
+    /// over steps of `dt`. Call [`Diffuser::checked`] before stepping.
+    pub fn new(field: &str, scheme: DiffusionScheme, d: f64, dt: f64, cg: &CgSettings) -> Self {
+        Diffuser { field: field.to_string(), scheme, d, dt, max_scale: 1.0, substeps: 1, cg: *cg }
+    }
+
+    /// Account for the effective coefficient reaching up to `max_scale`
+    /// times `d` (e.g. along the stretched axis of a metric perturbation,
+    /// or 1/a² at the smallest scale factor of a comoving grid) when
+    /// checking stability.
+    pub fn with_max_scale(mut self, max_scale: f64) -> Self {
+        self.max_scale = max_scale;
+        self
+    }
+
+    /// Check the setup on cells of size `dx` and log it. The explicit scheme
+    /// takes its substep count from [`diffusion_substeps`]; the implicit
+    /// ones are stable for any step.
+    pub fn checked(mut self, dx: f64, stability: &Stability) -> Result<Self, SimError> {
+        let d = self.d * self.max_scale;
+        if self.scheme == DiffusionScheme::Explicit {
+            self.substeps = diffusion_substeps(&self.field, d, self.dt, dx, stability)?;
+        } else {
+            println!(
+                "stability: {} diffusion D·dt/dx² = {:.3e}, {} (unconditionally stable)",
+                self.field,
+                diffusion_number(d, self.dt, dx),
+                self.scheme
+            );
+        }
+        Ok(self)
+    }
+
+    pub fn scheme(&self) -> DiffusionScheme {
+        self.scheme
+    }
+
+    /// Explicit substeps per time step; 1 for the implicit schemes.
+    pub fn substeps(&self) -> usize {
+        self.substeps
+    }
+
+    /// Change of `f` over one time step.
+    pub fn increment(&self, f: &Grid3<f64>, bc: &Boundaries) -> Result<Grid3<f64>, SimError> {
+        self.increment_scaled(f, bc, 1.0)
+    }
+
+    /// Change of `f` over one time step with the coefficient `scale` · d,
+    /// where `scale` must not exceed the `max_scale` the diffuser was
+    /// checked with.
+    pub fn increment_scaled(&self, f: &Grid3<f64>, bc: &Boundaries, scale: f64) -> Result<Grid3<f64>, SimError> {
+        let d = scale * self.d;
+        match self.scheme.theta() {
+            None => Ok(diffusion_increment(f, bc, d, self.dt, self.substeps)),
+            Some(theta) => self.theta_increment(f, bc, d, theta),
+        }
+    }
+
+    /// Change of `f` over one time step on the spatial metric δ + h, with
+    /// the perturbation `h` given on the cells of `f`.
+    pub fn increment_in_metric(&self, f: &Grid3<f64>, bc: &Boundaries, h: &Grid3<Tensor>) -> Result<Grid3<f64>, SimError> {
+        let mut inc = self.increment(f, bc)?;
+        let scale = self.d * self.dt;
+        for (x, y, z) in f.cells() {
+            inc[(x, y, z)] -= scale * tensor_laplacian(f, h, bc, x, y, z);
+        }
+        Ok(inc)
+    }
+
+    fn theta_increment(&self, f: &Grid3<f64>, bc: &Boundaries, d: f64, theta: f64) -> Result<Grid3<f64>, SimError> {
+        let implicit = theta * self.dt * d;
+        let explicit = (1.0 - theta) * self.dt * d;
+        // ∇²f = L f + b with L linear (the homogeneous boundaries) and
+        // b = ∇²0 the constant contribution of the Dirichlet faces.
+        let linear = bc.homogeneous();
+        let zero = Grid3::filled_like(f, 0.0);
+        let mut rhs = Grid3::filled_like(f, 0.0);
+        for (x, y, z) in f.cells() {
+            let lap = laplacian(f, bc, x, y, z);
+            let offset = laplacian(&zero, bc, x, y, z);
+            rhs[(x, y, z)] = f[(x, y, z)] + explicit * lap + implicit * offset;
+        }
+        let precond = jacobi_diagonal(f, &linear, implicit);
+        let apply = |p: &Grid3<f64>, out: &mut Grid3<f64>| {
+            for (x, y, z) in p.cells() {
+                out[(x, y, z)] = p[(x, y, z)] - implicit * laplacian(p, &linear, x, y, z);
+            }
+        };
+
+        let mut next = f.clone();
+        conjugate_gradient(apply, &rhs, &precond, &mut next, &self.cg).map_err(|(iterations, residual)| {
+            SimError::NoConvergence {
+                what: format!("{} {} diffusion solve", self.field, self.scheme),
+                iterations,
+                residual,
+            }
+        })?;
+        for (v, v0) in next.iter_mut().zip(f.iter()) {
+            *v -= v0;
+        }
+        Ok(next)
+    }
+}
+
+/// Diagonal of `1 - implicit * L` for the linear Laplacian `L` under
+/// `linear`, found by probing each cell with a unit value.
+fn jacobi_diagonal(f: &Grid3<f64>, linear: &Boundaries, implicit: f64) -> Grid3<f64> {
+    let mut probe = Grid3::filled_like(f, 0.0);
+    let mut diag = Grid3::filled_like(f, 0.0);
+    for (x, y, z) in f.cells() {
+        probe[(x, y, z)] = 1.0;
+        diag[(x, y, z)] = 1.0 - implicit * laplacian(&probe, linear, x, y, z);
+        probe[(x, y, z)] = 0.0;
+    }
+    diag
+}
+
+fn dot(a: &Grid3<f64>, b: &Grid3<f64>) -> f64 {
+    a.iter().zip(b.iter()).map(|(u, v)| u * v).sum()
+}
+
+/// Solve `A x = rhs` for symmetric positive definite `A` (given as `apply`)
+/// by Jacobi-preconditioned conjugate gradient, starting from `x`. On
+/// failure returns the iteration count and the relative residual reached.
+fn conjugate_gradient(
+    apply: impl Fn(&Grid3<f64>, &mut Grid3<f64>),
+    rhs: &Grid3<f64>,
+    diag: &Grid3<f64>,
+    x: &mut Grid3<f64>,
+    cg: &CgSettings,
+) -> Result<usize, (usize, f64)> {
+    let rhs_norm = dot(rhs, rhs).sqrt();
+    if rhs_norm == 0.0 {
+        x.iter_mut().for_each(|v| *v = 0.0);
+        return Ok(0);
+    }
+    let mut ap = Grid3::filled_like(x, 0.0);
+    apply(x, &mut ap);
+    let mut r = rhs.clone();
+    for (ri, api) in r.iter_mut().zip(ap.iter()) {
+        *ri -= api;
+    }
+    let precondition = |r: &Grid3<f64>| {
+        let mut z = r.clone();
+        for (zi, di) in z.iter_mut().zip(diag.iter()) {
+            *zi /= di;
+        }
+        z
+    };
+    let mut z = precondition(&r);
+    let mut p = z.clone();
+    let mut rz = dot(&r, &z);
+    let mut residual = dot(&r, &r).sqrt() / rhs_norm;
+    for iteration in 0..cg.max_iter {
+        if residual <= cg.rtol {
+            return Ok(iteration);
+        }
+        apply(&p, &mut ap);
+        let alpha = rz / dot(&p, &ap);
+        for i in 0..x.len() {
+            x[i] += alpha * p[i];
+            r[i] -= alpha * ap[i];
+        }
+        z = precondition(&r);
+        let rz_next = dot(&r, &z);
+        let beta = rz_next / rz;
+        rz = rz_next;
+        for (pi, zi) in p.iter_mut().zip(z.iter()) {
+            *pi = zi + beta * *pi;
+        }
+        residual = dot(&r, &r).sqrt() / rhs_norm;
+    }
+    if residual <= cg.rtol {
+        Ok(cg.max_iter)
+    } else {
+        Err((cg.max_iter, residual))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::boundary::{BoundaryCondition, Face};
+    use std::f64::consts::PI;
+
+    const IMPLICIT: [DiffusionScheme; 2] = [DiffusionScheme::BackwardEuler, DiffusionScheme::CrankNicolson];
+
+    /// A lumpy, non-symmetric field on a small box.
+    fn lumpy(nx: usize, ny: usize, nz: usize) -> Grid3<f64> {
+        Grid3::from_fn(nx, ny, nz, |x, y, z| 1.0 + ((x * 7 + y * 3 + z * 5) % 11) as f64 + (x * y) as f64 * 0.25)
+    }
+
+    #[test]
+    fn implicit_schemes_conserve_the_total() {
+        let f = lumpy(8, 6, 5);
+        let cg = CgSettings { rtol: 1e-13, max_iter: 1000 };
+        for bc in [Boundaries::periodic(), Boundaries::neumann()] {
+            for scheme in IMPLICIT {
+                // D·dt/dx² = 5, far beyond the explicit limit.
+                let diffuser = Diffuser::new("test", scheme, 5.0, 1.0, &cg);
+                let inc = diffuser.increment(&f, &bc).unwrap();
+                let drift = inc.sum() / f.sum();
+                assert!(drift.abs() < 1e-11, "{} under {:?}: relative drift {:e}", scheme, bc, drift);
+                assert!(inc.iter().any(|v| v.abs() > 1e-3), "{} did not diffuse", scheme);
+            }
+        }
+    }
+
+    #[test]
+    fn fourier_mode_decays_at_the_amplification_factor() {
+        let (n, k, dx, d, dt) = (16, 3, 0.5, 2.0, 0.1);
+        let f = Grid3::from_fn(n, 4, 4, |x, _, _| (2.0 * PI * (k * x) as f64 / n as f64).cos()).with_spacing(dx);
+        let lambda = -4.0 / (dx * dx) * (PI * k as f64 / n as f64).sin().powi(2);
+        let cg = CgSettings { rtol: 1e-14, max_iter: 1000 };
+        for scheme in IMPLICIT {
+            let theta = scheme.theta().unwrap();
+            let gain = (1.0 + (1.0 - theta) * dt * d * lambda) / (1.0 - theta * dt * d * lambda);
+            let inc = Diffuser::new("test", scheme, d, dt, &cg).increment(&f, &Boundaries::periodic()).unwrap();
+            for (v, v0) in inc.iter().zip(f.iter()) {
+                assert!((v0 + v - gain * v0).abs() < 1e-11, "{}: {} vs {}", scheme, v0 + v, gain * v0);
+            }
+        }
+    }
+
+    #[test]
+    fn conjugate_gradient_meets_its_tolerance() {
+        let f = lumpy(6, 6, 6);
+        let bc = Boundaries::neumann();
+        let implicit = 3.0;
+        let diag = jacobi_diagonal(&f, &bc, implicit);
+        let apply = |p: &Grid3<f64>, out: &mut Grid3<f64>| {
+            for (x, y, z) in p.cells() {
+                out[(x, y, z)] = p[(x, y, z)] - implicit * laplacian(p, &bc, x, y, z);
+            }
+        };
+        for rtol in [1e-6, 1e-10] {
+            let cg = CgSettings { rtol, max_iter: 500 };
+            let mut x = Grid3::filled_like(&f, 0.0);
+            conjugate_gradient(apply, &f, &diag, &mut x, &cg).unwrap();
+            let mut ax = Grid3::filled_like(&f, 0.0);
+            apply(&x, &mut ax);
+            let residual: f64 = ax.iter().zip(f.iter()).map(|(a, b)| (a - b).powi(2)).sum::<f64>().sqrt();
+            assert!(residual <= rtol * dot(&f, &f).sqrt(), "residual {:e} above rtol {:e}", residual, rtol);
+        }
+
+        let starved = CgSettings { rtol: 1e-14, max_iter: 1 };
+        let mut x = Grid3::filled_like(&f, 0.0);
+        let (iterations, residual) = conjugate_gradient(apply, &f, &diag, &mut x, &starved).unwrap_err();
+        assert_eq!(iterations, 1);
+        assert!(residual > 1e-14);
+    }
+
+    /// `f` diffused with `d = 1` to `t_end` by steps of `dt`.
+    fn evolve(f: &Grid3<f64>, scheme: DiffusionScheme, dt: f64, t_end: f64, bc: &Boundaries) -> Grid3<f64> {
+        let cg = CgSettings { rtol: 1e-13, max_iter: 1000 };
+        let diffuser = Diffuser::new("test", scheme, 1.0, dt, &cg).checked(f.spacing(), &Stability::default()).unwrap();
+        let mut f = f.clone();
+        for _ in 0..(t_end / dt).round() as usize {
+            let inc = diffuser.increment(&f, bc).unwrap();
+            for (v, dv) in f.iter_mut().zip(inc.iter()) {
+                *v += dv;
+            }
+        }
+        f
+    }
+
+    #[test]
+    fn implicit_schemes_converge_to_the_explicit_solution_at_their_order() {
+        let n = 8;
+        let c = (n as f64 - 1.0) / 2.0;
+        let bump = Grid3::from_fn(n, n, n, |x, y, z| {
+            let r2 = (x as f64 - c).powi(2) + (y as f64 - c).powi(2) + (z as f64 - c).powi(2);
+            1.0 + (-r2 / 4.0).exp()
+        });
+        let t_end = 2.0;
+        let cases = [
+            Boundaries::periodic(),
+            Boundaries::neumann(),
+            Boundaries::periodic()
+                .with_face(Face::ZMin, BoundaryCondition::Dirichlet(1.0))
+                .with_face(Face::ZMax, BoundaryCondition::Dirichlet(0.5)),
+        ];
+        for bc in cases {
+            // Forward Euler far below its stability limit stands in for the
+            // exact solution.
+            let reference = evolve(&bump, DiffusionScheme::Explicit, 1e-3, t_end, &bc);
+            for (scheme, order) in IMPLICIT.into_iter().zip([1.0, 2.0]) {
+                let errors = [0.5, 0.25].map(|dt| {
+                    let f = evolve(&bump, scheme, dt, t_end, &bc);
+                    f.iter().zip(reference.iter()).map(|(u, v)| (u - v).abs()).fold(0.0, f64::max)
+                });
+                let observed = (errors[0] / errors[1]).log2();
+                assert!(observed > 0.9 * order, "{} under {:?}: errors {:?}, order {:.2}", scheme, bc, errors, observed);
+            }
+        }
+    }
+}
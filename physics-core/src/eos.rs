@@ -0,0 +1,361 @@
+//! Equations of state p(ε) for the fluid scenarios.
+//!
+//! A solver holds a `Box<dyn EquationOfState>` built from the config's
+//! [`EosSettings`], so a new form is one more implementation and one more
+//! `kind` in the `eos` table:
+//!
+//! ```toml
+//! [eos]
+//! kind = "linear"
+//! c_s2 = 0.333
+//! ```
+//!
+//! Besides the closed forms the scenarios used to hard-code, an EoS can be
+//! tabulated, lattice-QCD style, in a CSV file with columns `T`, `epsilon`,
+//! `p`, `s` and `c_s2`:
+//!
+//! ```toml
+//! [eos]
+//! kind = "table"
+//! file = "qcd_eos.csv"
+//! ```
+//!
+//! The table is read in the scenario's own units and checked for
+//! thermodynamic consistency, ε + p = T s row by row, before any run.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{positive, Validate};
+use crate::error::SimError;
+use crate::interpolate::MonotoneCubic;
+use crate::io::read_csv;
+
+/// Pressure and speed of sound as functions of the energy density.
+pub trait EquationOfState: Send + Sync {
+    fn pressure(&self, epsilon: f64) -> f64;
+
+    /// c_s² = dp/dε, in units of c².
+    fn sound_speed_squared(&self, epsilon: f64) -> f64;
+}
+
+/// p = c_s² ε with constant c_s²; 1/3 is an ideal gas of massless
+/// particles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Linear {
+    pub c_s2: f64,
+}
+
+impl EquationOfState for Linear {
+    fn pressure(&self, epsilon: f64) -> f64 {
+        self.c_s2 * epsilon
+    }
+
+    fn sound_speed_squared(&self, _epsilon: f64) -> f64 {
+        self.c_s2
+    }
+}
+
+/// p = a ε^k above `epsilon_crit` and a ε below it, the stiffening plasma
+/// of `collider`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PowerLaw {
+    pub coefficient: f64,
+    pub exponent: f64,
+    pub epsilon_crit: f64,
+}
+
+impl EquationOfState for PowerLaw {
+    fn pressure(&self, epsilon: f64) -> f64 {
+        if epsilon > self.epsilon_crit {
+            self.coefficient * epsilon.powf(self.exponent)
+        } else {
+            self.coefficient * epsilon
+        }
+    }
+
+    fn sound_speed_squared(&self, epsilon: f64) -> f64 {
+        if epsilon > self.epsilon_crit {
+            self.coefficient * self.exponent * epsilon.powf(self.exponent - 1.0)
+        } else {
+            self.coefficient
+        }
+    }
+}
+
+/// A crossover between a hadron gas, p = c_h ε, and a quark-gluon plasma,
+/// p = c_q ε, weighted by the plasma fraction
+/// w = ½ (1 + tanh((ε − ε_c) / Δ)).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Crossover {
+    pub epsilon_crit: f64,
+    pub delta: f64,
+    pub c_s2_qgp: f64,
+    pub c_s2_hadron: f64,
+}
+
+impl Crossover {
+    /// The plasma fraction w(ε).
+    pub fn qgp_fraction(&self, epsilon: f64) -> f64 {
+        0.5 * (1.0 + ((epsilon - self.epsilon_crit) / self.delta).tanh())
+    }
+}
+
+impl EquationOfState for Crossover {
+    fn pressure(&self, epsilon: f64) -> f64 {
+        let w = self.qgp_fraction(epsilon);
+        (w * self.c_s2_qgp + (1.0 - w) * self.c_s2_hadron) * epsilon
+    }
+
+    fn sound_speed_squared(&self, epsilon: f64) -> f64 {
+        let w = self.qgp_fraction(epsilon);
+        let sech = 1.0 / ((epsilon - self.epsilon_crit) / self.delta).cosh();
+        let dw = 0.5 * sech * sech / self.delta;
+        w * self.c_s2_qgp + (1.0 - w) * self.c_s2_hadron + epsilon * (self.c_s2_qgp - self.c_s2_hadron) * dw
+    }
+}
+
+/// An EoS interpolated from a table of (T, ε, p, s, c_s²) by monotone
+/// cubics in ε. Beyond the table the pressure continues linearly with the
+/// end c_s², never below zero.
+#[derive(Clone, Debug)]
+pub struct Tabulated {
+    temperature: MonotoneCubic,
+    pressure: MonotoneCubic,
+    sound: MonotoneCubic,
+}
+
+impl Tabulated {
+    /// Read the table at `path`, checking every row for ε + p = T s to
+    /// within `tolerance` · (ε + p), for 0 ≤ c_s² ≤ 1 and for increasing ε.
+    pub fn load(path: &Path, tolerance: f64) -> Result<Self, SimError> {
+        let table = read_csv(path)?;
+        let invalid = |msg: String| SimError::Io(path.to_path_buf(), io::Error::new(io::ErrorKind::InvalidData, msg));
+        let column = |names: &[&str]| {
+            table.column(names).ok_or_else(|| invalid(format!("no {} column", names.join(" or "))))
+        };
+        let t = column(&["T"])?;
+        let epsilon = column(&["epsilon", "e"])?;
+        let p = column(&["p"])?;
+        let s = column(&["s"])?;
+        let c_s2 = column(&["c_s2", "cs2"])?;
+
+        for (row, ((((&t, &epsilon), &p), &s), &c_s2)) in t.iter().zip(&epsilon).zip(&p).zip(&s).zip(&c_s2).enumerate() {
+            let enthalpy = epsilon + p;
+            let consistent = (enthalpy - t * s).abs() <= tolerance * enthalpy.abs();
+            if !consistent {
+                return Err(invalid(format!(
+                    "row {} (T = {}) is thermodynamically inconsistent: ε + p = {:.6e} but T s = {:.6e}",
+                    row + 1,
+                    t,
+                    enthalpy,
+                    t * s
+                )));
+            }
+            if !(0.0..=1.0).contains(&c_s2) {
+                return Err(invalid(format!("row {} (T = {}) has c_s2 = {} outside [0, 1]", row + 1, t, c_s2)));
+            }
+        }
+
+        let interpolant = |y: Vec<f64>| MonotoneCubic::new(epsilon.clone(), y).map_err(|e| invalid(format!("epsilon: {}", e)));
+        Ok(Tabulated { temperature: interpolant(t)?, pressure: interpolant(p)?, sound: interpolant(c_s2)? })
+    }
+
+    /// T(ε), clamped to the table.
+    pub fn temperature(&self, epsilon: f64) -> f64 {
+        self.temperature.eval(epsilon)
+    }
+}
+
+impl EquationOfState for Tabulated {
+    fn pressure(&self, epsilon: f64) -> f64 {
+        let (lo, hi) = self.pressure.domain();
+        let end = epsilon.clamp(lo, hi);
+        (self.pressure.eval(end) + self.sound.eval(end) * (epsilon - end)).max(0.0)
+    }
+
+    fn sound_speed_squared(&self, epsilon: f64) -> f64 {
+        self.sound.eval(epsilon)
+    }
+}
+
+fn default_tolerance() -> f64 {
+    1e-3
+}
+
+/// The `eos` table of a scenario config.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum EosSettings {
+    Linear {
+        c_s2: f64,
+    },
+    PowerLaw {
+        coefficient: f64,
+        exponent: f64,
+        epsilon_crit: f64,
+    },
+    Crossover {
+        epsilon_crit: f64,
+        delta: f64,
+        c_s2_qgp: f64,
+        c_s2_hadron: f64,
+    },
+    Table {
+        file: PathBuf,
+        /// Allowed |ε + p − T s| / (ε + p) in any row.
+        #[serde(default = "default_tolerance")]
+        tolerance: f64,
+    },
+}
+
+impl Default for EosSettings {
+    fn default() -> Self {
+        EosSettings::Linear { c_s2: 1.0 / 3.0 }
+    }
+}
+
+/// `Err` naming `name` unless `value` is a causal squared sound speed.
+fn sound_speed(name: &str, value: f64) -> Result<(), String> {
+    if value > 0.0 && value <= 1.0 {
+        Ok(())
+    } else {
+        Err(format!("{} must be in (0, 1], got {}", name, value))
+    }
+}
+
+impl Validate for EosSettings {
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            EosSettings::Linear { c_s2 } => sound_speed("eos.c_s2", *c_s2),
+            EosSettings::PowerLaw { coefficient, exponent, epsilon_crit } => {
+                positive("eos.coefficient", *coefficient)?;
+                positive("eos.exponent", *exponent)?;
+                positive("eos.epsilon_crit", *epsilon_crit)
+            }
+            EosSettings::Crossover { epsilon_crit, delta, c_s2_qgp, c_s2_hadron } => {
+                positive("eos.epsilon_crit", *epsilon_crit)?;
+                positive("eos.delta", *delta)?;
+                sound_speed("eos.c_s2_qgp", *c_s2_qgp)?;
+                sound_speed("eos.c_s2_hadron", *c_s2_hadron)
+            }
+            EosSettings::Table { file, tolerance } => {
+                if file.as_os_str().is_empty() {
+                    return Err("eos.file must name a CSV table".into());
+                }
+                positive("eos.tolerance", *tolerance)
+            }
+        }
+    }
+}
+
+impl EosSettings {
+    /// The equation of state, reading and checking its table if it has one.
+    pub fn build(&self) -> Result<Box<dyn EquationOfState>, SimError> {
+        Ok(match self {
+            EosSettings::Linear { c_s2 } => Box::new(Linear { c_s2: *c_s2 }),
+            EosSettings::PowerLaw { coefficient, exponent, epsilon_crit } => {
+                Box::new(PowerLaw { coefficient: *coefficient, exponent: *exponent, epsilon_crit: *epsilon_crit })
+            }
+            EosSettings::Crossover { epsilon_crit, delta, c_s2_qgp, c_s2_hadron } => Box::new(Crossover {
+                epsilon_crit: *epsilon_crit,
+                delta: *delta,
+                c_s2_qgp: *c_s2_qgp,
+                c_s2_hadron: *c_s2_hadron,
+            }),
+            EosSettings::Table { file, tolerance } => Box::new(Tabulated::load(file, *tolerance)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const CROSSOVER: Crossover = Crossover { epsilon_crit: 1.0, delta: 0.2, c_s2_qgp: 1.0 / 3.0, c_s2_hadron: 0.15 };
+
+    /// Rows (T, ε, p, s, c_s²) of `eos` on ε ∈ [0.1, 3], with T from
+    /// d ln T = dp / (ε + p) and s = (ε + p) / T, so ε + p = T s holds.
+    fn rows(eos: &dyn EquationOfState, count: usize) -> Vec<[f64; 5]> {
+        let (lo, hi, fine) = (0.1, 3.0, 100);
+        let h = (hi - lo) / ((count - 1) * fine) as f64;
+        let integrand = |e: f64| eos.sound_speed_squared(e) / (e + eos.pressure(e));
+        let mut ln_t = 0.0;
+        let mut rows = Vec::with_capacity(count);
+        for k in 0..(count - 1) * fine + 1 {
+            let e = lo + k as f64 * h;
+            if k > 0 {
+                // Simpson's rule over [e − h, e].
+                ln_t += h / 6.0 * (integrand(e - h) + 4.0 * integrand(e - 0.5 * h) + integrand(e));
+            }
+            if k % fine == 0 {
+                let (t, p) = (ln_t.exp(), eos.pressure(e));
+                rows.push([t, e, p, (e + p) / t, eos.sound_speed_squared(e)]);
+            }
+        }
+        rows
+    }
+
+    fn write_table(name: &str, rows: &[[f64; 5]]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("physics-core-eos-{}-{}.csv", std::process::id(), name));
+        let mut text = String::from("# generated by the eos tests\nT,epsilon,p,s,c_s2\n");
+        for row in rows {
+            let cells: Vec<String> = row.iter().map(|v| format!("{:e}", v)).collect();
+            text.push_str(&cells.join(","));
+            text.push('\n');
+        }
+        fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn tabulated_agrees_with_the_closed_form() {
+        let path = write_table("crossover", &rows(&CROSSOVER, 201));
+        let table = Tabulated::load(&path, 1e-3).unwrap();
+        fs::remove_file(&path).unwrap();
+        for k in 0..290 {
+            // Between the nodes, which sit every 0.0145 in ε.
+            let e = 0.105 + k as f64 * 0.01;
+            let p = CROSSOVER.pressure(e);
+            let c_s2 = CROSSOVER.sound_speed_squared(e);
+            assert!((table.pressure(e) - p).abs() <= 1e-3 * p, "p({}) = {} vs {}", e, table.pressure(e), p);
+            assert!((table.sound_speed_squared(e) - c_s2).abs() <= 1e-3 * c_s2, "c_s2({}) = {} vs {}", e, table.sound_speed_squared(e), c_s2);
+        }
+    }
+
+    #[test]
+    fn tabulated_ideal_gas_is_linear() {
+        let ideal = Linear { c_s2: 1.0 / 3.0 };
+        let path = write_table("ideal", &rows(&ideal, 101));
+        let table = Tabulated::load(&path, 1e-3).unwrap();
+        fs::remove_file(&path).unwrap();
+        for e in [0.2, 0.77, 1.5, 2.9] {
+            assert!((table.pressure(e) - e / 3.0).abs() <= 1e-12);
+            // ε = 3 T⁴ up to the normalization of T at the first row.
+            let t0 = (0.1f64 / 3.0).powf(0.25);
+            assert!((table.temperature(e) - (e / 3.0).powf(0.25) / t0).abs() <= 1e-3 * table.temperature(e));
+        }
+        // Continued linearly past the last row.
+        assert!((table.pressure(6.0) - 2.0).abs() <= 1e-12);
+    }
+
+    #[test]
+    fn inconsistent_tables_are_rejected() {
+        let mut inconsistent = rows(&CROSSOVER, 21);
+        inconsistent[7][3] *= 1.01;
+        let path = write_table("inconsistent", &inconsistent);
+        let err = Tabulated::load(&path, 1e-3).unwrap_err().to_string();
+        fs::remove_file(&path).unwrap();
+        assert!(err.contains("row 8") && err.contains("inconsistent"), "{}", err);
+
+        let mut acausal = rows(&CROSSOVER, 21);
+        acausal[3][4] = 1.2;
+        let path = write_table("acausal", &acausal);
+        let err = Tabulated::load(&path, 1e-3).unwrap_err().to_string();
+        fs::remove_file(&path).unwrap();
+        assert!(err.contains("row 4") && err.contains("c_s2"), "{}", err);
+    }
+}
@@ -0,0 +1,118 @@
+//! Errors a simulation run can end with.
+//!
+//! Scenario `run` functions return `Result<(), SimError>` and propagate with
+//! `?`. Output writers flush on drop, so rows written before the error are
+//! left on disk.
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config::ConfigError;
+use crate::grid::Grid3;
+
+#[derive(Debug)]
+pub enum SimError {
+    /// Reading or writing the file at the path failed.
+    Io(PathBuf, io::Error),
+    Config(ConfigError),
+    /// Data whose size does not match the grid it is meant to fill.
+    Shape(String),
+    /// An explicit update whose stability number exceeds its limit, with a
+    /// hint on how to fix the setup.
+    Unstable { what: String, number: f64, limit: f64, hint: String },
+    /// An iterative solver stopped at its iteration cap short of its
+    /// tolerance.
+    NoConvergence { what: String, iterations: usize, residual: f64 },
+    /// An adaptive integrator could not meet its tolerances: its step
+    /// shrank below what the time resolves, or it ran out of attempts.
+    Stalled { what: String, t: f64, step: f64, attempts: usize },
+    /// A conserved total drifted beyond the configured tolerance.
+    NotConserved { quantity: String, step: usize, drift: f64, tolerance: f64 },
+    /// A quantity became NaN or infinite.
+    NonFinite {
+        quantity: String,
+        step: usize,
+        cell: Option<(usize, usize, usize)>,
+        value: f64,
+    },
+}
+
+impl SimError {
+    /// Adapter for `map_err` attaching `path` to an I/O error.
+    pub fn io(path: impl AsRef<Path>) -> impl FnOnce(io::Error) -> SimError {
+        let path = path.as_ref().to_path_buf();
+        move |e| SimError::Io(path, e)
+    }
+}
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            SimError::Config(e) => e.fmt(f),
+            SimError::Shape(msg) => write!(f, "shape mismatch: {}", msg),
+            SimError::Unstable { what, number, limit, hint } => {
+                write!(f, "{} is unstable: stability number {:.3e} exceeds {:.3e}; {}", what, number, limit, hint)
+            }
+            SimError::NoConvergence { what, iterations, residual } => {
+                write!(f, "{} did not converge: relative residual {:.3e} after {} iterations", what, residual, iterations)
+            }
+            SimError::Stalled { what, t, step, attempts } => write!(
+                f,
+                "{} stalled at t = {:.6e} with step {:.3e} after {} attempts; loosen ode.rtol and ode.atol or raise ode.max_steps",
+                what, t, step, attempts
+            ),
+            SimError::NotConserved { quantity, step, drift, tolerance } => write!(
+                f,
+                "{} is not conserved: relative drift {:.3e} exceeds conservation.tolerance = {:.3e} in step {}",
+                quantity, drift, tolerance, step
+            ),
+            SimError::NonFinite { quantity, step, cell, value } => {
+                write!(f, "numerical blow-up: {} became {}", quantity, value)?;
+                if let Some((x, y, z)) = cell {
+                    write!(f, " at cell ({}, {}, {})", x, y, z)?;
+                }
+                write!(f, " in step {}", step)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SimError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SimError::Io(_, e) => Some(e),
+            SimError::Config(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ConfigError> for SimError {
+    fn from(e: ConfigError) -> Self {
+        SimError::Config(e)
+    }
+}
+
+/// `Err` naming the first NaN or infinite cell of `grid`.
+pub fn check_finite(quantity: &str, step: usize, grid: &Grid3<f64>) -> Result<(), SimError> {
+    match grid.iter().position(|v| !v.is_finite()) {
+        None => Ok(()),
+        Some(i) => Err(SimError::NonFinite {
+            quantity: quantity.to_string(),
+            step,
+            cell: Some(grid.coords(i)),
+            value: grid[i],
+        }),
+    }
+}
+
+/// `Err` unless `value` is finite.
+pub fn check_finite_value(quantity: &str, step: usize, value: f64) -> Result<(), SimError> {
+    if value.is_finite() {
+        Ok(())
+    } else {
+        Err(SimError::NonFinite { quantity: quantity.to_string(), step, cell: None, value })
+    }
+}
@@ -0,0 +1,137 @@
+//! Fields in an expanding FRW background, on a comoving grid.
+//!
+//! A scenario in an expanding universe stores number densities as comoving
+//! densities N = a³ n on cells of fixed comoving size dx. Expansion alone
+//! leaves N unchanged, so no dilution is applied by hand; the scale factor
+//! enters through the spatial metric a² δ_ij instead. A comoving cell spans
+//! a · dx of physical length, and diffusion with a physical coefficient D
+//! reads
+//!
+//! ```text
+//! ∂N/∂t = (D / a²) ∇²N
+//! ```
+//!
+//! on the comoving Laplacian. A rate per unit physical time acts on N as it
+//! would on n when it is linear in the densities; a rate quadratic in them
+//! picks up a factor 1/a³.
+//!
+//! The run keeps an [`FrwClock`] over any [`Expansion`] history, a
+//! [`Background`] or a scenario's own, and takes the scale factors of each
+//! step from it:
+//!
+//! ```ignore
+//! let mut clock = FrwClock::new(background, t_start, dt);
+//! let photons = Diffuser::new("photon", cfg.photon_scheme, d, dt, &cfg.cg)
+//!     .with_max_scale(1.0 / (a_start * a_start))
+//!     .checked(dx, &cfg.stability)?;
+//! for step in 0..steps {
+//!     let frw = clock.advance(step)?;
+//!     let diff_ph = photons.increment_scaled(&field.photons, &bc, frw.diffusion_scale())?;
+//!     ...
+//! }
+//! ```
+
+use crate::cosmology::Background;
+use crate::error::{check_finite_value, SimError};
+
+/// A scale factor history a(t).
+pub trait Expansion {
+    fn scale_factor(&self, t: f64) -> f64;
+}
+
+impl Expansion for Background {
+    fn scale_factor(&self, t: f64) -> f64 {
+        Background::scale_factor(self, t)
+    }
+}
+
+/// Comoving density a³ n of the physical density `n` at scale factor `a`.
+pub fn comoving_density(n: f64, a: f64) -> f64 {
+    n * a * a * a
+}
+
+/// Physical density N / a³ of the comoving density `comoving`.
+pub fn physical_density(comoving: f64, a: f64) -> f64 {
+    comoving / (a * a * a)
+}
+
+/// The scale factors over one time step.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrwStep {
+    /// Time at the start of the step.
+    pub t: f64,
+    pub dt: f64,
+    pub a_start: f64,
+    pub a_mid: f64,
+    pub a_end: f64,
+}
+
+impl FrwStep {
+    /// Mean expansion rate over the step, ln(a_end / a_start) / dt.
+    pub fn hubble(&self) -> f64 {
+        (self.a_end / self.a_start).ln() / self.dt
+    }
+
+    /// Factor 1/a² on a physical diffusion coefficient on the comoving
+    /// grid, at mid-step.
+    pub fn diffusion_scale(&self) -> f64 {
+        1.0 / (self.a_mid * self.a_mid)
+    }
+
+    /// Physical length at mid-step of the comoving length `comoving`.
+    pub fn physical_length(&self, comoving: f64) -> f64 {
+        self.a_mid * comoving
+    }
+
+    /// Physical density at the end of the step of the comoving density
+    /// `comoving`.
+    pub fn physical_density(&self, comoving: f64) -> f64 {
+        physical_density(comoving, self.a_end)
+    }
+}
+
+/// Fixed time steps through an expansion history.
+#[derive(Clone, Debug)]
+pub struct FrwClock<E> {
+    expansion: E,
+    t: f64,
+    dt: f64,
+}
+
+impl<E: Expansion> FrwClock<E> {
+    /// Steps of `dt` through `expansion`, starting at its time `t`. A
+    /// negative `dt` runs the history backwards.
+    pub fn new(expansion: E, t: f64, dt: f64) -> Self {
+        FrwClock { expansion, t, dt }
+    }
+
+    pub fn expansion(&self) -> &E {
+        &self.expansion
+    }
+
+    /// The current time.
+    pub fn time(&self) -> f64 {
+        self.t
+    }
+
+    /// The current scale factor.
+    pub fn scale_factor(&self) -> f64 {
+        self.expansion.scale_factor(self.t)
+    }
+
+    /// Move on by one step, numbered `step` for errors, and return its
+    /// scale factors.
+    pub fn advance(&mut self, step: usize) -> Result<FrwStep, SimError> {
+        let (t, dt) = (self.t, self.dt);
+        let frw = FrwStep {
+            t,
+            dt,
+            a_start: self.expansion.scale_factor(t),
+            a_mid: self.expansion.scale_factor(t + 0.5 * dt),
+            a_end: self.expansion.scale_factor(t + dt),
+        };
+        check_finite_value("scale factor", step, frw.a_end)?;
+        self.t += dt;
+        Ok(frw)
+    }
+}
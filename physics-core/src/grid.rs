@@ -0,0 +1,314 @@
+//! Dense 3D grids shared by the lattice simulations.
+
+use std::ops::{Index, IndexMut, Range};
+
+use crate::error::SimError;
+
+/// A 3D array of cell values stored x-fastest, i.e. cell `(x, y, z)` lives
+/// at `x + nx * (y + ny * z)`, the layout every simulation already used.
+///
+/// Shape, cell spacing and the position of cell `(0, 0, 0)` are runtime
+/// values, so one binary can run at several resolutions. Spacing defaults to
+/// 1 and the origin to zero; set them with [`Grid3::with_spacing`] and
+/// [`Grid3::with_origin`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid3<T> {
+    nx: usize,
+    ny: usize,
+    nz: usize,
+    dx: f64,
+    origin: [f64; 3],
+    data: Vec<T>,
+}
+
+impl<T: Clone> Grid3<T> {
+    /// A grid of `nx * ny * nz` cells all holding `value`.
+    pub fn filled(nx: usize, ny: usize, nz: usize, value: T) -> Self {
+        Grid3 { nx, ny, nz, dx: 1.0, origin: [0.0; 3], data: vec![value; nx * ny * nz] }
+    }
+
+    /// A grid with the same shape, spacing and origin as `self`, every cell
+    /// holding `value`.
+    pub fn filled_like<U>(other: &Grid3<U>, value: T) -> Self {
+        Grid3::filled(other.nx, other.ny, other.nz, value)
+            .with_spacing(other.dx)
+            .with_origin(other.origin)
+    }
+
+    /// Copy of the cells in `xs × ys × zs` as a new grid. Its origin is the
+    /// position of the first copied cell, so positions stay consistent.
+    ///
+    /// Panics if a range reaches past the grid.
+    pub fn subgrid(&self, xs: Range<usize>, ys: Range<usize>, zs: Range<usize>) -> Self {
+        assert!(
+            xs.end <= self.nx && ys.end <= self.ny && zs.end <= self.nz,
+            "subgrid {:?} x {:?} x {:?} exceeds grid {:?}",
+            xs, ys, zs, self.dims()
+        );
+        let origin = self.position(xs.start, ys.start, zs.start);
+        Grid3::from_fn(xs.len(), ys.len(), zs.len(), |x, y, z| {
+            self[(xs.start + x, ys.start + y, zs.start + z)].clone()
+        })
+        .with_spacing(self.dx)
+        .with_origin(origin)
+    }
+}
+
+impl<T> Grid3<T> {
+    /// A grid whose cell `(x, y, z)` is initialised to `f(x, y, z)`.
+    pub fn from_fn(nx: usize, ny: usize, nz: usize, mut f: impl FnMut(usize, usize, usize) -> T) -> Self {
+        let mut data = Vec::with_capacity(nx * ny * nz);
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    data.push(f(x, y, z));
+                }
+            }
+        }
+        Grid3 { nx, ny, nz, dx: 1.0, origin: [0.0; 3], data }
+    }
+
+    /// Wrap `data`, stored x-fastest, as an `nx × ny × nz` grid.
+    pub fn from_vec(nx: usize, ny: usize, nz: usize, data: Vec<T>) -> Result<Self, SimError> {
+        if data.len() != nx * ny * nz {
+            return Err(SimError::Shape(format!(
+                "{} values cannot fill a {}x{}x{} grid",
+                data.len(), nx, ny, nz
+            )));
+        }
+        Ok(Grid3 { nx, ny, nz, dx: 1.0, origin: [0.0; 3], data })
+    }
+
+    /// Set the cell spacing (same along every axis).
+    pub fn with_spacing(mut self, dx: f64) -> Self {
+        self.dx = dx;
+        self
+    }
+
+    /// Set the physical position of cell `(0, 0, 0)`.
+    pub fn with_origin(mut self, origin: [f64; 3]) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    pub fn dims(&self) -> (usize, usize, usize) {
+        (self.nx, self.ny, self.nz)
+    }
+
+    pub fn spacing(&self) -> f64 {
+        self.dx
+    }
+
+    pub fn origin(&self) -> [f64; 3] {
+        self.origin
+    }
+
+    /// Physical position of cell `(x, y, z)`.
+    pub fn position(&self, x: usize, y: usize, z: usize) -> [f64; 3] {
+        [
+            self.origin[0] + x as f64 * self.dx,
+            self.origin[1] + y as f64 * self.dx,
+            self.origin[2] + z as f64 * self.dx,
+        ]
+    }
+
+    /// True if `other` has the same shape, so cells can be paired by index.
+    pub fn same_shape<U>(&self, other: &Grid3<U>) -> bool {
+        self.dims() == other.dims()
+    }
+
+    pub fn contains(&self, x: usize, y: usize, z: usize) -> bool {
+        x < self.nx && y < self.ny && z < self.nz
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Flat index of cell `(x, y, z)`, checked only in debug builds; index
+    /// by `(x, y, z)` or use [`Grid3::get`] for a checked lookup.
+    pub fn idx(&self, x: usize, y: usize, z: usize) -> usize {
+        debug_assert!(self.contains(x, y, z), "cell ({}, {}, {}) outside grid {:?}", x, y, z, self.dims());
+        x + self.nx * (y + self.ny * z)
+    }
+
+    /// Cell coordinates of flat index `i`.
+    pub fn coords(&self, i: usize) -> (usize, usize, usize) {
+        (i % self.nx, (i / self.nx) % self.ny, i / (self.nx * self.ny))
+    }
+
+    /// Cell `(x, y, z)`, or `None` if it lies outside the grid.
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Option<&T> {
+        if self.contains(x, y, z) {
+            Some(&self.data[self.idx(x, y, z)])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize, z: usize) -> Option<&mut T> {
+        if self.contains(x, y, z) {
+            let i = self.idx(x, y, z);
+            Some(&mut self.data[i])
+        } else {
+            None
+        }
+    }
+
+    /// All cell coordinates in storage order.
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize, usize)> {
+        let (nx, ny, nz) = self.dims();
+        (0..nz).flat_map(move |z| (0..ny).flat_map(move |y| (0..nx).map(move |x| (x, y, z))))
+    }
+
+    /// The up to six face neighbours of `(x, y, z)` that lie on the grid.
+    pub fn neighbors(&self, x: usize, y: usize, z: usize) -> impl Iterator<Item = (usize, usize, usize)> {
+        let (nx, ny, nz) = self.dims();
+        [
+            (x + 1 < nx).then(|| (x + 1, y, z)),
+            (x > 0).then(|| (x - 1, y, z)),
+            (y + 1 < ny).then(|| (x, y + 1, z)),
+            (y > 0).then(|| (x, y - 1, z)),
+            (z + 1 < nz).then(|| (x, y, z + 1)),
+            (z > 0).then(|| (x, y, z - 1)),
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// The six face neighbours of `(x, y, z)` with periodic wrap-around,
+    /// ordered +x, -x, +y, -y, +z, -z.
+    pub fn neighbors_periodic(&self, x: usize, y: usize, z: usize) -> [(usize, usize, usize); 6] {
+        let (nx, ny, nz) = self.dims();
+        [
+            ((x + 1) % nx, y, z),
+            ((x + nx - 1) % nx, y, z),
+            (x, (y + 1) % ny, z),
+            (x, (y + ny - 1) % ny, z),
+            (x, y, (z + 1) % nz),
+            (x, y, (z + nz - 1) % nz),
+        ]
+    }
+
+    /// The contiguous row of cells `(.., y, z)`.
+    pub fn row(&self, y: usize, z: usize) -> &[T] {
+        let start = self.idx(0, y, z);
+        &self.data[start..start + self.nx]
+    }
+
+    pub fn row_mut(&mut self, y: usize, z: usize) -> &mut [T] {
+        let start = self.idx(0, y, z);
+        &mut self.data[start..start + self.nx]
+    }
+
+    /// The contiguous plane of cells `(.., .., z)`, x-fastest.
+    pub fn plane(&self, z: usize) -> &[T] {
+        let len = self.nx * self.ny;
+        &self.data[z * len..(z + 1) * len]
+    }
+
+    pub fn plane_mut(&mut self, z: usize) -> &mut [T] {
+        let len = self.nx * self.ny;
+        &mut self.data[z * len..(z + 1) * len]
+    }
+
+    /// Apply `f` to every cell, keeping shape, spacing and origin.
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid3<U> {
+        Grid3 {
+            nx: self.nx,
+            ny: self.ny,
+            nz: self.nz,
+            dx: self.dx,
+            origin: self.origin,
+            data: self.data.iter().map(f).collect(),
+        }
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.data.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.data.iter_mut()
+    }
+}
+
+impl Grid3<f64> {
+    pub fn sum(&self) -> f64 {
+        self.data.iter().sum()
+    }
+
+    /// Average over all cells.
+    pub fn mean(&self) -> f64 {
+        self.sum() / self.len() as f64
+    }
+}
+
+impl<T> Index<usize> for Grid3<T> {
+    type Output = T;
+
+    fn index(&self, i: usize) -> &T {
+        &self.data[i]
+    }
+}
+
+impl<T> IndexMut<usize> for Grid3<T> {
+    fn index_mut(&mut self, i: usize) -> &mut T {
+        &mut self.data[i]
+    }
+}
+
+impl<T> Index<(usize, usize, usize)> for Grid3<T> {
+    type Output = T;
+
+    fn index(&self, (x, y, z): (usize, usize, usize)) -> &T {
+        match self.get(x, y, z) {
+            Some(v) => v,
+            None => panic!("cell ({}, {}, {}) outside grid {:?}", x, y, z, self.dims()),
+        }
+    }
+}
+
+impl<T> IndexMut<(usize, usize, usize)> for Grid3<T> {
+    fn index_mut(&mut self, (x, y, z): (usize, usize, usize)) -> &mut T {
+        let dims = self.dims();
+        match self.get_mut(x, y, z) {
+            Some(v) => v,
+            None => panic!("cell ({}, {}, {}) outside grid {:?}", x, y, z, dims),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tuple_indexing_is_checked() {
+        let mut grid = Grid3::from_fn(3, 2, 2, |x, y, z| (x, y, z));
+        assert_eq!(grid[(2, 1, 1)], (2, 1, 1));
+        grid[(0, 1, 0)] = (9, 9, 9);
+        assert_eq!(grid[grid.idx(0, 1, 0)], (9, 9, 9));
+        assert!(grid.get(3, 0, 0).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "outside grid")]
+    fn tuple_indexing_panics_outside_the_grid() {
+        // y = ny would land on (0, 0, 1) through the flat index.
+        let mut grid = Grid3::filled(3, 2, 2, 0.0);
+        grid[(0, 2, 0)] = 1.0;
+    }
+}
@@ -0,0 +1,433 @@
+//! Gravitational-wave backgrounds as linearized metric perturbations.
+//!
+//! A background is a sum of plane waves in transverse-traceless gauge,
+//!
+//! ```text
+//! ds² = −c² dt² + (δ_ij + h_ij) dx^i dx^j,
+//! h_ij(t, x) = Σ h₊(t − n·x/c) e⁺_ij + h×(t − n·x/c) e×_ij,
+//! ```
+//!
+//! each travelling along its unit vector n with polarization tensors
+//! e⁺ = p⊗p − q⊗q and e× = p⊗q + q⊗p built on axes p, q ⊥ n. A wave is
+//! either a monochromatic [`Mode`], h₊ = A₊ cos Φ and h× = A× sin Φ, or a
+//! quadrupole-order inspiral [`Chirp`]. In a config file:
+//!
+//! ```toml
+//! [[gw.modes]]
+//! plus = 1e-21
+//! frequency = 100.0
+//! direction = [1.0, 0.0, 0.0]
+//!
+//! [[gw.chirps]]
+//! chirp_mass = 28.3
+//! distance = 1.3e25
+//! coalescence_time = 0.5
+//! ```
+//!
+//! Being traceless, h leaves volumes and hence densities unchanged to first
+//! order; being transverse, ∂_i h_ij = 0, so a diffusing density feels it
+//! only through the metric in the operator, ∂_i((δ_ij − h_ij) ∂_j n) (see
+//! [`crate::diffusion::Diffuser::increment_in_metric`]).
+
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{non_negative, positive, Validate};
+use crate::constants::si::{C, G, M_SUN};
+use crate::grid::Grid3;
+use crate::stencil::Tensor;
+
+/// A monochromatic plane wave.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Mode {
+    /// Strain amplitude A₊ of the plus polarization.
+    pub plus: f64,
+    /// Strain amplitude A× of the cross polarization, a quarter period
+    /// behind; equal amplitudes make the wave circularly polarized.
+    pub cross: f64,
+    pub frequency: f64, // Hz
+    /// Propagation direction; need not be normalized.
+    pub direction: [f64; 3],
+    /// Rotation ψ of the polarization axes about the direction (rad).
+    pub polarization_angle: f64,
+    /// Φ at t = 0 at the origin (rad).
+    pub phase: f64,
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Mode { plus: 1e-21, cross: 0.0, frequency: 100.0, direction: [1.0, 0.0, 0.0], polarization_angle: 0.0, phase: 0.0 }
+    }
+}
+
+/// The inspiral of a compact binary at leading (quadrupole) order. With
+/// 𝓜 = G M_c / c³ and τ the time left to coalescence,
+///
+/// ```text
+/// f(τ) = (5 / 256τ)^(3/8) 𝓜^(−5/8) / π
+/// Φ(τ) = φ₀ − 2 (τ / 5𝓜)^(5/8)
+/// h₊ = A (1 + cos²ι)/2 cos Φ,  h× = A cos ι sin Φ,
+/// A  = 4 c 𝓜^(5/3) (π f)^(2/3) / r
+/// ```
+///
+/// The wave is cut off once f passes `max_frequency`, leaving out the
+/// merger and ringdown that this order cannot describe.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Chirp {
+    pub chirp_mass: f64,       // M_c (solar masses)
+    pub distance: f64,         // r (m)
+    pub coalescence_time: f64, // arrival of the coalescence at the origin (s)
+    pub max_frequency: f64,    // Hz
+    /// Angle ι between the orbital axis and the line of sight (rad).
+    pub inclination: f64,
+    pub direction: [f64; 3],
+    pub polarization_angle: f64,
+    /// φ₀, the phase at coalescence (rad).
+    pub phase: f64,
+}
+
+impl Default for Chirp {
+    fn default() -> Self {
+        // Roughly GW150914.
+        Chirp {
+            chirp_mass: 28.3,
+            distance: 1.3e25,
+            coalescence_time: 1.0,
+            max_frequency: 250.0,
+            inclination: 0.0,
+            direction: [0.0, 0.0, 1.0],
+            polarization_angle: 0.0,
+            phase: 0.0,
+        }
+    }
+}
+
+impl Chirp {
+    /// 𝓜 = G M_c / c³ (s).
+    fn mass_time(&self) -> f64 {
+        G * self.chirp_mass * M_SUN / C.powi(3)
+    }
+
+    /// Strain amplitude A at gravitational-wave frequency `f`.
+    fn amplitude(&self, f: f64) -> f64 {
+        4.0 * C * self.mass_time().powf(5.0 / 3.0) * (PI * f).powf(2.0 / 3.0) / self.distance
+    }
+
+    /// (h₊, h×) at retarded time `t`.
+    fn polarizations(&self, t: f64) -> (f64, f64) {
+        let tau = self.coalescence_time - t;
+        if tau <= 0.0 {
+            return (0.0, 0.0);
+        }
+        let m = self.mass_time();
+        let f = (5.0 / (256.0 * tau)).powf(3.0 / 8.0) * m.powf(-5.0 / 8.0) / PI;
+        if f > self.max_frequency {
+            return (0.0, 0.0);
+        }
+        let a = self.amplitude(f);
+        let phi = self.phase - 2.0 * (tau / (5.0 * m)).powf(5.0 / 8.0);
+        let cos_i = self.inclination.cos();
+        (a * 0.5 * (1.0 + cos_i * cos_i) * phi.cos(), a * cos_i * phi.sin())
+    }
+}
+
+/// The `gw` table of a scenario config. Without modes or chirps space is
+/// flat.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GwSettings {
+    pub modes: Vec<Mode>,
+    pub chirps: Vec<Chirp>,
+}
+
+/// `Err` naming `name` unless `direction` is finite and non-zero.
+fn direction(name: &str, direction: [f64; 3]) -> Result<(), String> {
+    if direction.iter().all(|d| d.is_finite()) && direction.iter().any(|&d| d != 0.0) {
+        Ok(())
+    } else {
+        Err(format!("{} must be a finite non-zero vector, got {:?}", name, direction))
+    }
+}
+
+impl Validate for GwSettings {
+    fn validate(&self) -> Result<(), String> {
+        for mode in &self.modes {
+            if !mode.plus.is_finite() || !mode.cross.is_finite() {
+                return Err("gw.modes must have finite plus and cross amplitudes".into());
+            }
+            non_negative("gw.modes.frequency", mode.frequency)?;
+            direction("gw.modes.direction", mode.direction)?;
+            if !mode.polarization_angle.is_finite() || !mode.phase.is_finite() {
+                return Err("gw.modes must have finite angles".into());
+            }
+        }
+        for chirp in &self.chirps {
+            positive("gw.chirps.chirp_mass", chirp.chirp_mass)?;
+            positive("gw.chirps.distance", chirp.distance)?;
+            positive("gw.chirps.max_frequency", chirp.max_frequency)?;
+            direction("gw.chirps.direction", chirp.direction)?;
+            if ![chirp.coalescence_time, chirp.inclination, chirp.polarization_angle, chirp.phase]
+                .iter()
+                .all(|v| v.is_finite())
+            {
+                return Err("gw.chirps must have a finite coalescence time and angles".into());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Waveform {
+    Mode(Mode),
+    Chirp(Chirp),
+}
+
+/// One wave with its geometry worked out.
+#[derive(Clone, Copy, Debug)]
+struct Wave {
+    /// Unit propagation direction n.
+    direction: [f64; 3],
+    plus: Tensor,
+    cross: Tensor,
+    waveform: Waveform,
+}
+
+fn cross_product(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn normalized(v: [f64; 3]) -> [f64; 3] {
+    let norm = v.iter().map(|c| c * c).sum::<f64>().sqrt();
+    v.map(|c| c / norm)
+}
+
+impl Wave {
+    /// Polarization tensors on the axes p = ẑ × n / |ẑ × n| (ŷ in place of
+    /// ẑ for waves near the z axis) and q = n × p, turned by ψ about n. A
+    /// wave along x has p = ŷ, q = ẑ; one along z has p = x̂, q = ŷ.
+    fn new(direction: [f64; 3], psi: f64, waveform: Waveform) -> Self {
+        let n = normalized(direction);
+        let reference = if n[2].abs() > 0.9 { [0.0, 1.0, 0.0] } else { [0.0, 0.0, 1.0] };
+        let p0 = normalized(cross_product(reference, n));
+        let q0 = cross_product(n, p0);
+        let (sin, cos) = psi.sin_cos();
+        let p: [f64; 3] = std::array::from_fn(|i| cos * p0[i] + sin * q0[i]);
+        let q: [f64; 3] = std::array::from_fn(|i| cos * q0[i] - sin * p0[i]);
+        Wave {
+            direction: n,
+            plus: std::array::from_fn(|i| std::array::from_fn(|j| p[i] * p[j] - q[i] * q[j])),
+            cross: std::array::from_fn(|i| std::array::from_fn(|j| p[i] * q[j] + q[i] * p[j])),
+            waveform,
+        }
+    }
+
+    /// (h₊, h×) at time `t` and position `x`.
+    fn polarizations(&self, t: f64, x: [f64; 3]) -> (f64, f64) {
+        let retarded = t - (0..3).map(|i| self.direction[i] * x[i]).sum::<f64>() / C;
+        match self.waveform {
+            Waveform::Mode(mode) => {
+                let phi = 2.0 * PI * mode.frequency * retarded + mode.phase;
+                (mode.plus * phi.cos(), mode.cross * phi.sin())
+            }
+            Waveform::Chirp(chirp) => chirp.polarizations(retarded),
+        }
+    }
+
+    /// The largest |eigenvalue| h can reach, √(h₊² + h×²) at its peak.
+    fn max_strain(&self) -> f64 {
+        match self.waveform {
+            Waveform::Mode(mode) => mode.plus.abs().max(mode.cross.abs()),
+            Waveform::Chirp(chirp) => chirp.amplitude(chirp.max_frequency),
+        }
+    }
+}
+
+/// A gravitational-wave background built from [`GwSettings`].
+#[derive(Clone, Debug)]
+pub struct GwBackground {
+    waves: Vec<Wave>,
+}
+
+impl GwBackground {
+    pub fn new(settings: &GwSettings) -> Self {
+        let modes = settings.modes.iter().map(|m| Wave::new(m.direction, m.polarization_angle, Waveform::Mode(*m)));
+        let chirps = settings.chirps.iter().map(|c| Wave::new(c.direction, c.polarization_angle, Waveform::Chirp(*c)));
+        GwBackground { waves: modes.chain(chirps).collect() }
+    }
+
+    pub fn is_flat(&self) -> bool {
+        self.waves.is_empty()
+    }
+
+    /// The perturbation h_ij at time `t` (s) and position `x` (m).
+    pub fn strain(&self, t: f64, x: [f64; 3]) -> Tensor {
+        let mut h = [[0.0; 3]; 3];
+        for wave in &self.waves {
+            let (plus, cross) = wave.polarizations(t, x);
+            for (i, row) in h.iter_mut().enumerate() {
+                for (j, h) in row.iter_mut().enumerate() {
+                    *h += plus * wave.plus[i][j] + cross * wave.cross[i][j];
+                }
+            }
+        }
+        h
+    }
+
+    /// The spatial metric γ_ij = δ_ij + h_ij.
+    pub fn metric(&self, t: f64, x: [f64; 3]) -> Tensor {
+        let h = self.strain(t, x);
+        std::array::from_fn(|i| std::array::from_fn(|j| if i == j { 1.0 + h[i][j] } else { h[i][j] }))
+    }
+
+    /// γ^ij = δ_ij − h_ij, the inverse metric to first order. Stencils on
+    /// O(1e-21) strains should take h itself from
+    /// [`GwBackground::perturbation`] instead, since 1 ± h rounds to 1.
+    pub fn inverse_metric(&self, t: f64, x: [f64; 3]) -> Tensor {
+        let h = self.strain(t, x);
+        std::array::from_fn(|i| std::array::from_fn(|j| if i == j { 1.0 - h[i][j] } else { -h[i][j] }))
+    }
+
+    /// h_ij at time `t` on the cells of `grid`, for
+    /// [`crate::stencil::tensor_laplacian`] and
+    /// [`crate::species::Mixture::step_in_metric`].
+    pub fn perturbation<T>(&self, t: f64, grid: &Grid3<T>) -> Grid3<Tensor> {
+        let mut h = Grid3::filled_like(grid, [[0.0; 3]; 3]);
+        for (x, y, z) in grid.cells() {
+            h[(x, y, z)] = self.strain(t, grid.position(x, y, z));
+        }
+        h
+    }
+
+    /// An upper bound on the eigenvalues of h, so that diffusion on this
+    /// background is at most 1 + `max_strain` times as fast as in flat space.
+    pub fn max_strain(&self) -> f64 {
+        self.waves.iter().map(Wave::max_strain).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mode(plus: f64, cross: f64, direction: [f64; 3]) -> Mode {
+        Mode { plus, cross, frequency: 50.0, direction, ..Mode::default() }
+    }
+
+    fn background(modes: &[Mode], chirps: &[Chirp]) -> GwBackground {
+        GwBackground::new(&GwSettings { modes: modes.to_vec(), chirps: chirps.to_vec() })
+    }
+
+    fn assert_close(a: Tensor, b: Tensor, tolerance: f64) {
+        for i in 0..3 {
+            for j in 0..3 {
+                assert!((a[i][j] - b[i][j]).abs() <= tolerance, "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn strain_is_transverse_traceless() {
+        let direction = [1.0, 2.0, -0.5];
+        let n = normalized(direction);
+        let wave = Mode { polarization_angle: 0.7, phase: 0.3, ..mode(0.4, 0.9, direction) };
+        let chirp = Chirp { direction, polarization_angle: -1.1, inclination: 0.5, ..Chirp::default() };
+        // Order-one amplitudes, and the chirp far above its strain.
+        let cases = [background(&[wave], &[]), background(&[], &[Chirp { distance: 1e-20, ..chirp }])];
+        for gw in &cases {
+            for (t, x) in [(0.0, [0.0; 3]), (0.013, [1e5, -3e6, 2e6]), (0.9, [4e6, 1e6, -1e6])] {
+                let h = gw.strain(t, x);
+                let size = h.iter().flatten().map(|v| v.abs()).fold(0.0, f64::max);
+                assert!(size > 1e-3, "{:?}", h);
+                assert!((h[0][0] + h[1][1] + h[2][2]).abs() < 1e-12 * size);
+                for (i, row) in h.iter().enumerate() {
+                    let contraction: f64 = row.iter().zip(n).map(|(h, n)| h * n).sum();
+                    assert!(contraction.abs() < 1e-12 * size, "row {}: {}", i, contraction);
+                    assert_eq!(*row, [h[0][i], h[1][i], h[2][i]]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn polarizations_along_the_axes() {
+        let z = [0.0, 0.0, 1.0];
+        let plus = background(&[mode(1.0, 0.0, z)], &[]).strain(0.0, [0.0; 3]);
+        assert_close(plus, [[1.0, 0.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, 0.0]], 1e-15);
+        // h× = A× sin Φ, so the cross polarization peaks a quarter period on.
+        let quarter = 0.25 / 50.0;
+        let cross = background(&[mode(0.0, 1.0, z)], &[]).strain(quarter, [0.0; 3]);
+        assert_close(cross, [[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 0.0]], 1e-15);
+        // Along x the axes are p = ŷ, q = ẑ.
+        let along_x = background(&[mode(1.0, 0.0, [2.0, 0.0, 0.0])], &[]).strain(0.0, [0.0; 3]);
+        assert_close(along_x, [[0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, -1.0]], 1e-15);
+        // A quarter turn of the axes swaps the polarizations, up to sign.
+        let turned = Mode { polarization_angle: PI / 4.0, ..mode(1.0, 0.0, z) };
+        let turned = background(&[turned], &[]).strain(0.0, [0.0; 3]);
+        assert_close(turned, [[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 0.0]], 1e-15);
+    }
+
+    #[test]
+    fn modes_superpose_linearly() {
+        let a = Mode { polarization_angle: 0.2, ..mode(0.5, 0.1, [1.0, 1.0, 0.0]) };
+        let b = Mode { frequency: 170.0, phase: 1.0, ..mode(-0.3, 0.7, [0.0, -1.0, 3.0]) };
+        let both = background(&[a, b], &[]);
+        let (only_a, only_b) = (background(&[a], &[]), background(&[b], &[]));
+        for (t, x) in [(0.0, [0.0; 3]), (0.021, [3e6, -1e6, 5e5])] {
+            let (ha, hb) = (only_a.strain(t, x), only_b.strain(t, x));
+            let sum: Tensor = std::array::from_fn(|i| std::array::from_fn(|j| ha[i][j] + hb[i][j]));
+            assert_close(both.strain(t, x), sum, 1e-15);
+        }
+        assert_eq!(both.max_strain(), only_a.max_strain() + only_b.max_strain());
+    }
+
+    #[test]
+    fn chirp_follows_the_quadrupole_law() {
+        // Face on and along z, h₊ = A cos Φ and h× = A sin Φ at the origin.
+        let chirp = Chirp::default();
+        let gw = background(&[], &[chirp]);
+        let mass_time = G * chirp.chirp_mass * M_SUN / C.powi(3);
+        let law = |tau: f64| (5.0 / (256.0 * tau)).powf(3.0 / 8.0) * mass_time.powf(-5.0 / 8.0) / PI;
+        let amplitude = |f: f64| 4.0 * C * mass_time.powf(5.0 / 3.0) * (PI * f).powf(2.0 / 3.0) / chirp.distance;
+        let polarizations = |t: f64| {
+            let h = gw.strain(t, [0.0; 3]);
+            (h[0][0], h[0][1])
+        };
+
+        let mut last_f = 0.0;
+        for tau in [0.9, 0.5, 0.2, 0.1, 0.05] {
+            let t = chirp.coalescence_time - tau;
+            let f = law(tau);
+            assert!(f < chirp.max_frequency);
+            let (plus, cross) = polarizations(t);
+            let a = plus.hypot(cross);
+            assert!((a / amplitude(f) - 1.0).abs() < 1e-12, "A({}) = {}", tau, a);
+            assert!(a <= gw.max_strain());
+
+            // dΦ/dt = 2π f, from the phase a little before and after.
+            let dt = 1e-6;
+            let phase = |t: f64| {
+                let (plus, cross) = polarizations(t);
+                cross.atan2(plus)
+            };
+            let mut d_phi = phase(t + dt) - phase(t - dt);
+            if d_phi < -PI {
+                d_phi += 2.0 * PI;
+            }
+            let measured = d_phi / (2.0 * dt) / (2.0 * PI);
+            assert!((measured / f - 1.0).abs() < 1e-6, "f({}) = {}, law {}", tau, measured, f);
+            assert!(f > last_f);
+            last_f = f;
+        }
+
+        assert!((gw.max_strain() / amplitude(chirp.max_frequency) - 1.0).abs() < 1e-12);
+        // Silent past max_frequency and after the coalescence.
+        let cutoff = 5.0 / 256.0 * (PI * chirp.max_frequency).powf(-8.0 / 3.0) * mass_time.powf(-5.0 / 3.0);
+        assert_eq!(polarizations(chirp.coalescence_time - 0.5 * cutoff), (0.0, 0.0));
+        assert_eq!(polarizations(chirp.coalescence_time + 0.1), (0.0, 0.0));
+        assert_ne!(polarizations(chirp.coalescence_time - 2.0 * cutoff), (0.0, 0.0));
+    }
+}
@@ -0,0 +1,177 @@
+//! Interpolation of tabulated functions.
+
+use std::cmp::Ordering;
+
+/// Piecewise-cubic Hermite interpolant: cubic between the nodes, matching
+/// the values and the given slopes at each of them.
+#[derive(Clone, Debug)]
+pub struct CubicHermite {
+    x: Vec<f64>,
+    y: Vec<f64>,
+    slopes: Vec<f64>,
+}
+
+impl CubicHermite {
+    /// Interpolant through `(x[k], y[k])` with slope `slopes[k]` there. `x`
+    /// must be strictly increasing and hold at least two nodes.
+    pub fn new(x: Vec<f64>, y: Vec<f64>, slopes: Vec<f64>) -> Result<Self, String> {
+        check_nodes(&x, &y)?;
+        if slopes.len() != x.len() {
+            return Err(format!("{} abscissae but {} slopes", x.len(), slopes.len()));
+        }
+        Ok(CubicHermite { x, y, slopes })
+    }
+
+    /// The tabulated range.
+    pub fn domain(&self) -> (f64, f64) {
+        (self.x[0], self.x[self.x.len() - 1])
+    }
+
+    /// Value at `x`, clamped to the end values outside the table.
+    pub fn eval(&self, x: f64) -> f64 {
+        let (lo, hi) = self.domain();
+        if x <= lo {
+            return self.y[0];
+        }
+        if x >= hi {
+            return self.y[self.y.len() - 1];
+        }
+        let k = self.x.partition_point(|&node| node <= x) - 1;
+        let h = self.x[k + 1] - self.x[k];
+        let t = (x - self.x[k]) / h;
+        let (t2, t3) = (t * t, t * t * t);
+        (2.0 * t3 - 3.0 * t2 + 1.0) * self.y[k]
+            + (t3 - 2.0 * t2 + t) * h * self.slopes[k]
+            + (-2.0 * t3 + 3.0 * t2) * self.y[k + 1]
+            + (t3 - t2) * h * self.slopes[k + 1]
+    }
+}
+
+/// `Err` unless `x` and `y` pair up into at least two nodes with strictly
+/// increasing `x`.
+fn check_nodes(x: &[f64], y: &[f64]) -> Result<(), String> {
+    if x.len() != y.len() {
+        return Err(format!("{} abscissae but {} values", x.len(), y.len()));
+    }
+    if x.len() < 2 {
+        return Err("at least two nodes are needed".into());
+    }
+    if let Some(k) = x.windows(2).position(|w| w[1].partial_cmp(&w[0]) != Some(Ordering::Greater)) {
+        return Err(format!("abscissae must be strictly increasing, but x[{}] = {} is followed by {}", k, x[k], x[k + 1]));
+    }
+    Ok(())
+}
+
+/// Monotone piecewise-cubic Hermite interpolant (Fritsch–Carlson): cubic
+/// between the nodes, continuous in value and slope, and monotone wherever
+/// the data are, so it never overshoots between two table rows.
+#[derive(Clone, Debug)]
+pub struct MonotoneCubic(CubicHermite);
+
+impl MonotoneCubic {
+    /// Interpolant through `(x[k], y[k])`. `x` must be strictly increasing
+    /// and hold at least two nodes.
+    pub fn new(x: Vec<f64>, y: Vec<f64>) -> Result<Self, String> {
+        check_nodes(&x, &y)?;
+        let h: Vec<f64> = x.windows(2).map(|w| w[1] - w[0]).collect();
+        let delta: Vec<f64> = y.windows(2).zip(&h).map(|(w, h)| (w[1] - w[0]) / h).collect();
+        let n = x.len();
+        let mut slopes = vec![0.0; n];
+        if n == 2 {
+            slopes = vec![delta[0]; 2];
+        } else {
+            for k in 1..n - 1 {
+                let (d0, d1) = (delta[k - 1], delta[k]);
+                if d0 * d1 > 0.0 {
+                    // Weighted harmonic mean of the neighbouring secants.
+                    let w0 = 2.0 * h[k] + h[k - 1];
+                    let w1 = h[k] + 2.0 * h[k - 1];
+                    slopes[k] = (w0 + w1) / (w0 / d0 + w1 / d1);
+                }
+            }
+            slopes[0] = end_slope(h[0], h[1], delta[0], delta[1]);
+            slopes[n - 1] = end_slope(h[n - 2], h[n - 3], delta[n - 2], delta[n - 3]);
+        }
+        Ok(MonotoneCubic(CubicHermite { x, y, slopes }))
+    }
+
+    /// The tabulated range.
+    pub fn domain(&self) -> (f64, f64) {
+        self.0.domain()
+    }
+
+    /// Value at `x`, clamped to the end values outside the table.
+    pub fn eval(&self, x: f64) -> f64 {
+        self.0.eval(x)
+    }
+}
+
+/// One-sided three-point slope at an end node, limited to keep the end
+/// interval monotone. `h0`, `delta0` belong to the end interval and `h1`,
+/// `delta1` to its neighbour.
+fn end_slope(h0: f64, h1: f64, delta0: f64, delta1: f64) -> f64 {
+    let slope = ((2.0 * h0 + h1) * delta0 - h0 * delta1) / (h0 + h1);
+    if slope * delta0 <= 0.0 {
+        0.0
+    } else if delta0 * delta1 <= 0.0 && slope.abs() > 3.0 * delta0.abs() {
+        3.0 * delta0
+    } else {
+        slope
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A monotone table with a plateau and a sharp step, where an ordinary
+    /// cubic spline overshoots.
+    fn steps() -> (Vec<f64>, Vec<f64>) {
+        let x = vec![0.0, 1.0, 2.0, 2.5, 3.0, 4.0, 6.0, 7.0];
+        let y = vec![0.0, 0.1, 0.1, 0.1, 5.0, 5.2, 5.3, 9.0];
+        (x, y)
+    }
+
+    #[test]
+    fn reproduces_the_nodes() {
+        let (x, y) = steps();
+        let interpolant = MonotoneCubic::new(x.clone(), y.clone()).unwrap();
+        for (x, y) in x.iter().zip(&y) {
+            assert_eq!(interpolant.eval(*x), *y);
+        }
+        let hermite = CubicHermite::new(vec![0.0, 1.0, 3.0], vec![1.0, -2.0, 4.0], vec![0.0, 3.0, -1.0]).unwrap();
+        assert_eq!(hermite.eval(1.0), -2.0);
+        assert_eq!(hermite.eval(3.0), 4.0);
+    }
+
+    #[test]
+    fn monotone_data_give_a_monotone_interpolant_without_overshoot() {
+        let (x, y) = steps();
+        let interpolant = MonotoneCubic::new(x.clone(), y.clone()).unwrap();
+        let mut previous = f64::NEG_INFINITY;
+        for i in 0..=7000 {
+            let at = i as f64 * 1e-3;
+            let value = interpolant.eval(at);
+            assert!(value >= previous - 1e-12, "decreases at x = {}: {} after {}", at, value, previous);
+            previous = value;
+            // Within the values of the interval's own nodes, up to rounding.
+            let k = x.partition_point(|&node| node <= at).clamp(1, x.len() - 1) - 1;
+            assert!(value >= y[k] - 1e-12 && value <= y[k + 1] + 1e-12, "overshoots at x = {}: {} outside [{}, {}]", at, value, y[k], y[k + 1]);
+        }
+    }
+
+    #[test]
+    fn clamps_outside_the_table() {
+        let (x, y) = steps();
+        let interpolant = MonotoneCubic::new(x, y).unwrap();
+        assert_eq!(interpolant.eval(-1.0), 0.0);
+        assert_eq!(interpolant.eval(10.0), 9.0);
+    }
+
+    #[test]
+    fn rejects_bad_nodes() {
+        assert!(MonotoneCubic::new(vec![0.0, 1.0, 1.0], vec![0.0, 1.0, 2.0]).is_err());
+        assert!(MonotoneCubic::new(vec![0.0], vec![0.0]).is_err());
+        assert!(CubicHermite::new(vec![0.0, 1.0], vec![0.0, 1.0], vec![1.0]).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+//! CSV and NPY writers for simulation output, and a CSV reader for input
+//! tables. Errors carry the path of the file being written or read.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::SimError;
+use crate::grid::Grid3;
+
+/// Buffered CSV file with a fixed header line. Dropping the writer (for
+/// instance when a run bails out with `?`) flushes the rows written so far.
+pub struct CsvWriter {
+    path: PathBuf,
+    out: BufWriter<File>,
+}
+
+impl CsvWriter {
+    /// Create (or truncate) `path` and write `header` as its first line.
+    pub fn create(path: impl AsRef<Path>, header: &[&str]) -> Result<Self, SimError> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::create(&path).map_err(SimError::io(&path))?;
+        let mut out = BufWriter::new(file);
+        writeln!(out, "{}", header.join(",")).map_err(SimError::io(&path))?;
+        Ok(CsvWriter { path, out })
+    }
+
+    /// Write one row of numbers, formatted with `{}`.
+    pub fn row(&mut self, values: &[f64]) -> Result<(), SimError> {
+        let fields: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        self.record(&fields)
+    }
+
+    /// Write one row of already formatted fields.
+    pub fn record<S: AsRef<str>>(&mut self, fields: &[S]) -> Result<(), SimError> {
+        self.write_record(fields).map_err(SimError::io(&self.path))
+    }
+
+    fn write_record<S: AsRef<str>>(&mut self, fields: &[S]) -> io::Result<()> {
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                self.out.write_all(b",")?;
+            }
+            self.out.write_all(field.as_ref().as_bytes())?;
+        }
+        self.out.write_all(b"\n")
+    }
+
+    /// Flush buffered rows to disk.
+    pub fn finish(mut self) -> Result<(), SimError> {
+        self.out.flush().map_err(SimError::io(&self.path))
+    }
+}
+
+/// A table of numbers under a header line, as read by [`read_csv`].
+#[derive(Clone, Debug)]
+pub struct CsvTable {
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<f64>>,
+}
+
+impl CsvTable {
+    /// The column named by the first of `names` in the header.
+    pub fn column(&self, names: &[&str]) -> Option<Vec<f64>> {
+        let c = names.iter().find_map(|name| self.header.iter().position(|h| h == name))?;
+        Some(self.rows.iter().map(|row| row[c]).collect())
+    }
+}
+
+/// Read a CSV file of numbers with a header line. Blank lines and lines
+/// starting with `#` are skipped; every other row must have one number per
+/// header field.
+pub fn read_csv(path: impl AsRef<Path>) -> Result<CsvTable, SimError> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).map_err(SimError::io(path))?;
+    let invalid = |msg: String| SimError::Io(path.to_path_buf(), io::Error::new(io::ErrorKind::InvalidData, msg));
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+    let header: Vec<String> = match lines.next() {
+        Some((_, line)) => line.split(',').map(|h| h.trim().to_string()).collect(),
+        None => return Err(invalid("no header line".into())),
+    };
+    let mut rows = Vec::new();
+    for (number, line) in lines {
+        let row = line
+            .split(',')
+            .map(|field| field.trim().parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|e| invalid(format!("line {}: {}", number, e)))?;
+        if row.len() != header.len() {
+            return Err(invalid(format!("line {}: {} fields under a header of {}", number, row.len(), header.len())));
+        }
+        rows.push(row);
+    }
+    Ok(CsvTable { header, rows })
+}
+
+/// Write `grid` as a little-endian `f8` NPY (format 1.0) array of shape
+/// `(nx, ny, nz)`, so that `arr[x, y, z]` in NumPy is cell `(x, y, z)`.
+pub fn write_npy(path: impl AsRef<Path>, grid: &Grid3<f64>) -> Result<(), SimError> {
+    let path = path.as_ref();
+    write_npy_to(path, grid).map_err(SimError::io(path))
+}
+
+fn write_npy_to(path: &Path, grid: &Grid3<f64>) -> io::Result<()> {
+    let (nx, ny, nz) = grid.dims();
+    // Cells are stored x-fastest, which is Fortran order for an (nx, ny, nz) array.
+    let mut header = format!(
+        "{{'descr': '<f8', 'fortran_order': True, 'shape': ({}, {}, {}), }}",
+        nx, ny, nz
+    );
+    // Magic (6) + version (2) + header length (2) + header must be a multiple of 64.
+    let unpadded = 10 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    header.push('\n');
+
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(b"\x93NUMPY\x01\x00")?;
+    out.write_all(&(header.len() as u16).to_le_bytes())?;
+    out.write_all(header.as_bytes())?;
+    for v in grid.iter() {
+        out.write_all(&v.to_le_bytes())?;
+    }
+    out.flush()
+}
@@ -0,0 +1,189 @@
+//! Conservation bookkeeping for a [`crate::species::Mixture`].
+//!
+//! A [`Ledger`] follows the totals (density summed over the grid times the
+//! cell volume) of groups of species, e.g. photons + axions + neutrinos or
+//! the energy density, and books every change to the [`Process`] that made
+//! it. One CSV row per step gives each group's total and that step's change
+//! per process.
+//!
+//! Reactions and diffusion are the model's own sources and sinks. Clamping
+//! and the R-matrix are not meant to create or destroy
+//! anything, so their accumulated change is reported as the group's drift,
+//! relative to its initial total. With a tolerance set, a drift beyond it
+//! fails the run.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{positive, Validate};
+use crate::error::SimError;
+use crate::io::CsvWriter;
+
+/// Something that changes species totals within a step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Process {
+    /// Rate laws: conversions, sources and sinks.
+    Reaction,
+    /// Transport, including boundary fluxes and sponge layers.
+    Diffusion,
+    /// Negative densities set to zero.
+    Clamp,
+    /// Species mixed through the Hecke R-matrix.
+    RMatrix,
+}
+
+impl Process {
+    pub const ALL: [Process; 4] = [Process::Reaction, Process::Diffusion, Process::Clamp, Process::RMatrix];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Process::Reaction => "reaction",
+            Process::Diffusion => "diffusion",
+            Process::Clamp => "clamp",
+            Process::RMatrix => "r_matrix",
+        }
+    }
+
+    /// Whether the change is part of the model's evolution equations rather
+    /// than drift.
+    pub fn is_modelled(self) -> bool {
+        matches!(self, Process::Reaction | Process::Diffusion)
+    }
+
+    fn slot(self) -> usize {
+        self as usize
+    }
+}
+
+/// The `conservation` table of a scenario config.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Conservation {
+    /// Write the per-step totals and changes to `file` in the output directory.
+    pub enabled: bool,
+    pub file: String,
+    /// Fail the run once a group drifts by more than this fraction of its
+    /// initial total. Unset, drift is only reported.
+    pub tolerance: Option<f64>,
+}
+
+impl Default for Conservation {
+    fn default() -> Self {
+        Conservation { enabled: true, file: "conservation.csv".into(), tolerance: None }
+    }
+}
+
+impl Validate for Conservation {
+    fn validate(&self) -> Result<(), String> {
+        match self.tolerance {
+            Some(tol) => positive("conservation.tolerance", tol),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A named sum of species.
+struct Group {
+    name: String,
+    species: Vec<usize>,
+    initial: f64,
+    total: f64,
+    /// Change per process in the current step.
+    step: [f64; 4],
+    /// Change per process since the start of the run.
+    run: [f64; 4],
+}
+
+impl Group {
+    fn drift(&self) -> f64 {
+        let unmodelled: f64 = Process::ALL.iter().filter(|p| !p.is_modelled()).map(|p| self.run[p.slot()]).sum();
+        if self.initial != 0.0 {
+            unmodelled / self.initial.abs()
+        } else {
+            unmodelled
+        }
+    }
+}
+
+/// Per-step conservation record of a mixture (see the module docs).
+pub struct Ledger {
+    groups: Vec<Group>,
+    tolerance: Option<f64>,
+    file: CsvWriter,
+}
+
+impl Ledger {
+    /// Track `groups` (name and species indices), starting from the
+    /// per-species totals `totals`, writing rows to `path`.
+    pub fn create(
+        path: impl AsRef<Path>,
+        groups: &[(&str, &[usize])],
+        totals: &[f64],
+        tolerance: Option<f64>,
+    ) -> Result<Self, SimError> {
+        let groups: Vec<Group> = groups
+            .iter()
+            .map(|(name, species)| {
+                let total = species.iter().map(|&s| totals[s]).sum();
+                Group { name: name.to_string(), species: species.to_vec(), initial: total, total, step: [0.0; 4], run: [0.0; 4] }
+            })
+            .collect();
+        let mut header = vec!["step".to_string(), "time(s)".to_string()];
+        for g in &groups {
+            header.push(format!("{}_total", g.name));
+            header.extend(Process::ALL.iter().map(|p| format!("{}_{}", g.name, p.name())));
+            header.push(format!("{}_drift", g.name));
+        }
+        let header: Vec<&str> = header.iter().map(String::as_str).collect();
+        let file = CsvWriter::create(path, &header)?;
+        Ok(Ledger { groups, tolerance, file })
+    }
+
+    /// Book the change from per-species totals `before` to `after` to
+    /// `process`.
+    pub fn book(&mut self, process: Process, before: &[f64], after: &[f64]) {
+        for g in &mut self.groups {
+            let change: f64 = g.species.iter().map(|&s| after[s] - before[s]).sum();
+            g.step[process.slot()] += change;
+            g.run[process.slot()] += change;
+            g.total = g.species.iter().map(|&s| after[s]).sum();
+        }
+    }
+
+    /// Write the row for `step` at time `t`, start the next step and fail if
+    /// a group has drifted beyond the tolerance.
+    pub fn end_step(&mut self, step: usize, t: f64) -> Result<(), SimError> {
+        let mut row = vec![step as f64, t];
+        for g in &mut self.groups {
+            row.push(g.total);
+            row.extend_from_slice(&g.step);
+            row.push(g.drift());
+            g.step = [0.0; 4];
+        }
+        self.file.row(&row)?;
+        if let Some(tolerance) = self.tolerance {
+            for g in &self.groups {
+                let drift = g.drift();
+                if drift.is_nan() || drift.abs() > tolerance {
+                    return Err(SimError::NotConserved { quantity: g.name.clone(), step, drift, tolerance });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Print each group's drift and its unmodelled changes, and flush the
+    /// file.
+    pub fn finish(self) -> Result<(), SimError> {
+        for g in &self.groups {
+            let parts: Vec<String> = Process::ALL
+                .iter()
+                .filter(|p| !p.is_modelled())
+                .map(|p| format!("{} {:.3e}", p.name(), g.run[p.slot()]))
+                .collect();
+            println!("conservation: {} drift {:.3e} ({})", g.name, g.drift(), parts.join(", "));
+        }
+        self.file.finish()
+    }
+}
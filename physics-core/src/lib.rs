@@ -0,0 +1,31 @@
+//! Shared building blocks for the simulation binaries: physical constants,
+//! cosmological backgrounds and comoving stepping through them, 3D grids,
+//! boundary conditions, finite-difference stencils and their stability
+//! limits, explicit and implicit diffusion integrators, an
+//! adaptive ODE integrator, gravitational-wave backgrounds, equations of
+//! state, table interpolation, reacting species and their conservation
+//! bookkeeping, run configuration, command-line handling, errors and input
+//! and output files.
+
+pub mod boundary;
+pub mod cli;
+pub mod config;
+pub mod constants;
+pub mod cosmology;
+pub mod diffusion;
+pub mod eos;
+pub mod error;
+pub mod frw;
+pub mod grid;
+pub mod gw;
+pub mod interpolate;
+pub mod io;
+pub mod ledger;
+pub mod ode;
+pub mod species;
+pub mod stability;
+pub mod stencil;
+
+pub use boundary::{BoundaryCondition, Boundaries, Face};
+pub use error::SimError;
+pub use grid::Grid3;
@@ -0,0 +1,261 @@
+//! Adaptive integration of ODE systems y' = f(t, y).
+//!
+//! [`DormandPrince`] is the embedded Runge–Kutta 5(4) pair of Dormand and
+//! Prince: every step yields a fifth-order solution and, from the same
+//! stages, a fourth-order one whose difference estimates the error. The
+//! step is accepted when the RMS over the components of
+//!
+//! ```text
+//! error_i / (atol + rtol · max(|y_i|, |y_i'|))
+//! ```
+//!
+//! is at most 1, and the next step is scaled by 0.9 · norm^(−1/5) (within
+//! 1/5 and 5). Rejected steps are retried shorter. Systems whose components
+//! must stay non-negative, such as densities, can also reject every step
+//! that would make one negative, so nothing needs clamping afterwards.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{at_least, non_negative, positive, Validate};
+use crate::error::SimError;
+
+/// The `ode` table of a scenario config.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OdeSettings {
+    pub rtol: f64,
+    /// In the units of the components.
+    pub atol: f64,
+    /// First trial step; 0 estimates it from the initial slope.
+    pub initial_step: f64,
+    /// Give up with an error after this many attempted steps in one call.
+    pub max_steps: usize,
+}
+
+impl Default for OdeSettings {
+    fn default() -> Self {
+        OdeSettings { rtol: 1e-6, atol: 1e-12, initial_step: 0.0, max_steps: 100_000 }
+    }
+}
+
+impl Validate for OdeSettings {
+    fn validate(&self) -> Result<(), String> {
+        positive("ode.rtol", self.rtol)?;
+        non_negative("ode.atol", self.atol)?;
+        non_negative("ode.initial_step", self.initial_step)?;
+        at_least("ode.max_steps", self.max_steps, 1)
+    }
+}
+
+/// What became of an attempted step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Accepted,
+    /// The error estimate exceeded the tolerances.
+    Rejected,
+    /// A component would have turned negative.
+    Negative,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Outcome::Accepted => "accepted",
+            Outcome::Rejected => "rejected",
+            Outcome::Negative => "negative",
+        };
+        f.write_str(name)
+    }
+}
+
+/// One attempted step, for the step-size history.
+#[derive(Clone, Copy, Debug)]
+pub struct Attempt {
+    /// Start of the step.
+    pub t: f64,
+    pub h: f64,
+    /// Scaled error norm; accepted steps have at most 1.
+    pub error: f64,
+    pub outcome: Outcome,
+}
+
+const C: [f64; 7] = [0.0, 1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0];
+
+const A: [[f64; 6]; 7] = [
+    [0.0; 6],
+    [1.0 / 5.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [3.0 / 40.0, 9.0 / 40.0, 0.0, 0.0, 0.0, 0.0],
+    [44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0, 0.0, 0.0, 0.0],
+    [19372.0 / 6561.0, -25360.0 / 2187.0, 64448.0 / 6561.0, -212.0 / 729.0, 0.0, 0.0],
+    [9017.0 / 3168.0, -355.0 / 33.0, 46732.0 / 5247.0, 49.0 / 176.0, -5103.0 / 18656.0, 0.0],
+    // The fifth-order weights: the last stage is f at the new point.
+    [35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0],
+];
+
+/// Fifth- minus fourth-order weights.
+const E: [f64; 7] = [
+    71.0 / 57600.0,
+    0.0,
+    -71.0 / 16695.0,
+    71.0 / 1920.0,
+    -17253.0 / 339200.0,
+    22.0 / 525.0,
+    -1.0 / 40.0,
+];
+
+const SAFETY: f64 = 0.9;
+const MIN_FACTOR: f64 = 0.2;
+const MAX_FACTOR: f64 = 5.0;
+
+/// Dormand–Prince integrator. It keeps its step size from one
+/// [`DormandPrince::integrate`] call to the next, so a run that integrates
+/// interval by interval does not restart its step control each time.
+#[derive(Clone, Debug)]
+pub struct DormandPrince {
+    what: String,
+    settings: OdeSettings,
+    non_negative: bool,
+    /// Next trial step, once known.
+    h: Option<f64>,
+    k: [Vec<f64>; 7],
+    stage: Vec<f64>,
+    next: Vec<f64>,
+}
+
+impl DormandPrince {
+    /// Integrator for `what` (a name for errors).
+    pub fn new(what: &str, settings: &OdeSettings) -> Self {
+        DormandPrince {
+            what: what.to_string(),
+            settings: *settings,
+            non_negative: false,
+            h: (settings.initial_step > 0.0).then_some(settings.initial_step),
+            k: Default::default(),
+            stage: Vec::new(),
+            next: Vec::new(),
+        }
+    }
+
+    /// Reject steps that would make any component negative.
+    pub fn non_negative(mut self) -> Self {
+        self.non_negative = true;
+        self
+    }
+
+    /// Advance `y` from `t0` to `t1` under `f(t, y, dydt)`, reporting every
+    /// attempted step to `on_attempt`. The last step is shortened to end on
+    /// `t1`.
+    pub fn integrate(
+        &mut self,
+        mut f: impl FnMut(f64, &[f64], &mut [f64]),
+        t0: f64,
+        t1: f64,
+        y: &mut [f64],
+        mut on_attempt: impl FnMut(&Attempt) -> Result<(), SimError>,
+    ) -> Result<(), SimError> {
+        let n = y.len();
+        for k in &mut self.k {
+            k.resize(n, 0.0);
+        }
+        self.stage.resize(n, 0.0);
+        self.next.resize(n, 0.0);
+
+        let span = t1 - t0;
+        if span <= 0.0 {
+            return Ok(());
+        }
+        f(t0, y, &mut self.k[0]);
+        let mut h = match self.h {
+            Some(h) => h,
+            None => self.initial_step(y, span),
+        };
+        let mut t = t0;
+        let mut attempts = 0;
+        let mut rejected = false;
+        while t < t1 {
+            if attempts == self.settings.max_steps || t + h == t {
+                return Err(SimError::Stalled { what: self.what.clone(), t, step: h, attempts });
+            }
+            attempts += 1;
+            let last = t + h >= t1;
+            let step = if last { t1 - t } else { h };
+
+            for s in 1..7 {
+                for (i, (stage, y)) in self.stage.iter_mut().zip(y.iter()).enumerate() {
+                    let increment: f64 = (0..s).map(|j| A[s][j] * self.k[j][i]).sum();
+                    *stage = y + step * increment;
+                }
+                f(t + C[s] * step, &self.stage, &mut self.k[s]);
+                if s == 6 {
+                    self.next.copy_from_slice(&self.stage);
+                }
+            }
+
+            let mut sum = 0.0;
+            for (i, (y, next)) in y.iter().zip(&self.next).enumerate() {
+                let error: f64 = step * (0..7).map(|j| E[j] * self.k[j][i]).sum::<f64>();
+                let scale = self.settings.atol + self.settings.rtol * y.abs().max(next.abs());
+                sum += (error / scale).powi(2);
+            }
+            let error = if n == 0 { 0.0 } else { (sum / n as f64).sqrt() };
+            let negative = self.non_negative && self.next.iter().any(|&v| v < 0.0);
+            let outcome = if error.is_nan() || error > 1.0 {
+                Outcome::Rejected
+            } else if negative {
+                Outcome::Negative
+            } else {
+                Outcome::Accepted
+            };
+            on_attempt(&Attempt { t, h: step, error, outcome })?;
+
+            match outcome {
+                Outcome::Accepted => {
+                    t = if last { t1 } else { t + step };
+                    y.copy_from_slice(&self.next);
+                    // First same as last: the final stage is f at the new point.
+                    self.k.swap(0, 6);
+                    let factor = if error == 0.0 { MAX_FACTOR } else { SAFETY * error.powf(-0.2) };
+                    let factor = factor.clamp(MIN_FACTOR, if rejected { 1.0 } else { MAX_FACTOR });
+                    // A step cut short to end on t1 says nothing about the
+                    // next one beyond the step it was cut from.
+                    if !last || step * factor > h {
+                        h = step * factor;
+                    }
+                    rejected = false;
+                }
+                Outcome::Rejected => {
+                    let factor = if error.is_nan() { MIN_FACTOR } else { (SAFETY * error.powf(-0.2)).max(MIN_FACTOR) };
+                    h = step * factor;
+                    rejected = true;
+                }
+                Outcome::Negative => {
+                    h = 0.5 * step;
+                    rejected = true;
+                }
+            }
+        }
+        self.h = Some(h);
+        Ok(())
+    }
+
+    /// A first step for which the initial slope changes `y` by about 1% of
+    /// its size, at most the whole span.
+    fn initial_step(&self, y: &[f64], span: f64) -> f64 {
+        let rms = |v: &[f64]| {
+            let sum: f64 = v
+                .iter()
+                .zip(y)
+                .map(|(v, y)| (v / (self.settings.atol + self.settings.rtol * y.abs())).powi(2))
+                .sum();
+            (sum / v.len().max(1) as f64).sqrt()
+        };
+        let (size, slope) = (rms(y), rms(&self.k[0]));
+        if size < 1e-5 || slope < 1e-5 {
+            span
+        } else {
+            (0.01 * size / slope).min(span)
+        }
+    }
+}
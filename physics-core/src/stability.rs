@@ -0,0 +1,118 @@
+//! Stability limits for the explicit updates.
+//!
+//! The 7-point Laplacian stepped with forward Euler is stable in 3D only for
+//! D·dt/dx² ≤ 1/6, and a wave equation on it stepped with symplectic Euler
+//! (leapfrog) only for the Courant number c·dt/dx ≤ 1/√3. Finite-volume
+//! fluxes with signal speeds up to c need 3·c·dt/dx ≤ 1/2. Each such field
+//! checks its number once at startup and, depending on the configured
+//! [`Stability`], either stops or splits every time step into enough equal
+//! substeps to satisfy the limit.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{at_least, Validate};
+use crate::error::SimError;
+
+/// Largest stable diffusion number D·dt/dx² in 3D.
+pub const DIFFUSION_LIMIT: f64 = 1.0 / 6.0;
+
+/// Largest stable Courant number c·dt/dx of the 3D wave equation: the
+/// stiffest mode of the 7-point Laplacian has ω = 2√3 c/dx, and leapfrog
+/// needs ω·dt ≤ 2.
+pub const COURANT_LIMIT: f64 = 0.577_350_269_189_625_8;
+
+/// Largest stable Courant number Σ_axes c·dt/dx of a finite-volume update
+/// with a central flux and second-order strong-stability-preserving
+/// Runge–Kutta steps.
+pub const CFL_LIMIT: f64 = 0.5;
+
+/// What to do when an explicit update would violate its stability limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StabilityPolicy {
+    /// Split each time step into equal substeps that satisfy the limit.
+    #[default]
+    Substep,
+    /// Stop before running.
+    Refuse,
+}
+
+/// The `stability` table of a scenario config.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Stability {
+    pub policy: StabilityPolicy,
+    /// Refuse setups needing more substeps than this per time step; a
+    /// violation by orders of magnitude points at wrong parameters rather
+    /// than a step that is a little too long.
+    pub max_substeps: usize,
+}
+
+impl Default for Stability {
+    fn default() -> Self {
+        Stability { policy: StabilityPolicy::Substep, max_substeps: 1000 }
+    }
+}
+
+impl Validate for Stability {
+    fn validate(&self) -> Result<(), String> {
+        at_least("stability.max_substeps", self.max_substeps, 1)
+    }
+}
+
+/// D·dt/dx² for diffusion coefficient `d`.
+pub fn diffusion_number(d: f64, dt: f64, dx: f64) -> f64 {
+    d.abs() * dt / (dx * dx)
+}
+
+/// Substeps per time step needed to diffuse `field` stably, printed to the
+/// run log. Setups the policy does not allow are an error.
+pub fn diffusion_substeps(field: &str, d: f64, dt: f64, dx: f64, stability: &Stability) -> Result<usize, SimError> {
+    let number = diffusion_number(d, dt, dx);
+    substeps(&format!("{} diffusion", field), "D·dt/dx²", number, DIFFUSION_LIMIT, stability)
+}
+
+/// c·dt/dx for wave speed `c`.
+pub fn courant_number(c: f64, dt: f64, dx: f64) -> f64 {
+    c.abs() * dt / dx
+}
+
+/// Substeps per time step needed to propagate waves of `field` stably,
+/// printed to the run log. Setups the policy does not allow are an error.
+pub fn wave_substeps(field: &str, c: f64, dt: f64, dx: f64, stability: &Stability) -> Result<usize, SimError> {
+    let number = courant_number(c, dt, dx);
+    substeps(&format!("{} waves", field), "c·dt/dx", number, COURANT_LIMIT, stability)
+}
+
+/// Substeps per time step needed to advance the fluxes of `field` stably in
+/// 3D, for signals no faster than `c`, printed to the run log. Setups the
+/// policy does not allow are an error.
+pub fn flux_substeps(field: &str, c: f64, dt: f64, dx: f64, stability: &Stability) -> Result<usize, SimError> {
+    let number = 3.0 * courant_number(c, dt, dx);
+    substeps(&format!("{} fluxes", field), "3·c·dt/dx", number, CFL_LIMIT, stability)
+}
+
+/// Substeps bringing stability number `number`, which scales with the
+/// step, below `limit`.
+fn substeps(what: &str, symbol: &str, number: f64, limit: f64, stability: &Stability) -> Result<usize, SimError> {
+    let unstable = |hint: String| SimError::Unstable { what: what.to_string(), number, limit, hint };
+    let substeps = if number <= limit {
+        1
+    } else if stability.policy == StabilityPolicy::Refuse {
+        return Err(unstable("reduce dt or set stability.policy = \"substep\"".into()));
+    } else {
+        let needed = (number / limit).ceil();
+        if needed.is_nan() || needed > stability.max_substeps as f64 {
+            return Err(unstable(format!(
+                "substepping would need {:.3e} substeps per step, more than stability.max_substeps = {}",
+                needed, stability.max_substeps
+            )));
+        }
+        needed as usize
+    };
+    println!(
+        "stability: {} {} = {:.3e} (limit {:.3e}), {} substep(s) per step",
+        what, symbol, number, limit, substeps
+    );
+    Ok(substeps)
+}
@@ -0,0 +1,75 @@
+//! Finite-difference stencils on `Grid3<f64>`. The cell spacing is taken
+//! from the grid itself and off-grid neighbours from its [`Boundaries`].
+
+use crate::boundary::Boundaries;
+use crate::grid::Grid3;
+
+/// 7-point Laplacian at `(x, y, z)`, with neighbours past the edge of the
+/// grid supplied by `bc`.
+pub fn laplacian(f: &Grid3<f64>, bc: &Boundaries, x: usize, y: usize, z: usize) -> f64 {
+    let dx = f.spacing();
+    let c = f[(x, y, z)];
+    let mut sum = 0.0;
+    for axis in 0..3 {
+        sum += bc.neighbor(f, x, y, z, axis, 1) + bc.neighbor(f, x, y, z, axis, -1);
+    }
+    (sum - 6.0 * c) / (dx * dx)
+}
+
+/// A symmetric rank-2 tensor in Cartesian components, e.g. a metric
+/// perturbation h_ij.
+pub type Tensor = [[f64; 3]; 3];
+
+/// ∂_i (K_ij ∂_j f) at `(x, y, z)` for the symmetric tensor field `k` given
+/// on the cells of `f`. The diagonal terms are face fluxes with K averaged
+/// onto the face, so on a periodic grid they sum to zero; the off-diagonal
+/// ones are central differences of K_ij ∂_j f at the neighbours. With
+/// K = δ this is [`laplacian`].
+pub fn tensor_laplacian(f: &Grid3<f64>, k: &Grid3<Tensor>, bc: &Boundaries, x: usize, y: usize, z: usize) -> f64 {
+    let dx = f.spacing();
+    let dims = f.dims();
+    let c = f[(x, y, z)];
+    let k0 = &k[(x, y, z)];
+    let mut sum = 0.0;
+    for a in 0..3 {
+        for dir in [1, -1] {
+            // Past a Dirichlet face K keeps its value at the cell.
+            let neighbor = bc.neighbor_cell(dims, x, y, z, a, dir).ok();
+            let kn = neighbor.map_or(k0, |cell| &k[cell]);
+            sum += 0.5 * (k0[a][a] + kn[a][a]) * (bc.neighbor(f, x, y, z, a, dir) - c);
+            // A fixed ghost value has no gradient along the face.
+            let Some((i, j, l)) = neighbor else { continue };
+            for b in (0..3).filter(|&b| b != a && kn[a][b] != 0.0) {
+                let gradient = 0.5 * (bc.neighbor(f, i, j, l, b, 1) - bc.neighbor(f, i, j, l, b, -1));
+                sum += 0.5 * dir as f64 * kn[a][b] * gradient;
+            }
+        }
+    }
+    sum / (dx * dx)
+}
+
+/// Change of `f` over `dt` under explicit diffusion ∂f/∂t = d ∇²f, taken as
+/// `substeps` forward-Euler steps of `dt / substeps` (see
+/// [`crate::stability::diffusion_substeps`]). With one substep this is
+/// exactly `d * ∇²f * dt` in every cell.
+pub fn diffusion_increment(f: &Grid3<f64>, bc: &Boundaries, d: f64, dt: f64, substeps: usize) -> Grid3<f64> {
+    if substeps <= 1 {
+        let mut inc = Grid3::filled_like(f, 0.0);
+        for (x, y, z) in f.cells() {
+            inc[(x, y, z)] = d * laplacian(f, bc, x, y, z) * dt;
+        }
+        return inc;
+    }
+    let h = dt / substeps as f64;
+    let mut g = f.clone();
+    for _ in 0..substeps {
+        let inc = diffusion_increment(&g, bc, d, h, 1);
+        for (v, dv) in g.iter_mut().zip(inc.iter()) {
+            *v += dv;
+        }
+    }
+    for (v, v0) in g.iter_mut().zip(f.iter()) {
+        *v -= v0;
+    }
+    g
+}
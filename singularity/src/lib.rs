@@ -0,0 +1,336 @@
+use std::path::{Path, PathBuf};
+
+use physics_core::config::{at_least, non_negative, positive, GridConfig, RunConfig, Validate};
+use physics_core::diffusion::CgSettings;
+use physics_core::error::{check_finite, SimError};
+use physics_core::io::{write_npy, CsvWriter};
+use physics_core::ledger::{Conservation, Process};
+use physics_core::ode::{Attempt, DormandPrince, OdeSettings, Outcome};
+use physics_core::species::{Mixture, Reaction, Species, Splitting};
+use physics_core::stability::Stability;
+use physics_core::Grid3;
+use serde::{Deserialize, Serialize};
+
+mod torsion;
+
+use torsion::{Torsion, TorsionSettings};
+
+//----------------------------------------------
+// RUN CONFIGURATION
+//----------------------------------------------
+// Defaults are the values this simulation has always run with; pass a TOML
+// or JSON file with `--config` to override any of them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    g_a_gamma: f64,                // Axion-photon coupling constant
+    torsion_scalar: f64,           // Torsion strength (arbitrary scaling)
+    torsion: TorsionSettings,      // Initial profile and evolution of the torsion field
+    photon_to_neutrino_coeff: f64, // Photon to neutrino conversion efficiency
+    dt: f64,                       // Time step (s)
+    steps: usize,                  // Total simulation steps
+    // Particle densities
+    photon_init: f64,
+    axion_init: f64,
+    neutrino_init: f64,
+    integrator: Integrator,        // How the rate equations are stepped
+    ode: OdeSettings,              // Tolerances of the adaptive integrator
+    step_log: String,              // Adaptive steps per time step over all cells, relative to out_dir
+    splitting: Splitting,          // Order of transport and reactions in a step
+    conservation: Conservation,    // Per-step totals and drift of the particle number
+    stability: Stability,
+    cg: CgSettings,
+    seed: u64,                     // For the random torsion profile
+    out_dir: PathBuf,
+    results_file: String,
+    data_dir: String,              // npy snapshots, relative to out_dir
+    grid: GridConfig,              // Lattice size and spatial resolution (m)
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            g_a_gamma: 1e-7,
+            torsion_scalar: 1e-4,
+            torsion: TorsionSettings::default(),
+            photon_to_neutrino_coeff: 1e-10,
+            dt: 1.22e-17, //22
+            steps: 100,
+            photon_init: 1e38,
+            axion_init: 1e32,
+            neutrino_init: 1e35,
+            integrator: Integrator::DormandPrince,
+            ode: OdeSettings::default(),
+            step_log: "step_history.csv".into(),
+            splitting: Splitting::Lie,
+            conservation: Conservation::default(),
+            stability: Stability::default(),
+            cg: CgSettings::default(),
+            seed: 42,
+            out_dir: PathBuf::from("."),
+            results_file: "results_with_torsion.csv".into(),
+            data_dir: "data".into(),
+            grid: GridConfig::new(7, 7, 7, 0.5e-15),
+        }
+    }
+}
+
+impl Validate for Config {
+    fn validate(&self) -> Result<(), String> {
+        non_negative("g_a_gamma", self.g_a_gamma)?;
+        non_negative("torsion_scalar", self.torsion_scalar)?;
+        non_negative("photon_to_neutrino_coeff", self.photon_to_neutrino_coeff)?;
+        positive("dt", self.dt)?;
+        at_least("steps", self.steps, 1)?;
+        non_negative("photon_init", self.photon_init)?;
+        non_negative("axion_init", self.axion_init)?;
+        non_negative("neutrino_init", self.neutrino_init)?;
+        self.torsion.validate()?;
+        self.ode.validate()?;
+        self.conservation.validate()?;
+        self.stability.validate()?;
+        self.cg.validate()?;
+        self.grid.validate()
+    }
+}
+
+/// Integrator of the per-cell rate equations over each step of `dt`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Integrator {
+    /// One forward-Euler step, with negative densities clamped to zero. It
+    /// overshoots once a rate times dt exceeds the density it drains.
+    Fixed,
+    /// Adaptive Dormand–Prince steps within the tolerances of `ode`,
+    /// rejecting any that would make a density negative.
+    DormandPrince,
+}
+
+impl RunConfig for Config {
+    fn out_dir(&self) -> &Path {
+        &self.out_dir
+    }
+
+    fn set_out_dir(&mut self, dir: PathBuf) {
+        self.out_dir = dir;
+    }
+
+    fn set_steps(&mut self, steps: usize) {
+        self.steps = steps;
+    }
+
+    fn set_seed(&mut self, seed: u64) -> Result<(), String> {
+        self.seed = seed;
+        Ok(())
+    }
+}
+
+//----------------------------------------------
+// FIELD STRUCTURE
+//----------------------------------------------
+// Indices into the species mixture, in the order the species are added.
+const PHOTON: usize = 0;
+const AXION: usize = 1;
+const NEUTRINO: usize = 2;
+
+struct Field {
+    species: Mixture,
+    torsion: Torsion,
+    /// Neutrino density each cell has gained through the torsion channel.
+    converted: Grid3<f64>,
+}
+
+impl Field {
+    fn new(cfg: &Config) -> Result<Self, SimError> {
+        let mut species = Mixture::new(cfg.splitting);
+        species.add(Species::new("photon", cfg.grid.filled(cfg.photon_init)));
+        species.add(Species::new("axion", cfg.grid.filled(cfg.axion_init)));
+        species.add(Species::new("neutrino", cfg.grid.filled(cfg.neutrino_init)));
+        let torsion =
+            Torsion::new(&cfg.torsion, cfg.torsion_scalar, &cfg.grid, cfg.seed, cfg.dt, &cfg.stability, &cfg.cg)?;
+        Ok(Field { species, torsion, converted: cfg.grid.filled(0.0) })
+    }
+}
+
+/// Rate of the torsion channel at torsion `t`: photons to neutrinos where it
+/// is positive, neutrinos back to photons (a negative rate) where it is not,
+/// in proportion to the density drained.
+fn torsion_rate(cfg: &Config, n_photon: f64, n_neutrino: f64, t: f64) -> f64 {
+    let drained = if t >= 0.0 { n_photon } else { n_neutrino };
+    cfg.photon_to_neutrino_coeff * drained * t
+}
+
+fn reactions<'a>(cfg: &'a Config, torsion: &'a Grid3<f64>) -> [Reaction<'a>; 2] {
+    [
+        // Photon to neutrino conversion via torsion
+        Reaction::new("photon → neutrino", move |n, i| torsion_rate(cfg, n[PHOTON], n[NEUTRINO], torsion[i]))
+            .consumes(PHOTON)
+            .produces(NEUTRINO),
+        // Axion to photon conversion
+        Reaction::new("axion → photon", |n, _| cfg.g_a_gamma * n[AXION])
+            .consumes(AXION)
+            .produces(PHOTON),
+    ]
+}
+
+/// Per-cell torsion and the conversion it has driven so far, tagged by `tag`.
+fn write_snapshot(data_dir: &Path, tag: &str, field: &Field) -> Result<(), SimError> {
+    write_npy(data_dir.join(format!("torsion_{}.npy", tag)), &field.torsion.field)?;
+    write_npy(data_dir.join(format!("conversion_{}.npy", tag)), &field.converted)
+}
+
+//----------------------------------------------
+// MAIN TIME EVOLUTION
+//----------------------------------------------
+/// Evolve the densities and the torsion for `steps` steps, writing the
+/// lattice averages to `results_file` in `out_dir` and the per-cell torsion
+/// and conversion to `data_dir`.
+pub fn run(cfg: &Config) -> Result<(), SimError> {
+    let dt = cfg.dt;
+
+    let mut field = Field::new(cfg)?;
+    let results_path = cfg.out_dir.join(&cfg.results_file);
+    let mut file = CsvWriter::create(
+        &results_path,
+        &[
+            "time(s)",
+            "avg_photon_density",
+            "avg_axion_density",
+            "avg_neutrino_density",
+            "avg_torsion",
+            "avg_conversion_rate",
+        ],
+    )?;
+    let data_dir = cfg.out_dir.join(&cfg.data_dir);
+    std::fs::create_dir_all(&data_dir).map_err(SimError::io(&data_dir))?;
+
+    // One integrator per cell, each keeping its own step size
+    let cells = field.converted.len();
+    let mut odes = vec![DormandPrince::new("singularity rate equations", &cfg.ode).non_negative(); cells];
+    let step_log_path = cfg.out_dir.join(&cfg.step_log);
+    let mut step_log = match cfg.integrator {
+        Integrator::DormandPrince => Some(CsvWriter::create(
+            &step_log_path,
+            &[
+                "time(s)",
+                "accepted",
+                "retried",
+                "shortest_step(s)",
+                "longest_step(s)",
+                "max_error_norm",
+                "busiest_cell",
+                "busiest_cell_attempts",
+            ],
+        )?),
+        Integrator::Fixed => None,
+    };
+    let mut cell_attempts = vec![0usize; cells];
+    let (mut accepted, mut retried) = (0usize, 0usize);
+    let (mut shortest, mut longest) = (f64::INFINITY, 0.0f64);
+
+    if cfg.conservation.enabled {
+        let path = cfg.out_dir.join(&cfg.conservation.file);
+        field.species.track(path, &[("number", &[PHOTON, AXION, NEUTRINO])], cfg.conservation.tolerance)?;
+    }
+
+    for step in 0..cfg.steps {
+        let t = step as f64 * dt;
+
+        // Neutrinos only change through the torsion channel, so what it
+        // converts this step is their change over the reaction update
+        let neutrinos_before = field.species[NEUTRINO].density.clone();
+
+        // Update fields
+        {
+            let reactions = reactions(cfg, &field.torsion.field);
+            match &mut step_log {
+                Some(log) => {
+                    // The attempts of all cells over this step, summarised in one row
+                    let (mut step_accepted, mut step_retried) = (0usize, 0usize);
+                    let (mut step_shortest, mut step_longest, mut max_error) = (f64::INFINITY, 0.0f64, 0.0f64);
+                    cell_attempts.iter_mut().for_each(|n| *n = 0);
+                    let on_attempt = |cell: usize, attempt: &Attempt| {
+                        cell_attempts[cell] += 1;
+                        match attempt.outcome {
+                            Outcome::Accepted => {
+                                step_accepted += 1;
+                                step_shortest = step_shortest.min(attempt.h);
+                                step_longest = step_longest.max(attempt.h);
+                                max_error = max_error.max(attempt.error);
+                            }
+                            Outcome::Rejected | Outcome::Negative => step_retried += 1,
+                        }
+                        Ok(())
+                    };
+                    field.species.apply(Process::Reaction, |m| m.react_adaptive(dt, &reactions, &mut odes, on_attempt))?;
+                    let (busiest, attempts) =
+                        cell_attempts.iter().enumerate().rev().max_by_key(|&(_, n)| *n).map_or((0, 0), |(i, n)| (i, *n));
+                    log.record(&[
+                        format!("{}", t),
+                        step_accepted.to_string(),
+                        step_retried.to_string(),
+                        format!("{}", step_shortest),
+                        format!("{}", step_longest),
+                        format!("{}", max_error),
+                        busiest.to_string(),
+                        attempts.to_string(),
+                    ])?;
+                    accepted += step_accepted;
+                    retried += step_retried;
+                    shortest = shortest.min(step_shortest);
+                    longest = longest.max(step_longest);
+                }
+                // Negative densities are clamped to zero
+                None => field.species.step(dt, &reactions)?,
+            }
+        }
+
+        let mut conversion = 0.0;
+        let neutrinos = &field.species[NEUTRINO].density;
+        for ((c, after), before) in field.converted.iter_mut().zip(neutrinos.iter()).zip(neutrinos_before.iter()) {
+            *c += after - before;
+            conversion += after - before;
+        }
+
+        // Evolve the torsion, sourced by the new photon density
+        field.torsion.step(&field.species[PHOTON].density)?;
+
+        // Calculate averages
+        let avg_photon = field.species[PHOTON].density.mean();
+        let avg_axion = field.species[AXION].density.mean();
+        let avg_neutrino = field.species[NEUTRINO].density.mean();
+        let avg_torsion = field.torsion.field.mean();
+        let avg_rate = conversion / field.converted.len() as f64 / dt;
+
+        file.row(&[t, avg_photon, avg_axion, avg_neutrino, avg_torsion, avg_rate])?;
+        for s in field.species.species() {
+            check_finite(&format!("{} density", s.name), step, &s.density)?;
+        }
+        check_finite("torsion", step, &field.torsion.field)?;
+        if cfg.torsion.snapshot_interval > 0 && (step + 1) % cfg.torsion.snapshot_interval == 0 {
+            write_snapshot(&data_dir, &format!("{:06}", step + 1), &field)?;
+        }
+        field.species.end_step(step, t)?;
+    }
+    file.finish()?;
+    if let Some(log) = step_log {
+        log.finish()?;
+        println!(
+            "dormand-prince: {} steps accepted, {} retried, step {:.3e} to {:.3e} s; history in {}",
+            accepted,
+            retried,
+            shortest,
+            longest,
+            step_log_path.display()
+        );
+    }
+    field.species.close_ledger()?;
+    write_snapshot(&data_dir, "final", &field)?;
+
+    println!(
+        "Simulation complete. Results saved to {} and per-cell torsion and conversion to {}",
+        results_path.display(),
+        data_dir.display()
+    );
+    Ok(())
+}
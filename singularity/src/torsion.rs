@@ -0,0 +1,267 @@
+//! The torsion field: its initial profile and its evolution.
+//!
+//! Torsion T(x, t) drives the photon → neutrino conversion at the rate
+//! `photon_to_neutrino_coeff · n_γ · T`, cell by cell. With `dynamics =
+//! "static"` it keeps its initial profile; otherwise it evolves as
+//!
+//! ```text
+//! diffusion:  ∂T/∂t   = D ∇²T − γ (T − T₀) + κ n_γ
+//! wave:       ∂²T/∂t² = c² ∇²T − γ ∂T/∂t + κ n_γ
+//! ```
+//!
+//! with T₀ = `torsion_scalar`, so that the photons source it back. The
+//! diffusion equation relaxes towards T₀ + κ n_γ / γ; the wave starts at rest.
+//! Where T turns negative the channel runs backwards, neutrinos to photons
+//! at `photon_to_neutrino_coeff · n_ν · |T|`.
+
+use std::f64::consts::PI;
+
+use physics_core::config::{non_negative, positive, GridConfig, Validate};
+use physics_core::diffusion::{CgSettings, DiffusionScheme, Diffuser};
+use physics_core::error::SimError;
+use physics_core::stability::{wave_substeps, Stability};
+use physics_core::stencil::laplacian;
+use physics_core::{Boundaries, Grid3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Initial torsion, in units of `torsion_scalar`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Profile {
+    /// 1 everywhere.
+    #[default]
+    Uniform,
+    /// The sum of `blobs`.
+    Gaussian,
+    /// 1 + `spread` · U(−1, 1), independently per cell, from `seed`.
+    Random,
+    /// sin(2πx̂) cos(2πŷ) exp(−ẑ) over the box coordinates x̂, ŷ, ẑ ∈ [0, 1],
+    /// the pattern `star-search` writes.
+    StarSearch,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dynamics {
+    #[default]
+    Static,
+    Diffusion,
+    Wave,
+}
+
+/// One Gaussian of the `gaussian` profile.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Blob {
+    /// Centre, as fractions of the box along x, y, z.
+    pub center: [f64; 3],
+    /// Standard deviation (m).
+    pub width: f64,
+    /// Peak, in units of `torsion_scalar`.
+    pub amplitude: f64,
+}
+
+impl Default for Blob {
+    fn default() -> Self {
+        Blob { center: [0.5; 3], width: 1e-15, amplitude: 1.0 }
+    }
+}
+
+/// The `torsion` table of the config.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TorsionSettings {
+    pub profile: Profile,
+    pub blobs: Vec<Blob>,
+    pub spread: f64,
+    pub dynamics: Dynamics,
+    pub diffusivity: f64, // D (m²/s)
+    pub scheme: DiffusionScheme,
+    pub speed: f64,       // c (m/s); light speed needs a far smaller dt than the default
+    pub damping: f64,     // γ (1/s)
+    pub source: f64,      // κ, torsion per photon density per second
+    pub bc: Boundaries,
+    /// Steps between per-cell snapshots of the torsion and the conversion it
+    /// drove; 0 writes only the final ones.
+    pub snapshot_interval: usize,
+}
+
+impl Default for TorsionSettings {
+    fn default() -> Self {
+        TorsionSettings {
+            profile: Profile::Uniform,
+            blobs: vec![Blob::default()],
+            spread: 0.5,
+            dynamics: Dynamics::Static,
+            diffusivity: 1e-15,
+            scheme: DiffusionScheme::Explicit,
+            speed: 10.0,
+            damping: 0.0,
+            source: 0.0,
+            bc: Boundaries::neumann(),
+            snapshot_interval: 0,
+        }
+    }
+}
+
+impl Validate for TorsionSettings {
+    fn validate(&self) -> Result<(), String> {
+        for blob in &self.blobs {
+            positive("torsion.blobs.width", blob.width)?;
+            if !blob.amplitude.is_finite() || blob.center.iter().any(|c| !c.is_finite()) {
+                return Err("torsion.blobs must have finite centers and amplitudes".into());
+            }
+        }
+        if self.profile == Profile::Gaussian && self.blobs.is_empty() {
+            return Err("torsion.profile = \"gaussian\" needs at least one torsion.blobs entry".into());
+        }
+        non_negative("torsion.spread", self.spread)?;
+        non_negative("torsion.diffusivity", self.diffusivity)?;
+        positive("torsion.speed", self.speed)?;
+        non_negative("torsion.damping", self.damping)?;
+        if !self.source.is_finite() {
+            return Err(format!("torsion.source must be finite, got {}", self.source));
+        }
+        self.bc.validate()
+    }
+}
+
+/// Box coordinate of cell `i` of `n` in [0, 1]; 0 on a single cell.
+fn unit(i: usize, n: usize) -> f64 {
+    if n > 1 {
+        i as f64 / (n - 1) as f64
+    } else {
+        0.0
+    }
+}
+
+/// The initial torsion on `grid`, scaled by `background`.
+pub fn initial_profile(settings: &TorsionSettings, background: f64, grid: &GridConfig, seed: u64) -> Grid3<f64> {
+    let (nx, ny, nz) = (grid.nx, grid.ny, grid.nz);
+    let extent = [nx, ny, nz].map(|n| n.saturating_sub(1) as f64 * grid.dx);
+    let mut rng = StdRng::seed_from_u64(seed);
+    Grid3::from_fn(nx, ny, nz, |x, y, z| {
+        let r = [unit(x, nx), unit(y, ny), unit(z, nz)];
+        let shape = match settings.profile {
+            Profile::Uniform => 1.0,
+            Profile::Gaussian => settings
+                .blobs
+                .iter()
+                .map(|blob| {
+                    let d2: f64 = (0..3).map(|a| ((r[a] - blob.center[a]) * extent[a]).powi(2)).sum();
+                    blob.amplitude * (-d2 / (2.0 * blob.width * blob.width)).exp()
+                })
+                .sum(),
+            Profile::Random => 1.0 + settings.spread * rng.random_range(-1.0..=1.0),
+            Profile::StarSearch => (2.0 * PI * r[0]).sin() * (2.0 * PI * r[1]).cos() * (-r[2]).exp(),
+        };
+        background * shape
+    })
+    .with_spacing(grid.dx)
+}
+
+pub struct Torsion {
+    pub field: Grid3<f64>,
+    /// ∂T/∂t, evolved by the wave equation.
+    velocity: Grid3<f64>,
+    settings: TorsionSettings,
+    background: f64,
+    dt: f64,
+    diffuser: Option<Diffuser>,
+    /// Wave substeps per time step.
+    substeps: usize,
+}
+
+impl Torsion {
+    /// Torsion with the configured profile, checked for stable stepping by
+    /// `dt`.
+    pub fn new(
+        settings: &TorsionSettings,
+        background: f64,
+        grid: &GridConfig,
+        seed: u64,
+        dt: f64,
+        stability: &Stability,
+        cg: &CgSettings,
+    ) -> Result<Self, SimError> {
+        let field = initial_profile(settings, background, grid, seed);
+        let mut diffuser = None;
+        let mut substeps = 1;
+        match settings.dynamics {
+            Dynamics::Static => {}
+            Dynamics::Diffusion => {
+                diffuser = Some(
+                    Diffuser::new("torsion", settings.scheme, settings.diffusivity, dt, cg).checked(grid.dx, stability)?,
+                );
+            }
+            Dynamics::Wave => substeps = wave_substeps("torsion", settings.speed, dt, grid.dx, stability)?,
+        }
+        Ok(Torsion {
+            velocity: Grid3::filled_like(&field, 0.0),
+            field,
+            settings: settings.clone(),
+            background,
+            dt,
+            diffuser,
+            substeps,
+        })
+    }
+
+    /// Advance the torsion by one time step, sourced by `photons`.
+    pub fn step(&mut self, photons: &Grid3<f64>) -> Result<(), SimError> {
+        match self.settings.dynamics {
+            Dynamics::Static => Ok(()),
+            Dynamics::Diffusion => self.diffuse(photons),
+            Dynamics::Wave => {
+                self.propagate(photons);
+                Ok(())
+            }
+        }
+    }
+
+    /// Diffusion by the configured scheme and the sponge layers, then
+    /// relaxation and source integrated exactly over the step at fixed
+    /// photon density.
+    fn diffuse(&mut self, photons: &Grid3<f64>) -> Result<(), SimError> {
+        if let Some(diffuser) = &self.diffuser {
+            let inc = diffuser.increment(&self.field, &self.settings.bc)?;
+            for (t, dt) in self.field.iter_mut().zip(inc.iter()) {
+                *t += dt;
+            }
+        }
+        self.settings.bc.apply_sponge(&mut self.field, self.dt);
+        let (gamma, kappa, dt) = (self.settings.damping, self.settings.source, self.dt);
+        let decay = (-gamma * dt).exp();
+        for (t, n) in self.field.iter_mut().zip(photons.iter()) {
+            if gamma > 0.0 {
+                let equilibrium = self.background + kappa * n / gamma;
+                *t = equilibrium + (*t - equilibrium) * decay;
+            } else {
+                *t += kappa * n * dt;
+            }
+        }
+        Ok(())
+    }
+
+    /// Symplectic Euler substeps: the velocity from the current field, with
+    /// the damping implicit, then the field from the new velocity.
+    fn propagate(&mut self, photons: &Grid3<f64>) {
+        let h = self.dt / self.substeps as f64;
+        let (c2, gamma, kappa) = (self.settings.speed.powi(2), self.settings.damping, self.settings.source);
+        for _ in 0..self.substeps {
+            for (x, y, z) in self.field.cells() {
+                let i = self.field.idx(x, y, z);
+                let force = c2 * laplacian(&self.field, &self.settings.bc, x, y, z) + kappa * photons[i];
+                self.velocity[i] = (self.velocity[i] + h * force) / (1.0 + gamma * h);
+            }
+            for (t, v) in self.field.iter_mut().zip(self.velocity.iter()) {
+                *t += h * v;
+            }
+            // Absorbing layers damp the wave, field and velocity alike.
+            self.settings.bc.apply_sponge(&mut self.field, h);
+            self.settings.bc.apply_sponge(&mut self.velocity, h);
+        }
+    }
+}
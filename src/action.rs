@@ -0,0 +1,133 @@
+//! Local actions of the scalar lattice.
+//!
+//! Every model has N real components per site, the nearest-neighbour
+//! kinetic term Σ_bonds |φ_x - φ_y|²/2 and an on-site potential V(φ) given
+//! by its [`Action`]:
+//!
+//! * `free`: V = m² φ²/2, one component;
+//! * `phi4`: V = m² φ²/2 + λ φ⁴/4!, one component, with a Z₂ symmetry
+//!   that breaks for m² < 0 and small enough λ;
+//! * `o_n`: V = m² |φ|²/2 + λ |φ|⁴/4! for an N-component field;
+//! * `u1`: a complex field with V = m² |φ|² + λ |φ|⁴, stored as
+//!   φ = (φ₁ + i φ₂)/√2, so that its kinetic term is the same as that of
+//!   two real components.
+//!
+//! Here m² is `mass_sq` and λ is `coupling`.
+
+use serde::{Deserialize, Serialize};
+
+/// The `model` key of the config.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelKind {
+    #[default]
+    Free,
+    Phi4,
+    #[serde(rename = "o_n")]
+    On,
+    U1,
+}
+
+/// On-site part of a lattice action.
+pub trait Action: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Real components per site.
+    fn components(&self) -> usize;
+
+    /// Coefficient m² of the quadratic part m² |φ|²/2 of the potential.
+    fn mass_sq(&self) -> f64;
+
+    /// Potential V(φ) of one site.
+    fn potential(&self, phi: &[f64]) -> f64;
+
+    /// Gradient ∂V/∂φ_a of one site into `out`.
+    fn gradient(&self, phi: &[f64], out: &mut [f64]);
+
+    /// V(φ) - m² |φ|²/2: what a Gaussian proposal leaves out.
+    fn interaction(&self, phi: &[f64]) -> f64 {
+        self.potential(phi) - 0.5 * self.mass_sq() * norm_sq(phi)
+    }
+}
+
+pub fn norm_sq(phi: &[f64]) -> f64 {
+    phi.iter().map(|v| v * v).sum()
+}
+
+/// The action `model` asks for, with mass `mass_sq` and coupling
+/// `coupling`; `components` is used by `o_n` only.
+pub fn from_model(model: ModelKind, components: usize, mass_sq: f64, coupling: f64) -> Box<dyn Action> {
+    match model {
+        ModelKind::Free => Box::new(ONModel { name: "free", n: 1, mass_sq, lambda: 0.0 }),
+        ModelKind::Phi4 => Box::new(ONModel { name: "phi4", n: 1, mass_sq, lambda: coupling }),
+        ModelKind::On => Box::new(ONModel { name: "o_n", n: components, mass_sq, lambda: coupling }),
+        ModelKind::U1 => Box::new(ComplexScalar { mass_sq, lambda: coupling }),
+    }
+}
+
+/// V = m² |φ|²/2 + λ |φ|⁴/4! for N real components.
+pub struct ONModel {
+    name: &'static str,
+    n: usize,
+    mass_sq: f64,
+    lambda: f64,
+}
+
+impl Action for ONModel {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn components(&self) -> usize {
+        self.n
+    }
+
+    fn mass_sq(&self) -> f64 {
+        self.mass_sq
+    }
+
+    fn potential(&self, phi: &[f64]) -> f64 {
+        let r2 = norm_sq(phi);
+        0.5 * self.mass_sq * r2 + self.lambda / 24.0 * r2 * r2
+    }
+
+    fn gradient(&self, phi: &[f64], out: &mut [f64]) {
+        let r2 = norm_sq(phi);
+        for (g, p) in out.iter_mut().zip(phi) {
+            *g = (self.mass_sq + self.lambda / 6.0 * r2) * p;
+        }
+    }
+}
+
+/// V = m² |φ|² + λ |φ|⁴ for a complex φ = (φ₁ + i φ₂)/√2.
+pub struct ComplexScalar {
+    mass_sq: f64,
+    lambda: f64,
+}
+
+impl Action for ComplexScalar {
+    fn name(&self) -> &'static str {
+        "u1"
+    }
+
+    fn components(&self) -> usize {
+        2
+    }
+
+    fn mass_sq(&self) -> f64 {
+        self.mass_sq
+    }
+
+    fn potential(&self, phi: &[f64]) -> f64 {
+        // |φ|² of the complex field is half the sum of the real components.
+        let modulus_sq = 0.5 * norm_sq(phi);
+        self.mass_sq * modulus_sq + self.lambda * modulus_sq * modulus_sq
+    }
+
+    fn gradient(&self, phi: &[f64], out: &mut [f64]) {
+        let modulus_sq = 0.5 * norm_sq(phi);
+        for (g, p) in out.iter_mut().zip(phi) {
+            *g = (self.mass_sq + 2.0 * self.lambda * modulus_sq) * p;
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Red/black checkerboard sweeps, parallel over threads.
+//!
+//! On a lattice with even extents every neighbour of a site has the other
+//! colour, (Σ_μ x_μ) mod 2, so all sites of one colour can be updated at
+//! once from the fixed sites of the other. A sweep updates the red sites,
+//! then the black ones, each colour in blocks of [`BLOCK`] sites spread
+//! over the threads.
+//!
+//! Every block draws from its own ChaCha8 stream, keyed by the run's seed
+//! and addressed by counters: the stream by the pass and the colour, the
+//! position within it by the block. Which thread handles a block does not
+//! enter, so the chain is bit for bit the same for any `parallel.threads`.
+
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::Lattice;
+
+/// The `parallel` table of the config.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ParallelSettings {
+    /// Sweep the local updaters in red/black order over threads instead of
+    /// site by site from the lattice's generator.
+    pub checkerboard: bool,
+    /// Worker threads; 0 uses one per core.
+    pub threads: usize,
+}
+
+/// Sites per block, the unit of work and of random streams.
+pub const BLOCK: usize = 256;
+
+/// Each block's stream starts 2^32 words after the previous one, far more
+/// than its sites can draw.
+const BLOCK_WORDS_LOG2: u32 = 32;
+
+/// Update of one site: propose a new value of site `i` into
+/// the last argument, drawing from the generator, and tell whether it is
+/// accepted.
+pub trait SiteUpdate: Fn(&Lattice, usize, &mut dyn RngCore, &mut [f64]) -> bool + Sync {}
+
+impl<F: Fn(&Lattice, usize, &mut dyn RngCore, &mut [f64]) -> bool + Sync> SiteUpdate for F {}
+
+pub struct Checkerboard {
+    /// Sites of each colour, empty until the first pass.
+    colors: [Vec<usize>; 2],
+    key: ChaCha8Rng,
+    /// Passes over the whole lattice so far.
+    passes: u64,
+    pool: ThreadPool,
+}
+
+impl Checkerboard {
+    pub fn new(settings: &ParallelSettings, seed: u64) -> Self {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(settings.threads)
+            .build()
+            .expect("failed to start the checkerboard worker threads");
+        Checkerboard { colors: [Vec::new(), Vec::new()], key: ChaCha8Rng::seed_from_u64(seed), passes: 0, pool }
+    }
+
+    /// Passes over the whole lattice so far, which address the streams.
+    pub fn passes(&self) -> u64 {
+        self.passes
+    }
+
+    pub fn set_passes(&mut self, passes: u64) {
+        self.passes = passes;
+    }
+
+    /// The generator of `block` of `color` in the current pass.
+    fn stream(&self, color: usize, block: usize) -> ChaCha8Rng {
+        let mut rng = self.key.clone();
+        rng.set_stream(2 * self.passes + color as u64);
+        rng.set_word_pos((block as u128) << BLOCK_WORDS_LOG2);
+        rng
+    }
+
+    /// Offer every site to `update` once, red then black. Returns the
+    /// number of accepted proposals.
+    pub fn pass(&mut self, lattice: &mut Lattice, update: impl SiteUpdate) -> usize {
+        if self.colors[0].is_empty() {
+            let mut coords = vec![0; lattice.dim()];
+            for i in 0..lattice.sites() {
+                lattice.geometry.coords(i, &mut coords);
+                self.colors[coords.iter().sum::<usize>() % 2].push(i);
+            }
+        }
+        let n = lattice.components();
+        let mut accepted = 0;
+        for color in 0..2 {
+            let sites = &self.colors[color];
+            let lattice_ref = &*lattice;
+            let blocks: Vec<(Vec<f64>, Vec<bool>)> = self.pool.install(|| {
+                sites
+                    .par_chunks(BLOCK)
+                    .enumerate()
+                    .map(|(b, block)| {
+                        let mut rng = self.stream(color, b);
+                        let mut values = vec![0.0; n * block.len()];
+                        let taken = block
+                            .iter()
+                            .zip(values.chunks_exact_mut(n))
+                            .map(|(&i, new)| update(lattice_ref, i, &mut rng, new))
+                            .collect();
+                        (values, taken)
+                    })
+                    .collect()
+            });
+            for (block, (values, taken)) in sites.chunks(BLOCK).zip(blocks) {
+                for ((&i, new), taken) in block.iter().zip(values.chunks_exact(n)).zip(taken) {
+                    if taken {
+                        lattice.set_site(i, new);
+                        accepted += 1;
+                    }
+                }
+            }
+        }
+        self.passes += 1;
+        accepted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::ModelKind;
+    use crate::update::{self, UpdaterKind};
+    use crate::Config;
+
+    /// The field after `sweeps` checkerboard sweeps of `updater` on
+    /// `threads` worker threads.
+    fn field_after(updater: UpdaterKind, threads: usize, sweeps: usize) -> Vec<u64> {
+        let cfg = Config {
+            dim: 3,
+            lattice_size: 12,
+            model: ModelKind::Phi4,
+            coupling: 0.5,
+            mass_sq: -0.5,
+            updater,
+            parallel: ParallelSettings { checkerboard: true, threads },
+            seed: 11,
+            ..Config::default()
+        };
+        let mut lattice = Lattice::new(&cfg, 1.0);
+        let mut updater = update::from_config(&cfg);
+        for _ in 0..sweeps {
+            updater.sweep(&mut lattice);
+        }
+        lattice.field.iter().map(|v| v.to_bits()).collect()
+    }
+
+    #[test]
+    fn sweeps_do_not_depend_on_the_thread_count() {
+        // 12³ sites are several blocks of each colour.
+        for updater in [UpdaterKind::Metropolis, UpdaterKind::HeatBath, UpdaterKind::OverRelaxation] {
+            let serial = field_after(updater, 1, 10);
+            assert_eq!(serial, field_after(updater, 4, 10), "{:?}", updater);
+            assert_ne!(serial, field_after(updater, 1, 9), "{:?} left the field alone", updater);
+        }
+    }
+}
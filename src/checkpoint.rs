@@ -0,0 +1,272 @@
+//! Checkpoints of the Markov chain, and resuming from them.
+//!
+//! Every `checkpoint.interval` sweeps the run writes `checkpoint.file`: the
+//! field, the number of sweeps done, the full state of the chain's
+//! generator and of the updater, the measurements so far and the config it
+//! was started with. `--resume FILE` (or `checkpoint.resume`) continues that
+//! chain bit for bit, up to the `n_sweeps` of the new config, which may be
+//! larger than the original one.
+//!
+//! The file starts with [`MAGIC`] and a little-endian u32 format version,
+//! followed by the bincode encoding of a [`Checkpoint`]. It is written to a
+//! temporary file first and renamed into place, so an interrupted write
+//! leaves the previous checkpoint intact.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+
+use physics_core::config::{ConfigError, Validate};
+use physics_core::error::SimError;
+
+use crate::correlator::CorrelatorState;
+use crate::observables::SeriesState;
+use crate::update::UpdaterState;
+use crate::Config;
+
+/// The `checkpoint` table of the config.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CheckpointSettings {
+    /// Sweeps between checkpoints; 0 writes none.
+    pub interval: usize,
+    pub file: String,
+    /// Checkpoint to continue from.
+    pub resume: Option<PathBuf>,
+}
+
+impl Default for CheckpointSettings {
+    fn default() -> Self {
+        CheckpointSettings { interval: 0, file: "checkpoint.bin".into(), resume: None }
+    }
+}
+
+impl Validate for CheckpointSettings {
+    fn validate(&self) -> Result<(), String> {
+        if self.interval > 0 && self.file.is_empty() {
+            return Err("checkpoint.file must not be empty".into());
+        }
+        Ok(())
+    }
+}
+
+pub const MAGIC: &[u8; 8] = b"TBATHCKP";
+
+/// Bumped whenever [`Checkpoint`] changes.
+pub const VERSION: u32 = 1;
+
+/// Top-level config keys that may differ between a checkpoint and the run
+/// resuming it: they do not change the chain.
+const RESUMABLE_KEYS: [&str; 3] = ["n_sweeps", "out_dir", "checkpoint"];
+
+/// The keys of a config that determine the chain: all but
+/// [`RESUMABLE_KEYS`] and `parallel.threads`, which a checkerboard chain
+/// does not depend on.
+fn chain_keys(config: serde_json::Value) -> serde_json::Map<String, serde_json::Value> {
+    let mut table = match config {
+        serde_json::Value::Object(table) => table,
+        _ => serde_json::Map::new(),
+    };
+    for key in RESUMABLE_KEYS {
+        table.remove(key);
+    }
+    if let Some(serde_json::Value::Object(parallel)) = table.get_mut("parallel") {
+        parallel.remove("threads");
+    }
+    table
+}
+
+/// Everything needed to continue a run.
+#[derive(Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The run's config, as JSON.
+    pub config: String,
+    /// Sweeps done.
+    pub sweep: usize,
+    pub field: Vec<f64>,
+    pub rng: ChaCha12Rng,
+    pub updater: UpdaterState,
+    pub series: SeriesState,
+    pub correlator: Option<CorrelatorState>,
+}
+
+fn invalid_data(path: &Path, msg: String) -> SimError {
+    SimError::Io(path.to_path_buf(), io::Error::new(io::ErrorKind::InvalidData, msg))
+}
+
+impl Checkpoint {
+    pub fn write(&self, path: &Path) -> Result<(), SimError> {
+        let tmp = path.with_extension("tmp");
+        let mut file = io::BufWriter::new(fs::File::create(&tmp).map_err(SimError::io(&tmp))?);
+        file.write_all(MAGIC).map_err(SimError::io(&tmp))?;
+        file.write_all(&VERSION.to_le_bytes()).map_err(SimError::io(&tmp))?;
+        bincode::serialize_into(&mut file, self).map_err(|e| invalid_data(&tmp, e.to_string()))?;
+        file.flush().map_err(SimError::io(&tmp))?;
+        drop(file);
+        fs::rename(&tmp, path).map_err(SimError::io(path))
+    }
+
+    pub fn read(path: &Path) -> Result<Self, SimError> {
+        let mut file = io::BufReader::new(fs::File::open(path).map_err(SimError::io(path))?);
+        let mut magic = [0; 8];
+        let mut version = [0; 4];
+        file.read_exact(&mut magic).map_err(SimError::io(path))?;
+        if &magic != MAGIC {
+            return Err(invalid_data(path, "not a tbath checkpoint".into()));
+        }
+        file.read_exact(&mut version).map_err(SimError::io(path))?;
+        let version = u32::from_le_bytes(version);
+        if version != VERSION {
+            return Err(invalid_data(
+                path,
+                format!("checkpoint format version {}, this build reads version {}", version, VERSION),
+            ));
+        }
+        bincode::deserialize_from(file).map_err(|e| invalid_data(path, e.to_string()))
+    }
+
+    /// `Err` naming the keys of `cfg` that differ from the checkpointed
+    /// config in ways that would change the chain.
+    pub fn check_config(&self, cfg: &Config) -> Result<(), SimError> {
+        let invalid = |msg: String| SimError::Config(ConfigError::Invalid(msg));
+        let stored: serde_json::Value =
+            serde_json::from_str(&self.config).map_err(|e| invalid(format!("unreadable checkpoint config: {}", e)))?;
+        // Both sides go through the same text round trip, so that floats
+        // compare equal when they are.
+        let current = serde_json::to_string(cfg)
+            .and_then(|text| serde_json::from_str(&text))
+            .map_err(|e| invalid(e.to_string()))?;
+        let (stored, current) = (chain_keys(stored), chain_keys(current));
+        let differing: Vec<&str> =
+            current.iter().filter(|(key, value)| stored.get(*key) != Some(*value)).map(|(key, _)| key.as_str()).collect();
+        if !differing.is_empty() {
+            return Err(invalid(format!(
+                "the checkpoint was written with different {}; only {} may change on resume",
+                differing.join(", "),
+                RESUMABLE_KEYS.join(", ")
+            )));
+        }
+        if self.sweep > cfg.n_sweeps {
+            return Err(invalid(format!(
+                "the checkpoint is already {} sweeps in, beyond n_sweeps = {}",
+                self.sweep, cfg.n_sweeps
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observables::{MeasurementSettings, TimeSeries};
+    use crate::update::{self, MetropolisSettings, UpdaterKind};
+    use crate::Lattice;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tbath-checkpoint-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn config(updater: UpdaterKind, out_dir: PathBuf) -> Config {
+        Config {
+            dim: 2,
+            lattice_size: 8,
+            n_sweeps: 40,
+            updater,
+            // The checkpoint falls within the tuning of the Metropolis step.
+            metropolis: MetropolisSettings { adapt_sweeps: 20, ..MetropolisSettings::default() },
+            measurement: MeasurementSettings { thermalization: 5, ..MeasurementSettings::default() },
+            seed: 3,
+            out_dir,
+            ..Config::default()
+        }
+    }
+
+    /// A fresh chain of `cfg`, and its time series.
+    fn chain(cfg: &Config, series_file: &str) -> (Lattice, Box<dyn update::Updater>, TimeSeries) {
+        let lattice = Lattice::new(cfg, 1.5);
+        let series = TimeSeries::create(cfg.out_dir.join(series_file), &Lattice::OBSERVABLES).unwrap();
+        (lattice, update::from_config(cfg), series)
+    }
+
+    #[test]
+    fn resume_continues_the_chain_exactly() {
+        for kind in [UpdaterKind::Metropolis, UpdaterKind::Hmc] {
+            let dir = scratch(&format!("{:?}", kind));
+            let cfg = config(kind, dir.clone());
+
+            let (mut whole, mut whole_updater, mut whole_series) = chain(&cfg, "whole.csv");
+            whole.run(&cfg, 0, whole_updater.as_mut(), &mut whole_series, None).unwrap();
+
+            // Stop after 15 sweeps and continue from the checkpoint in a
+            // fresh lattice and updater.
+            let first = Config { n_sweeps: 15, ..cfg.clone() };
+            let (mut lattice, mut updater, mut series) = chain(&first, "first.csv");
+            lattice.run(&first, 0, updater.as_mut(), &mut series, None).unwrap();
+            let path = dir.join("checkpoint.bin");
+            lattice.checkpoint(&first, 15, updater.as_ref(), &series, None).write(&path).unwrap();
+
+            let checkpoint = Checkpoint::read(&path).unwrap();
+            checkpoint.check_config(&cfg).unwrap();
+            let (mut resumed, mut resumed_updater, mut resumed_series) = chain(&cfg, "resumed.csv");
+            let start = resumed.resume(checkpoint, resumed_updater.as_mut(), &mut resumed_series, None).unwrap();
+            assert_eq!(start, 15);
+            resumed.run(&cfg, start, resumed_updater.as_mut(), &mut resumed_series, None).unwrap();
+
+            let bits = |l: &Lattice| l.field.iter().map(|v| v.to_bits()).collect::<Vec<_>>();
+            assert_eq!(bits(&resumed), bits(&whole), "{:?}", kind);
+            assert!(resumed.rng == whole.rng);
+            assert_eq!(resumed_updater.state(), whole_updater.state());
+            let (a, b) = (resumed_series.state(), whole_series.state());
+            assert_eq!(a.sweeps, b.sweeps);
+            assert_eq!(a.values, b.values);
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn foreign_files_and_other_versions_are_rejected() {
+        let dir = scratch("format");
+        let path = dir.join("checkpoint.bin");
+
+        fs::write(&path, b"NOTACKPT\x01\x00\x00\x00").unwrap();
+        let err = Checkpoint::read(&path).err().unwrap().to_string();
+        assert!(err.contains("not a tbath checkpoint"), "{}", err);
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&(VERSION + 1).to_le_bytes());
+        fs::write(&path, bytes).unwrap();
+        let err = Checkpoint::read(&path).err().unwrap().to_string();
+        assert!(err.contains(&format!("version {}", VERSION + 1)), "{}", err);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn check_config_names_the_keys_that_change_the_chain() {
+        let cfg = config(UpdaterKind::Metropolis, PathBuf::from("."));
+        let lattice = Lattice::new(&cfg, 1.5);
+        let updater = update::from_config(&cfg);
+        let dir = scratch("config");
+        let series = TimeSeries::create(dir.join("series.csv"), &Lattice::OBSERVABLES).unwrap();
+        let checkpoint = lattice.checkpoint(&cfg, 30, updater.as_ref(), &series, None);
+
+        // More sweeps, another output directory and more threads are fine.
+        let mut resumable = Config { n_sweeps: 100, out_dir: dir.clone(), ..cfg.clone() };
+        resumable.parallel.threads = 4;
+        checkpoint.check_config(&resumable).unwrap();
+
+        let other = Config { seed: 4, mass_sq: 2.0, ..cfg.clone() };
+        let err = checkpoint.check_config(&other).err().unwrap().to_string();
+        assert!(err.contains("different mass_sq, seed;"), "{}", err);
+
+        let short = Config { n_sweeps: 20, ..cfg };
+        let err = checkpoint.check_config(&short).err().unwrap().to_string();
+        assert!(err.contains("beyond n_sweeps = 20"), "{}", err);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,471 @@
+//! Two-point functions of the lattice field and masses from them.
+//!
+//! With axis 0 as Euclidean time and Φ_a(t) = Σ_x φ_a(t, x) the sum of a
+//! time slice, each measurement adds the zero-momentum correlator
+//!
+//! ```text
+//! C(t) = 1/(L_t V_s) Σ_τ Σ_a Φ_a(τ) Φ_a(τ + t)
+//! ```
+//!
+//! and the momentum-space propagator G(p) = Σ_a |φ̃_a(p)|² / V from a
+//! d-dimensional FFT of the field. On a periodic lattice a single state
+//! gives C(t) ∝ cosh(m (t - L_t/2)), so the effective mass m_eff(t) solves
+//! C(t)/C(t+1) = cosh(m (t - L_t/2)) / cosh(m (t + 1 - L_t/2)), and a cosh
+//! fit over `correlator.fit_min ..= fit_max` gives the mass with a
+//! jackknife error over the bins of the measurements.
+//!
+//! Both are written next to the free lattice propagator at `mass_sq`,
+//!
+//! ```text
+//! G₀(p) = N / (β (m² + p̂²)),   p̂² = Σ_μ 4 sin²(p_μ/2),   cosh m₀ = 1 + m²/2,
+//! ```
+//!
+//! which the `free` model (or any model at zero coupling) has to reproduce
+//! within errors. In a broken phase C(t) also holds the constant V_s |M|².
+
+use std::path::Path;
+use std::sync::Arc;
+
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use serde::{Deserialize, Serialize};
+
+use physics_core::config::{at_least, Validate};
+use physics_core::error::SimError;
+use physics_core::io::CsvWriter;
+
+use crate::observables::{self, MeasurementSettings};
+use crate::Lattice;
+
+/// The `correlator` table of the config.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorrelatorSettings {
+    /// Measure C(t) and G(p) along with the other observables.
+    pub enabled: bool,
+    /// First time separation of the cosh fit.
+    pub fit_min: usize,
+    /// Last time separation of the cosh fit; 0 fits up to L_t/2.
+    pub fit_max: usize,
+    pub file: String,
+    pub propagator_file: String,
+}
+
+impl Default for CorrelatorSettings {
+    fn default() -> Self {
+        CorrelatorSettings {
+            enabled: false,
+            fit_min: 1,
+            fit_max: 0,
+            file: "correlator.csv".into(),
+            propagator_file: "propagator.csv".into(),
+        }
+    }
+}
+
+impl Validate for CorrelatorSettings {
+    fn validate(&self) -> Result<(), String> {
+        if self.fit_max > 0 {
+            at_least("correlator.fit_max", self.fit_max, self.fit_min + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl CorrelatorSettings {
+    /// The fit window on a lattice of time extent `lt`.
+    pub fn fit_range(&self, lt: usize) -> (usize, usize) {
+        let max = if self.fit_max > 0 { self.fit_max } else { lt / 2 };
+        (self.fit_min, max)
+    }
+}
+
+/// Accumulated correlators kept in a checkpoint.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CorrelatorState {
+    pub slices: Vec<Vec<f64>>,
+    pub propagator: Vec<f64>,
+    pub propagator_sq: Vec<f64>,
+}
+
+/// Correlators accumulated over the measurements of one run.
+pub struct Correlator {
+    extents: Vec<usize>,
+    /// C(t) of every measurement.
+    slices: Vec<Vec<f64>>,
+    ffts: Vec<Arc<dyn Fft<f64>>>,
+    buffer: Vec<Complex<f64>>,
+    line: Vec<Complex<f64>>,
+    scratch: Vec<Complex<f64>>,
+    /// Σ G(p) and Σ G(p)² over the measurements.
+    propagator: Vec<f64>,
+    propagator_sq: Vec<f64>,
+}
+
+impl Correlator {
+    pub fn new(lattice: &Lattice) -> Self {
+        let extents = lattice.geometry.extents().to_vec();
+        let mut planner = FftPlanner::new();
+        let ffts: Vec<Arc<dyn Fft<f64>>> = extents.iter().map(|&l| planner.plan_fft_forward(l)).collect();
+        let scratch_len = ffts.iter().map(|f| f.get_inplace_scratch_len()).max().unwrap_or(0);
+        let sites = lattice.sites();
+        Correlator {
+            line: vec![Complex::default(); extents.iter().copied().max().unwrap_or(0)],
+            extents,
+            slices: Vec::new(),
+            ffts,
+            buffer: vec![Complex::default(); sites],
+            scratch: vec![Complex::default(); scratch_len],
+            propagator: vec![0.0; sites],
+            propagator_sq: vec![0.0; sites],
+        }
+    }
+
+    pub fn state(&self) -> CorrelatorState {
+        CorrelatorState {
+            slices: self.slices.clone(),
+            propagator: self.propagator.clone(),
+            propagator_sq: self.propagator_sq.clone(),
+        }
+    }
+
+    pub fn restore(&mut self, state: CorrelatorState) -> Result<(), SimError> {
+        let lt = self.time_extent();
+        if state.propagator.len() != self.propagator.len()
+            || state.propagator_sq.len() != self.propagator_sq.len()
+            || state.slices.iter().any(|c| c.len() != lt)
+        {
+            return Err(SimError::Shape(format!(
+                "the checkpointed correlators do not fit a {:?} lattice",
+                self.extents
+            )));
+        }
+        self.slices = state.slices;
+        self.propagator = state.propagator;
+        self.propagator_sq = state.propagator_sq;
+        Ok(())
+    }
+
+    fn time_extent(&self) -> usize {
+        self.extents[0]
+    }
+
+    /// Add C(t) and G(p) of the current configuration.
+    pub fn measure(&mut self, lattice: &Lattice) {
+        let lt = self.time_extent();
+        let n = lattice.components();
+        let sites = lattice.sites();
+
+        // Coordinate 0 runs fastest, so site i lies on time slice i mod L_t.
+        let mut slices = vec![0.0; lt * n];
+        for (i, phi) in lattice.field.chunks_exact(n).enumerate() {
+            let t = i % lt;
+            for (s, p) in slices[n * t..n * (t + 1)].iter_mut().zip(phi) {
+                *s += p;
+            }
+        }
+        let norm = 1.0 / sites as f64;
+        let c: Vec<f64> = (0..lt)
+            .map(|t| {
+                let mut sum = 0.0;
+                for tau in 0..lt {
+                    let a = &slices[n * tau..n * (tau + 1)];
+                    let b = &slices[n * ((tau + t) % lt)..n * ((tau + t) % lt + 1)];
+                    sum += a.iter().zip(b).map(|(x, y)| x * y).sum::<f64>();
+                }
+                sum * norm
+            })
+            .collect();
+        self.slices.push(c);
+
+        let mut g = vec![0.0; sites];
+        for a in 0..n {
+            for (z, phi) in self.buffer.iter_mut().zip(lattice.field.chunks_exact(n)) {
+                *z = Complex::new(phi[a], 0.0);
+            }
+            self.transform(lattice);
+            for (g, z) in g.iter_mut().zip(&self.buffer) {
+                *g += z.norm_sqr() * norm;
+            }
+        }
+        for ((sum, sum_sq), g) in self.propagator.iter_mut().zip(self.propagator_sq.iter_mut()).zip(g) {
+            *sum += g;
+            *sum_sq += g * g;
+        }
+    }
+
+    /// In-place d-dimensional FFT of `buffer`, one axis after the other.
+    fn transform(&mut self, lattice: &Lattice) {
+        let sites = self.buffer.len();
+        for (mu, fft) in self.ffts.iter().enumerate() {
+            let l = self.extents[mu];
+            let stride = lattice.geometry.stride(mu);
+            let line = &mut self.line[..l];
+            for start in (0..sites).filter(|i| (i / stride).is_multiple_of(l)) {
+                for (k, z) in line.iter_mut().enumerate() {
+                    *z = self.buffer[start + k * stride];
+                }
+                fft.process_with_scratch(line, &mut self.scratch);
+                for (k, z) in line.iter().enumerate() {
+                    self.buffer[start + k * stride] = *z;
+                }
+            }
+        }
+    }
+
+    /// Analyse the measurements, print the fitted mass next to the free
+    /// one and write C(t) and G(p) to `correlator_path` and
+    /// `propagator_path`.
+    pub fn finish(
+        self,
+        settings: &CorrelatorSettings,
+        measurement: &MeasurementSettings,
+        lattice: &Lattice,
+        correlator_path: impl AsRef<Path>,
+        propagator_path: impl AsRef<Path>,
+    ) -> Result<(), SimError> {
+        let lt = self.time_extent();
+        let n = self.slices.len();
+        let beta = lattice.beta();
+        let mass_sq = lattice.action.mass_sq();
+        let components = lattice.components() as f64;
+
+        // Series of C(t) for each t, binned like the other observables.
+        let series: Vec<Vec<f64>> = (0..lt).map(|t| self.slices.iter().map(|c| c[t]).collect()).collect();
+        let taus: Vec<_> = series.iter().map(|s| observables::autocorrelation(s)).collect();
+        let bin_size = observables::bin_size(measurement, &taus, n, "correlator");
+        let bins: Vec<Vec<f64>> = series.iter().map(|s| observables::bin(s, bin_size)).collect();
+        let c: Vec<f64> = series.iter().map(|s| observables::mean(s)).collect();
+        let errors: Vec<f64> = (0..lt).map(|t| observables::jackknife(&bins, |m| m[t])).collect();
+        let free: Vec<f64> = (0..lt).map(|t| components * free_correlator(t, lt, mass_sq, beta)).collect();
+        let free_mass = free_mass(mass_sq);
+
+        let mut file = CsvWriter::create(
+            correlator_path,
+            &["t", "C", "C_error", "C_free", "m_eff", "m_eff_error", "m_eff_free"],
+        )?;
+        for t in 0..lt {
+            let (m_eff, m_eff_error, m_eff_free) = if t + 1 < lt {
+                (
+                    effective_mass(&c, t),
+                    observables::jackknife(&bins, |m| effective_mass(m, t)),
+                    effective_mass(&free, t),
+                )
+            } else {
+                (f64::NAN, f64::NAN, f64::NAN)
+            };
+            file.row(&[t as f64, c[t], errors[t], free[t], m_eff, m_eff_error, m_eff_free])?;
+        }
+        file.finish()?;
+
+        // The largest deviation from the free propagator, in errors.
+        let deviation = (0..lt)
+            .filter(|&t| errors[t] > 0.0)
+            .map(|t| ((c[t] - free[t]) / errors[t]).abs())
+            .fold(0.0, f64::max);
+        println!("correlator: C(t) is at most {:.2}σ from the free lattice propagator at mass_sq", deviation);
+
+        let (t_min, t_max) = settings.fit_range(lt);
+        let weights: Vec<f64> = errors.iter().map(|&e| if e > 0.0 { 1.0 / (e * e) } else { 1.0 }).collect();
+        let fit = cosh_fit(&c, &weights, t_min, t_max);
+        let fit_error = observables::jackknife(&bins, |m| cosh_fit(m, &weights, t_min, t_max).mass);
+        let dof = (t_max - t_min + 1).saturating_sub(2);
+        println!(
+            "correlator: cosh fit over t = {}..={}: mass = {:.6} ± {:.6}, χ²/dof = {:.2}; free lattice mass at mass_sq: {:.6}",
+            t_min,
+            t_max,
+            fit.mass,
+            fit_error,
+            fit.chi_sq / dof.max(1) as f64,
+            free_mass
+        );
+
+        let d = self.extents.len();
+        let mut header: Vec<String> = (0..d).map(|mu| format!("k{}", mu)).collect();
+        header.extend(["p_hat_sq", "G", "G_error", "G_free"].map(String::from));
+        let header: Vec<&str> = header.iter().map(String::as_str).collect();
+        let mut file = CsvWriter::create(propagator_path, &header)?;
+        let mut coords = vec![0; d];
+        let mut row = vec![0.0; d + 4];
+        let count = n as f64;
+        for (i, (&sum, &sum_sq)) in self.propagator.iter().zip(&self.propagator_sq).enumerate() {
+            lattice.geometry.coords(i, &mut coords);
+            let p_hat_sq: f64 = coords
+                .iter()
+                .zip(&self.extents)
+                .map(|(&k, &l)| 4.0 * (std::f64::consts::PI * k as f64 / l as f64).sin().powi(2))
+                .sum();
+            let mean = sum / count;
+            // Measurements are taken as independent here; the C(t) errors
+            // above account for autocorrelations.
+            let error = ((sum_sq / count - mean * mean).max(0.0) / (count - 1.0)).sqrt();
+            for (r, &k) in row.iter_mut().zip(&coords) {
+                *r = k as f64;
+            }
+            row[d..].copy_from_slice(&[p_hat_sq, mean, error, components / (beta * (mass_sq + p_hat_sq))]);
+            file.row(&row)?;
+        }
+        file.finish()
+    }
+}
+
+/// Free propagator of one component at time separation `t` and zero
+/// spatial momentum, summed over the L_t time momenta.
+fn free_correlator(t: usize, lt: usize, mass_sq: f64, beta: f64) -> f64 {
+    let sum: f64 = (0..lt)
+        .map(|k| {
+            let p = 2.0 * std::f64::consts::PI * k as f64 / lt as f64;
+            (p * t as f64).cos() / (mass_sq + 4.0 * (p / 2.0).sin().powi(2))
+        })
+        .sum();
+    sum / (beta * lt as f64)
+}
+
+/// Lattice mass m₀ of the free field, cosh m₀ = 1 + m²/2.
+fn free_mass(mass_sq: f64) -> f64 {
+    if mass_sq > 0.0 {
+        (1.0 + 0.5 * mass_sq).acosh()
+    } else {
+        f64::NAN
+    }
+}
+
+/// cosh(a)/cosh(b) without overflowing for large arguments.
+fn cosh_ratio(a: f64, b: f64) -> f64 {
+    let (a, b) = (a.abs(), b.abs());
+    (a - b).exp() * (1.0 + (-2.0 * a).exp()) / (1.0 + (-2.0 * b).exp())
+}
+
+/// Largest mass the effective-mass and fit searches consider.
+const MAX_MASS: f64 = 20.0;
+
+/// Mass m with C(t)/C(t+1) = cosh(m (t - L/2))/cosh(m (t + 1 - L/2)) for
+/// L = `c.len()`, by bisection; NaN if there is none.
+fn effective_mass(c: &[f64], t: usize) -> f64 {
+    let half = c.len() as f64 / 2.0;
+    let ratio = c[t] / c[t + 1];
+    if !ratio.is_finite() || ratio <= 0.0 {
+        return f64::NAN;
+    }
+    let f = |m: f64| cosh_ratio(m * (t as f64 - half), m * (t as f64 + 1.0 - half)) - ratio;
+    let (mut lo, mut hi) = (0.0, MAX_MASS);
+    let f_lo = f(lo);
+    if f_lo * f(hi) > 0.0 {
+        return f64::NAN;
+    }
+    for _ in 0..100 {
+        let mid = 0.5 * (lo + hi);
+        if f(mid) * f_lo > 0.0 {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    0.5 * (lo + hi)
+}
+
+struct CoshFit {
+    mass: f64,
+    chi_sq: f64,
+}
+
+/// Weighted least-squares fit of A cosh(m (t - L/2)) to `c` over
+/// t_min ..= t_max, L = `c.len()`. For a given m the amplitude is linear;
+/// m is located on a logarithmic grid and refined by golden section.
+fn cosh_fit(c: &[f64], weights: &[f64], t_min: usize, t_max: usize) -> CoshFit {
+    let half = c.len() as f64 / 2.0;
+    let t_max = t_max.min(c.len() - 1);
+    let chi_sq = |m: f64| {
+        // Shapes relative to t_min keep cosh finite for heavy masses.
+        let shape = |t: usize| cosh_ratio(m * (t as f64 - half), m * (t_min as f64 - half));
+        let (mut cf, mut ff) = (0.0, 0.0);
+        for t in t_min..=t_max {
+            cf += weights[t] * c[t] * shape(t);
+            ff += weights[t] * shape(t) * shape(t);
+        }
+        let amplitude = cf / ff;
+        (t_min..=t_max).map(|t| weights[t] * (c[t] - amplitude * shape(t)).powi(2)).sum::<f64>()
+    };
+
+    const GRID: usize = 200;
+    let grid = |k: usize| 1e-4 * (MAX_MASS / 1e-4).powf(k as f64 / (GRID - 1) as f64);
+    let best = (0..GRID).min_by(|&a, &b| chi_sq(grid(a)).total_cmp(&chi_sq(grid(b)))).unwrap_or(0);
+    let (mut lo, mut hi) = (grid(best.saturating_sub(1)), grid((best + 1).min(GRID - 1)));
+    let golden = (5f64.sqrt() - 1.0) / 2.0;
+    for _ in 0..60 {
+        let a = hi - golden * (hi - lo);
+        let b = lo + golden * (hi - lo);
+        if chi_sq(a) < chi_sq(b) {
+            hi = b;
+        } else {
+            lo = a;
+        }
+    }
+    let mass = 0.5 * (lo + hi);
+    CoshFit { mass, chi_sq: chi_sq(mass) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::ModelKind;
+    use crate::update::{self, UpdaterKind};
+    use crate::Config;
+
+    #[test]
+    fn zero_coupling_reproduces_the_free_propagator() {
+        // At zero coupling the heat bath draws every site exactly from its
+        // Gaussian conditional, so the chain samples the free field.
+        let cfg = Config {
+            dim: 2,
+            extents: vec![8, 4],
+            model: ModelKind::On,
+            components: 2,
+            coupling: 0.0,
+            mass_sq: 0.5,
+            updater: UpdaterKind::HeatBath,
+            seed: 7,
+            ..Config::default()
+        };
+        cfg.validate().unwrap();
+        let beta = 2.0;
+        let mut lattice = Lattice::new(&cfg, beta);
+        let mut updater = update::from_config(&cfg);
+        let mut correlator = Correlator::new(&lattice);
+        for sweep in 0..40_000 {
+            updater.sweep(&mut lattice);
+            // Every fifth sweep, so that G(p) can be taken as independent.
+            if sweep >= 200 && sweep % 5 == 0 {
+                correlator.measure(&lattice);
+            }
+        }
+
+        let lt = 8;
+        let components = 2.0;
+        let series: Vec<Vec<f64>> = (0..lt).map(|t| correlator.slices.iter().map(|c| c[t]).collect()).collect();
+        let taus: Vec<_> = series.iter().map(|s| observables::autocorrelation(s)).collect();
+        let bin_size = observables::bin_size(&MeasurementSettings::default(), &taus, series[0].len(), "test");
+        let bins: Vec<Vec<f64>> = series.iter().map(|s| observables::bin(s, bin_size)).collect();
+        for t in 0..lt {
+            let c = observables::mean(&series[t]);
+            let error = observables::jackknife(&bins, |m| m[t]);
+            let free = components * free_correlator(t, lt, cfg.mass_sq, beta);
+            assert!((c - free).abs() < 4.0 * error, "C({}) = {} ± {}, free {}", t, c, error, free);
+        }
+
+        let count = correlator.slices.len() as f64;
+        let mut coords = vec![0; 2];
+        for (i, (&sum, &sum_sq)) in correlator.propagator.iter().zip(&correlator.propagator_sq).enumerate() {
+            lattice.geometry.coords(i, &mut coords);
+            let p_hat_sq: f64 = coords
+                .iter()
+                .zip(&cfg.extents)
+                .map(|(&k, &l)| 4.0 * (std::f64::consts::PI * k as f64 / l as f64).sin().powi(2))
+                .sum();
+            let mean = sum / count;
+            let error = ((sum_sq / count - mean * mean) / (count - 1.0)).sqrt();
+            let free = components / (beta * (cfg.mass_sq + p_hat_sq));
+            assert!((mean - free).abs() < 4.0 * error, "G{:?} = {} ± {}, free {}", coords, mean, error, free);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Periodic hypercubic lattices of any dimension.
+//!
+//! A [`Geometry`] numbers the sites of an L₀ × L₁ × … × L_{d-1} lattice with
+//! coordinate 0 running fastest, and precomputes the 2d neighbours of every
+//! site once. With anisotropic extents such as `extents = [4, 16, 16, 16]`,
+//! axis 0 is the (short) time direction of a finite-temperature setup.
+
+/// Site numbering and neighbour table of a periodic lattice.
+#[derive(Clone, Debug)]
+pub struct Geometry {
+    extents: Vec<usize>,
+    strides: Vec<usize>,
+    /// For site i, entries 2d·i .. 2d·(i+1): the neighbour in +μ for
+    /// μ = 0..d, then in -μ.
+    neighbors: Vec<usize>,
+}
+
+impl Geometry {
+    pub fn new(extents: &[usize]) -> Self {
+        let mut strides = Vec::with_capacity(extents.len());
+        let mut stride = 1;
+        for &l in extents {
+            strides.push(stride);
+            stride *= l;
+        }
+        let sites = stride;
+        let d = extents.len();
+        let mut geometry = Geometry { extents: extents.to_vec(), strides, neighbors: vec![0; 2 * d * sites] };
+        let mut coords = vec![0; d];
+        for i in 0..sites {
+            geometry.coords(i, &mut coords);
+            for mu in 0..d {
+                let l = extents[mu];
+                let x = coords[mu];
+                let base = i - x * geometry.strides[mu];
+                geometry.neighbors[2 * d * i + mu] = base + (x + 1) % l * geometry.strides[mu];
+                geometry.neighbors[2 * d * i + d + mu] = base + (x + l - 1) % l * geometry.strides[mu];
+            }
+        }
+        geometry
+    }
+
+    pub fn dim(&self) -> usize {
+        self.extents.len()
+    }
+
+    pub fn extents(&self) -> &[usize] {
+        &self.extents
+    }
+
+    pub fn sites(&self) -> usize {
+        self.extents.iter().product()
+    }
+
+    /// Distance in the site numbering between neighbours along axis `mu`.
+    pub fn stride(&self, mu: usize) -> usize {
+        self.strides[mu]
+    }
+
+    /// Coordinates of site `i` into `coords`.
+    pub fn coords(&self, i: usize, coords: &mut [usize]) {
+        for ((c, &l), &s) in coords.iter_mut().zip(&self.extents).zip(&self.strides) {
+            *c = i / s % l;
+        }
+    }
+
+    /// The 2d neighbours of site `i`: +μ for μ = 0..d, then -μ.
+    pub fn neighbors(&self, i: usize) -> &[usize] {
+        let n = 2 * self.dim();
+        &self.neighbors[n * i..n * (i + 1)]
+    }
+
+    /// Neighbour of site `i` in direction +μ.
+    pub fn forward(&self, i: usize, mu: usize) -> usize {
+        self.neighbors[2 * self.dim() * i + mu]
+    }
+}
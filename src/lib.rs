@@ -0,0 +1,555 @@
+mod action;
+mod checkerboard;
+mod checkpoint;
+mod correlator;
+mod geometry;
+mod observables;
+mod units;
+mod update;
+
+use physics_core::config::{at_least, positive, RunConfig, Validate};
+use physics_core::error::{check_finite_value, SimError};
+use physics_core::io::CsvWriter;
+use physics_core::constants::si::{C, G, HBAR, K_B};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand_chacha::ChaCha12Rng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+use action::{Action, ModelKind};
+use checkerboard::ParallelSettings;
+use checkpoint::{Checkpoint, CheckpointSettings};
+use correlator::{Correlator, CorrelatorSettings};
+use geometry::Geometry;
+use observables::{Estimate, MeasurementSettings, TimeSeries};
+use units::{Scan, Units};
+use update::{HmcSettings, MetropolisSettings, OverRelaxationSettings, Updater, UpdaterKind};
+
+// Run parameters. The defaults are the original constants; pass a TOML or
+// JSON file with `--config` to override them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    rs: f64,             // Schwarzschild radius (for example)
+    lattice_size: usize,
+    dim: usize,
+    extents: Vec<usize>, // per axis, overriding lattice_size; axis 0 is time
+    n_sweeps: usize,
+    coupling: f64,       // coupling constant for field interactions
+    mass_sq: f64,        // mass^2 term for the scalar field
+    model: ModelKind,
+    components: usize,   // field components of the O(N) model
+    updater: UpdaterKind,
+    metropolis: MetropolisSettings,
+    over_relaxation: OverRelaxationSettings,
+    hmc: HmcSettings,
+    parallel: ParallelSettings,
+    measurement: MeasurementSettings,
+    correlator: CorrelatorSettings,
+    checkpoint: CheckpointSettings,
+    units: Units,
+    scan: Scan,
+    seed: u64,
+    out_dir: PathBuf,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            rs: 1e-6,
+            lattice_size: 20,
+            dim: 3,
+            extents: Vec::new(),
+            n_sweeps: 1000,
+            coupling: 1.0,
+            mass_sq: 1.0,
+            model: ModelKind::Free,
+            components: 3,
+            updater: UpdaterKind::Metropolis,
+            metropolis: MetropolisSettings::default(),
+            over_relaxation: OverRelaxationSettings::default(),
+            hmc: HmcSettings::default(),
+            parallel: ParallelSettings::default(),
+            measurement: MeasurementSettings::default(),
+            correlator: CorrelatorSettings::default(),
+            checkpoint: CheckpointSettings::default(),
+            units: Units::default(),
+            scan: Scan::default(),
+            seed: 42,
+            out_dir: PathBuf::from("."),
+        }
+    }
+}
+
+impl Validate for Config {
+    fn validate(&self) -> Result<(), String> {
+        positive("rs", self.rs)?;
+        at_least("lattice_size", self.lattice_size, 1)?;
+        at_least("dim", self.dim, 1)?;
+        if !self.extents.is_empty() && self.extents.len() != self.dim {
+            return Err(format!("extents has {} entries but dim = {}", self.extents.len(), self.dim));
+        }
+        for (mu, &l) in self.extents.iter().enumerate() {
+            at_least(&format!("extents[{}]", mu), l, 1)?;
+        }
+        at_least("n_sweeps", self.n_sweeps, 1)?;
+        if !self.coupling.is_finite() {
+            return Err("coupling must be finite".into());
+        }
+        if !self.mass_sq.is_finite() {
+            return Err("mass_sq must be finite".into());
+        }
+        match self.model {
+            // Without a quartic term the weight is only normalizable for m² > 0.
+            ModelKind::Free => positive("mass_sq", self.mass_sq)?,
+            _ if self.coupling < 0.0 => {
+                return Err(format!("coupling must not be negative with a quartic term, got {}", self.coupling))
+            }
+            _ if self.coupling == 0.0 => positive("mass_sq", self.mass_sq)?,
+            _ => {}
+        }
+        at_least("components", self.components, 1)?;
+        if matches!(self.updater, UpdaterKind::HeatBath | UpdaterKind::OverRelaxation)
+            && self.mass_sq <= -2.0 * self.dim as f64
+        {
+            return Err(format!(
+                "the heat-bath and over-relaxation updaters need mass_sq above -2·dim = -{}, got {}",
+                2 * self.dim,
+                self.mass_sq
+            ));
+        }
+        if self.parallel.checkerboard {
+            if self.updater == UpdaterKind::Hmc {
+                return Err("parallel.checkerboard needs a local updater; hmc moves all sites at once".into());
+            }
+            let extents = self.extents();
+            if extents.iter().any(|l| l % 2 != 0) {
+                return Err(format!("parallel.checkerboard needs even extents, got {:?}", extents));
+            }
+        }
+        self.metropolis.validate()?;
+        self.hmc.validate()?;
+        self.measurement.validate()?;
+        self.correlator.validate()?;
+        if self.correlator.enabled {
+            let lt = self.extents()[0];
+            let (t_min, t_max) = self.correlator.fit_range(lt);
+            if t_max >= lt || t_max <= t_min {
+                return Err(format!(
+                    "the correlator fit over t = {}..={} needs at least two time separations below L_t = {}",
+                    t_min, t_max, lt
+                ));
+            }
+        }
+        self.checkpoint.validate()?;
+        self.units.validate()?;
+        self.scan.validate()?;
+        if !self.scan.radii.is_empty() && (self.checkpoint.interval > 0 || self.checkpoint.resume.is_some()) {
+            return Err("checkpoints need a single radius; they are not written for a scan".into());
+        }
+        if self.measurement.thermalization >= self.n_sweeps {
+            return Err(format!(
+                "measurement.thermalization ({}) leaves no sweeps to measure out of n_sweeps = {}",
+                self.measurement.thermalization, self.n_sweeps
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Extent of every axis: `extents`, or `lattice_size` along each of
+    /// `dim` axes.
+    fn extents(&self) -> Vec<usize> {
+        if self.extents.is_empty() {
+            vec![self.lattice_size; self.dim]
+        } else {
+            self.extents.clone()
+        }
+    }
+}
+
+impl RunConfig for Config {
+    fn out_dir(&self) -> &Path {
+        &self.out_dir
+    }
+
+    fn set_out_dir(&mut self, dir: PathBuf) {
+        self.out_dir = dir;
+    }
+
+    fn set_steps(&mut self, steps: usize) {
+        self.n_sweeps = steps;
+    }
+
+    fn set_seed(&mut self, seed: u64) -> Result<(), String> {
+        self.seed = seed;
+        Ok(())
+    }
+
+    fn set_resume(&mut self, path: PathBuf) -> Result<(), String> {
+        self.checkpoint.resume = Some(path);
+        Ok(())
+    }
+}
+
+// Black hole parameters
+fn black_hole_mass(rs: f64) -> f64 {
+    (rs * C.powi(2)) / (2.0 * G)
+}
+fn hawking_temperature(m: f64) -> f64 {
+    HBAR * C.powi(3) / (8.0 * PI * G * m * K_B)
+}
+
+/// We consider a simple scalar field lattice model:
+/// Hamiltonian (discretized) ~ sum over neighbors (phi_x - phi_y)^2 + V(phi_x),
+/// with the on-site potential V of the configured model in [`action`]: the
+/// free field mass_sq * phi_x^2, or lambda phi^4, O(N) and U(1) models with
+/// `coupling` as lambda.
+///
+/// Configurations are sampled at thermal equilibrium,
+/// probability ~ exp(-H/k_B T) = exp(-beta * S), by one of the updaters in
+/// [`update`]; [`units`] maps the physical temperature to beta.
+///
+/// The lattice has any dimension and extents; see [`geometry`].
+/// Boundary conditions: periodic for simplicity.
+/// This does not violate relativity. We are just sampling field configurations at a given temperature.
+struct Lattice {
+    geometry: Geometry,
+    /// The components of site i at [n·i, n·(i + 1)), n = components.
+    field: Vec<f64>,
+    action: Box<dyn Action>,
+    beta: f64,
+    n_sweeps: usize,
+    /// The chain's generator; the same stream as `StdRng`, which cannot be
+    /// checkpointed.
+    rng: ChaCha12Rng,
+}
+
+impl Lattice {
+    fn new(cfg: &Config, beta: f64) -> Self {
+        let geometry = Geometry::new(&cfg.extents());
+        let action = action::from_model(cfg.model, cfg.components, cfg.mass_sq, cfg.coupling);
+        let mut rng = ChaCha12Rng::seed_from_u64(cfg.seed);
+        let field = (0..geometry.sites() * action.components())
+            .map(|_| rng.random_range(-0.1..0.1)) // small random initial field
+            .collect();
+        Lattice { geometry, field, action, beta, n_sweeps: cfg.n_sweeps, rng }
+    }
+
+    fn components(&self) -> usize {
+        self.action.components()
+    }
+
+    fn dim(&self) -> usize {
+        self.geometry.dim()
+    }
+
+    fn site(&self, i: usize) -> &[f64] {
+        let n = self.components();
+        &self.field[n * i..n * (i + 1)]
+    }
+
+    fn set_site(&mut self, i: usize, phi: &[f64]) {
+        let n = self.components();
+        self.field[n * i..n * (i + 1)].copy_from_slice(phi);
+    }
+
+    /// Local action of site `i` if it held `phi`:
+    /// V(phi) + (1/2)*sum_neighbors |phi - phi_n|^2.
+    fn local_action(&self, i: usize, phi: &[f64]) -> f64 {
+        let mut e = self.action.potential(phi);
+        for &n in self.geometry.neighbors(i) {
+            for (p, q) in phi.iter().zip(self.site(n)) {
+                let d = p - q;
+                e += 0.5 * d * d;
+            }
+        }
+        e
+    }
+
+    fn local_energy(&self, i: usize) -> f64 {
+        self.local_action(i, self.site(i))
+    }
+
+    fn sites(&self) -> usize {
+        self.geometry.sites()
+    }
+
+    /// Inverse temperature in units of the lattice action.
+    fn beta(&self) -> f64 {
+        self.beta
+    }
+
+    /// Sum of the neighbours' components into `sum`.
+    fn neighbor_sum(&self, i: usize, sum: &mut [f64]) {
+        sum.iter_mut().for_each(|s| *s = 0.0);
+        for &n in self.geometry.neighbors(i) {
+            for (s, q) in sum.iter_mut().zip(self.site(n)) {
+                *s += q;
+            }
+        }
+    }
+
+    /// Total action S = Σ_x [V(φ_x) + Σ_μ |φ_x+μ - φ_x|²/2].
+    fn action(&self) -> f64 {
+        let mut s = 0.0;
+        for i in 0..self.sites() {
+            let phi = self.site(i);
+            s += self.action.potential(phi);
+            for mu in 0..self.dim() {
+                for (p, q) in phi.iter().zip(self.site(self.geometry.forward(i, mu))) {
+                    let d = p - q;
+                    s += 0.5 * d * d;
+                }
+            }
+        }
+        s
+    }
+
+    fn measure_energy(&self) -> f64 {
+        let mut e = 0.0;
+        for i in 0..self.sites() {
+            // Each local energy counts neighbor pairs twice, but we do not double count if careful:
+            // We'll just sum local_energy and divide by 2 since each bond counted twice.
+            e += self.local_energy(i);
+        }
+        e / 2.0
+    }
+
+    /// Observables recorded in the time series: energy, action and |φ|²
+    /// per site, and powers of the magnetization |M|, M = Σ_x φ_x / V.
+    const OBSERVABLES: [&'static str; 6] = ["energy", "action", "phi2", "m", "m2", "m4"];
+
+    fn measure(&self) -> [f64; 6] {
+        let n = self.sites() as f64;
+        let mut magnetization = vec![0.0; self.components()];
+        for phi in self.field.chunks_exact(self.components()) {
+            for (m, p) in magnetization.iter_mut().zip(phi) {
+                *m += p / n;
+            }
+        }
+        let m2 = action::norm_sq(&magnetization);
+        let phi2 = action::norm_sq(&self.field) / n;
+        [self.measure_energy() / n, self.action() / n, phi2, m2.sqrt(), m2, m2 * m2]
+    }
+
+    /// Add the susceptibility χ = V (⟨|M|²⟩ - ⟨|M|⟩²) and the Binder
+    /// cumulant U = 1 - N ⟨|M|⁴⟩ / ((N + 2) ⟨|M|²⟩²) to `series`. U is 0
+    /// for Gaussian fluctuations about M = 0 and 2/(N + 2) deep in the
+    /// ordered phase.
+    fn derived(&self, series: TimeSeries) -> TimeSeries {
+        let volume = self.sites() as f64;
+        let n = self.components() as f64;
+        series
+            .derive("susceptibility", move |m| volume * (m[4] - m[3] * m[3]))
+            .derive("binder", move |m| 1.0 - n * m[5] / ((n + 2.0) * m[4] * m[4]))
+    }
+
+    const DERIVED: [&'static str; 2] = ["susceptibility", "binder"];
+
+    /// Everything `cfg`'s run needs to continue after `sweep` sweeps.
+    fn checkpoint(
+        &self,
+        cfg: &Config,
+        sweep: usize,
+        updater: &dyn Updater,
+        series: &TimeSeries,
+        correlator: Option<&Correlator>,
+    ) -> Checkpoint {
+        Checkpoint {
+            config: serde_json::to_string(cfg).expect("configs serialize to JSON"),
+            sweep,
+            field: self.field.clone(),
+            rng: self.rng.clone(),
+            updater: updater.state(),
+            series: series.state(),
+            correlator: correlator.map(Correlator::state),
+        }
+    }
+
+    /// Continue from `checkpoint`; returns the sweeps it had done.
+    fn resume(
+        &mut self,
+        checkpoint: Checkpoint,
+        updater: &mut dyn Updater,
+        series: &mut TimeSeries,
+        correlator: Option<&mut Correlator>,
+    ) -> Result<usize, SimError> {
+        if checkpoint.field.len() != self.field.len() {
+            return Err(SimError::Shape(format!(
+                "the checkpointed field has {} values, the lattice {}",
+                checkpoint.field.len(),
+                self.field.len()
+            )));
+        }
+        self.field = checkpoint.field;
+        self.rng = checkpoint.rng;
+        updater.restore(&checkpoint.updater);
+        series.restore(&checkpoint.series)?;
+        match (correlator, checkpoint.correlator) {
+            (Some(correlator), Some(state)) => correlator.restore(state)?,
+            (None, None) => {}
+            _ => return Err(SimError::Shape("the checkpoint and the config disagree on the correlator".into())),
+        }
+        Ok(checkpoint.sweep)
+    }
+
+    /// Sweep from `start` up to `n_sweeps`, measuring and writing
+    /// checkpoints as `cfg` asks.
+    fn run(
+        &mut self,
+        cfg: &Config,
+        start: usize,
+        updater: &mut dyn Updater,
+        series: &mut TimeSeries,
+        mut correlator: Option<&mut Correlator>,
+    ) -> Result<(), SimError> {
+        let measurement = &cfg.measurement;
+        for sweep in start..self.n_sweeps {
+            updater.sweep(self);
+            let measured = sweep - measurement.thermalization.min(sweep);
+            if sweep >= measurement.thermalization && measured % measurement.interval == 0 {
+                series.push(sweep, &self.measure())?;
+                if let Some(correlator) = correlator.as_deref_mut() {
+                    correlator.measure(self);
+                }
+            }
+            if sweep % 100 == 0 {
+                let e = self.measure_energy();
+                check_finite_value("lattice energy", sweep, e)?;
+                println!(
+                    "Sweep: {}, Energy per site: {}, acceptance: {:.3}",
+                    sweep,
+                    e / (self.sites() as f64),
+                    updater.acceptance_rate()
+                );
+            }
+            let interval = cfg.checkpoint.interval;
+            if interval > 0 && (sweep + 1) % interval == 0 {
+                let path = cfg.out_dir.join(&cfg.checkpoint.file);
+                self.checkpoint(cfg, sweep + 1, updater, series, correlator.as_deref()).write(&path)?;
+                println!("Checkpoint after {} sweeps written to {}", sweep + 1, path.display());
+            }
+        }
+        println!("Updater {}: acceptance rate {:.3}", updater.name(), updater.acceptance_rate());
+        Ok(())
+    }
+}
+
+/// `file` with `_{i}` inserted before its extension.
+fn indexed(file: &str, i: usize) -> String {
+    match file.rsplit_once('.') {
+        Some((stem, ext)) => format!("{}_{}.{}", stem, i, ext),
+        None => format!("{}_{}", file, i),
+    }
+}
+
+/// Thermalize a lattice at `beta` and analyse its observables, writing the
+/// output files of the config, indexed by the scan `point` if there is one.
+fn thermalize(cfg: &Config, beta: f64, point: Option<usize>) -> Result<Vec<Estimate>, SimError> {
+    let output = |file: &str| cfg.out_dir.join(point.map_or_else(|| file.to_string(), |i| indexed(file, i)));
+    let mut lattice = Lattice::new(cfg, beta);
+    println!(
+        "Model: {} with {} field component(s) on a {:?} lattice",
+        lattice.action.name(),
+        lattice.components(),
+        lattice.geometry.extents()
+    );
+    let mut updater = update::from_config(cfg);
+    let series = TimeSeries::create(output(&cfg.measurement.series_file), &Lattice::OBSERVABLES)?;
+    let mut series = lattice.derived(series);
+    let mut correlator = cfg.correlator.enabled.then(|| Correlator::new(&lattice));
+    let start = match &cfg.checkpoint.resume {
+        Some(path) => {
+            let checkpoint = Checkpoint::read(path)?;
+            checkpoint.check_config(cfg)?;
+            let start = lattice.resume(checkpoint, updater.as_mut(), &mut series, correlator.as_mut())?;
+            println!("Resuming from {} after {} sweeps", path.display(), start);
+            start
+        }
+        None => 0,
+    };
+    lattice.run(cfg, start, updater.as_mut(), &mut series, correlator.as_mut())?;
+
+    // The bootstrap draws from its own generator so that the analysis leaves
+    // the chain's stream alone.
+    let mut resampling = StdRng::seed_from_u64(cfg.seed.wrapping_add(1));
+    let summary_path = output(&cfg.measurement.summary_file);
+    println!(
+        "Measured {} configurations after {} thermalization sweeps:",
+        series.len(),
+        cfg.measurement.thermalization
+    );
+    let estimates = series.finish(&cfg.measurement, &summary_path, &mut resampling)?;
+    println!("Observables written to {}", summary_path.display());
+    if let Some(correlator) = correlator {
+        let correlator_path = output(&cfg.correlator.file);
+        let propagator_path = output(&cfg.correlator.propagator_file);
+        correlator.finish(&cfg.correlator, &cfg.measurement, &lattice, &correlator_path, &propagator_path)?;
+        println!(
+            "Correlator written to {}, propagator to {}",
+            correlator_path.display(),
+            propagator_path.display()
+        );
+    }
+
+    let final_energy = lattice.measure_energy();
+    println!("Final energy per site: {}", final_energy / (lattice.sites() as f64));
+    Ok(estimates)
+}
+
+/// Black hole mass, Hawking temperature and lattice beta for radius `rs`.
+fn hawking_point(cfg: &Config, rs: f64) -> (f64, f64, f64) {
+    let mass = black_hole_mass(rs);
+    let t_hawk = hawking_temperature(mass);
+    let beta = cfg.units.beta(t_hawk);
+    println!("Black hole mass: {} kg", mass);
+    println!("Hawking temperature: {} K", t_hawk);
+    println!("Lattice beta: {}", beta);
+    (mass, t_hawk, beta)
+}
+
+/// Thermalize the scalar lattice at the Hawking temperature of a black hole
+/// of radius `rs`, or of every radius in `scan.radii`.
+pub fn run(cfg: &Config) -> Result<(), SimError> {
+    println!(
+        "Lattice spacing {} m, field scale {}: one unit of lattice action is {:e} J",
+        cfg.units.spacing,
+        cfg.units.field_scale,
+        cfg.units.energy_scale()
+    );
+
+    if cfg.scan.radii.is_empty() {
+        // Use Hawking temperature as the system temperature
+        let (_, _, beta) = hawking_point(cfg, cfg.rs);
+        thermalize(cfg, beta, None)?;
+    } else {
+        let scan_path = cfg.out_dir.join(&cfg.scan.file);
+        let mut header = vec!["rs(m)".to_string(), "mass(kg)".into(), "T_hawking(K)".into(), "beta".into()];
+        for name in Lattice::OBSERVABLES.iter().chain(&Lattice::DERIVED) {
+            header.push(name.to_string());
+            header.push(format!("{}_error", name));
+        }
+        let header: Vec<&str> = header.iter().map(String::as_str).collect();
+        let mut file = CsvWriter::create(&scan_path, &header)?;
+        for (i, &rs) in cfg.scan.radii.iter().enumerate() {
+            println!("Scan point {}: rs = {} m", i, rs);
+            let (mass, t_hawk, beta) = hawking_point(cfg, rs);
+            let estimates = thermalize(cfg, beta, Some(i))?;
+            let mut row = vec![rs, mass, t_hawk, beta];
+            for e in &estimates {
+                row.push(e.mean);
+                row.push(e.jackknife_error);
+            }
+            file.row(&row)?;
+        }
+        file.finish()?;
+        println!("Beta scan written to {}", scan_path.display());
+    }
+
+    println!("Simulation complete with pure statistical mechanics initialization from Hawking radiation temperature. No violations of Special Relativity introduced.");
+    Ok(())
+}
@@ -0,0 +1,434 @@
+//! Time series of lattice observables and their error analysis.
+//!
+//! After `measurement.thermalization` sweeps, every observable is recorded
+//! each `measurement.interval` sweeps into a [`TimeSeries`], which is also
+//! streamed to `measurement.series_file`. At the end of the run each series
+//! gets
+//!
+//! * its integrated autocorrelation time τ_int, summed up to the first
+//!   window W ≥ 6 τ_int(W) (Sokal's automatic windowing), with the
+//!   Madras–Sokal error τ_int √((4W + 2)/N);
+//! * bins of `measurement.bin_size` consecutive measurements, by default
+//!   the smallest size above 4 τ_int of the slowest observable;
+//! * the mean with the naive, jackknife and bootstrap errors over the bins.
+//!
+//! Derived quantities, functions of the means of several series such as a
+//! susceptibility, are resampled through the same bins and get jackknife
+//! and bootstrap errors only. One row per observable goes to
+//! `measurement.summary_file`.
+
+use std::path::Path;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use physics_core::config::{at_least, Validate};
+use physics_core::error::SimError;
+use physics_core::io::CsvWriter;
+
+/// The `measurement` table of the config.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MeasurementSettings {
+    /// Sweeps discarded before the first measurement.
+    pub thermalization: usize,
+    /// Sweeps between measurements.
+    pub interval: usize,
+    /// Measurements per bin; 0 picks it from the autocorrelation time.
+    pub bin_size: usize,
+    /// Resamples for the bootstrap error.
+    pub bootstrap_samples: usize,
+    pub series_file: String,
+    pub summary_file: String,
+}
+
+impl Default for MeasurementSettings {
+    fn default() -> Self {
+        MeasurementSettings {
+            thermalization: 100,
+            interval: 1,
+            bin_size: 0,
+            bootstrap_samples: 1000,
+            series_file: "timeseries.csv".into(),
+            summary_file: "observables.csv".into(),
+        }
+    }
+}
+
+impl Validate for MeasurementSettings {
+    fn validate(&self) -> Result<(), String> {
+        at_least("measurement.interval", self.interval, 1)?;
+        at_least("measurement.bootstrap_samples", self.bootstrap_samples, 2)
+    }
+}
+
+/// Function of the means of all series of a [`TimeSeries`], in the order
+/// of their names.
+pub type DerivedFn = Box<dyn Fn(&[f64]) -> f64>;
+
+/// Measurements of a fixed set of observables, one row per measured sweep.
+pub struct TimeSeries {
+    names: Vec<String>,
+    sweeps: Vec<usize>,
+    values: Vec<Vec<f64>>,
+    derived: Vec<(String, DerivedFn)>,
+    file: CsvWriter,
+}
+
+impl TimeSeries {
+    /// Record the observables `names`, streaming every row to `path`.
+    pub fn create(path: impl AsRef<Path>, names: &[&str]) -> Result<Self, SimError> {
+        let mut header = vec!["sweep"];
+        header.extend_from_slice(names);
+        let file = CsvWriter::create(path, &header)?;
+        Ok(TimeSeries {
+            names: names.iter().map(|n| n.to_string()).collect(),
+            sweeps: Vec::new(),
+            values: vec![Vec::new(); names.len()],
+            derived: Vec::new(),
+            file,
+        })
+    }
+
+    /// Also estimate `name`, computed by `f` from the means of all series.
+    pub fn derive(mut self, name: &str, f: impl Fn(&[f64]) -> f64 + 'static) -> Self {
+        self.derived.push((name.to_string(), Box::new(f)));
+        self
+    }
+
+    /// Record `values`, in the order of the names, measured after `sweep`.
+    pub fn push(&mut self, sweep: usize, values: &[f64]) -> Result<(), SimError> {
+        self.sweeps.push(sweep);
+        for (series, &v) in self.values.iter_mut().zip(values) {
+            series.push(v);
+        }
+        let mut row = vec![sweep as f64];
+        row.extend_from_slice(values);
+        self.file.row(&row)
+    }
+
+    pub fn len(&self) -> usize {
+        self.sweeps.len()
+    }
+
+    /// The measurements so far, for a checkpoint.
+    pub fn state(&self) -> SeriesState {
+        SeriesState { sweeps: self.sweeps.clone(), values: self.values.clone() }
+    }
+
+    /// Record the measurements of a checkpoint again, as if they had just
+    /// been taken.
+    pub fn restore(&mut self, state: &SeriesState) -> Result<(), SimError> {
+        if state.values.len() != self.names.len() || state.values.iter().any(|v| v.len() != state.sweeps.len()) {
+            return Err(SimError::Shape(format!(
+                "the checkpoint holds {} series, expected {} of {} measurements",
+                state.values.len(),
+                self.names.len(),
+                state.sweeps.len()
+            )));
+        }
+        for (k, &sweep) in state.sweeps.iter().enumerate() {
+            let row: Vec<f64> = state.values.iter().map(|v| v[k]).collect();
+            self.push(sweep, &row)?;
+        }
+        Ok(())
+    }
+
+    /// Analyse every series, print the results and write them to
+    /// `summary_path`.
+    pub fn finish(
+        self,
+        settings: &MeasurementSettings,
+        summary_path: impl AsRef<Path>,
+        rng: &mut StdRng,
+    ) -> Result<Vec<Estimate>, SimError> {
+        let n = self.len();
+        self.file.finish()?;
+        let taus: Vec<Autocorrelation> = self.values.iter().map(|v| autocorrelation(v)).collect();
+        let bin_size = bin_size(settings, &taus, n, "observables");
+
+        let mut summary = CsvWriter::create(
+            summary_path,
+            &[
+                "observable",
+                "mean",
+                "naive_error",
+                "jackknife_error",
+                "bootstrap_error",
+                "tau_int",
+                "tau_int_error",
+                "window",
+                "bin_size",
+                "bins",
+            ],
+        )?;
+        let bins: Vec<Vec<f64>> = self.values.iter().map(|v| bin(v, bin_size)).collect();
+        let means: Vec<f64> = self.values.iter().map(|v| mean(v)).collect();
+        let mut estimates = Vec::new();
+        for (i, (name, tau)) in self.names.iter().zip(taus).enumerate() {
+            if !tau.closed {
+                println!("observables: the τ_int window of {} did not close; the run is too short for it", name);
+            }
+            let pick = |m: &[f64]| m[i];
+            estimates.push(Estimate {
+                name: name.clone(),
+                mean: means[i],
+                naive_error: standard_error(&self.values[i]),
+                jackknife_error: jackknife(&bins, pick),
+                bootstrap_error: bootstrap(&bins, settings.bootstrap_samples, rng, pick),
+                tau_int: tau.tau,
+                tau_int_error: tau.error,
+                window: tau.window,
+                bin_size,
+                bins: bins[i].len(),
+            });
+        }
+        for (name, f) in &self.derived {
+            estimates.push(Estimate {
+                name: name.clone(),
+                mean: f(&means),
+                naive_error: f64::NAN,
+                jackknife_error: jackknife(&bins, f),
+                bootstrap_error: bootstrap(&bins, settings.bootstrap_samples, rng, f),
+                tau_int: f64::NAN,
+                tau_int_error: f64::NAN,
+                window: 0,
+                bin_size,
+                bins: bins.first().map_or(0, Vec::len),
+            });
+        }
+        for estimate in &estimates {
+            if estimate.tau_int.is_nan() {
+                println!(
+                    "{}: {:.6e} ± {:.2e} (jackknife), ± {:.2e} (bootstrap)",
+                    estimate.name, estimate.mean, estimate.jackknife_error, estimate.bootstrap_error
+                );
+            } else {
+                println!(
+                    "{}: {:.6e} ± {:.2e} (jackknife), ± {:.2e} (bootstrap), τ_int = {:.2} ± {:.2}",
+                    estimate.name,
+                    estimate.mean,
+                    estimate.jackknife_error,
+                    estimate.bootstrap_error,
+                    estimate.tau_int,
+                    estimate.tau_int_error
+                );
+            }
+            summary.record(&[
+                estimate.name.clone(),
+                estimate.mean.to_string(),
+                estimate.naive_error.to_string(),
+                estimate.jackknife_error.to_string(),
+                estimate.bootstrap_error.to_string(),
+                estimate.tau_int.to_string(),
+                estimate.tau_int_error.to_string(),
+                estimate.window.to_string(),
+                estimate.bin_size.to_string(),
+                estimate.bins.to_string(),
+            ])?;
+        }
+        summary.finish()?;
+        Ok(estimates)
+    }
+}
+
+/// Measurements of a [`TimeSeries`] kept in a checkpoint.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SeriesState {
+    pub sweeps: Vec<usize>,
+    pub values: Vec<Vec<f64>>,
+}
+
+/// Result of the analysis of one time series or derived quantity. The
+/// naive error and the autocorrelation time of a derived quantity are NaN.
+#[derive(Clone, Debug)]
+pub struct Estimate {
+    pub name: String,
+    pub mean: f64,
+    /// Standard error assuming independent measurements.
+    pub naive_error: f64,
+    pub jackknife_error: f64,
+    pub bootstrap_error: f64,
+    pub tau_int: f64,
+    pub tau_int_error: f64,
+    pub window: usize,
+    pub bin_size: usize,
+    pub bins: usize,
+}
+
+/// Integrated autocorrelation time in units of measurements.
+pub struct Autocorrelation {
+    pub tau: f64,
+    pub error: f64,
+    /// Summation window W.
+    pub window: usize,
+    /// Whether W ≥ 6 τ_int(W) was reached within half the series. If not,
+    /// τ_int is a lower bound and the run is too short.
+    pub closed: bool,
+}
+
+/// Sokal's window factor: stop summing once W ≥ C τ_int(W).
+const WINDOW_FACTOR: f64 = 6.0;
+
+pub fn autocorrelation(values: &[f64]) -> Autocorrelation {
+    let n = values.len();
+    let m = mean(values);
+    let gamma = |t: usize| -> f64 {
+        values[..n - t].iter().zip(&values[t..]).map(|(a, b)| (a - m) * (b - m)).sum::<f64>() / (n - t) as f64
+    };
+    let gamma0 = if n > 0 { gamma(0) } else { 0.0 };
+    let mut tau = 0.5;
+    let mut window = 0;
+    let mut closed = true;
+    if gamma0 > 0.0 {
+        closed = false;
+        for t in 1..n / 2 {
+            tau += gamma(t) / gamma0;
+            window = t;
+            if t as f64 >= WINDOW_FACTOR * tau {
+                closed = true;
+                break;
+            }
+        }
+    }
+    // A negative sum means pure noise; it cannot be below that of
+    // independent measurements.
+    let tau = tau.max(0.5);
+    let error = if n > 0 { tau * ((4 * window + 2) as f64 / n as f64).sqrt() } else { f64::NAN };
+    Autocorrelation { tau, error, window, closed }
+}
+
+/// `measurement.bin_size`, or the smallest size above 4 τ_int of the
+/// slowest of `taus`, for `n` measurements; `label` prefixes the warning
+/// about too few bins.
+pub fn bin_size(settings: &MeasurementSettings, taus: &[Autocorrelation], n: usize, label: &str) -> usize {
+    let size = if settings.bin_size > 0 {
+        settings.bin_size
+    } else {
+        let tau_max = taus.iter().map(|a| a.tau).fold(0.5, f64::max);
+        (4.0 * tau_max).ceil() as usize
+    };
+    let size = size.clamp(1, (n / 2).max(1));
+    if n / size < 10 {
+        println!("{}: only {} bins of {} measurements; the error estimates are unreliable", label, n / size, size);
+    }
+    size
+}
+
+pub fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn standard_error(values: &[f64]) -> f64 {
+    let n = values.len() as f64;
+    let m = mean(values);
+    let var = values.iter().map(|v| (v - m).powi(2)).sum::<f64>() / (n - 1.0);
+    (var / n).sqrt()
+}
+
+/// Means of consecutive blocks of `size` values; a trailing partial block is
+/// dropped.
+pub fn bin(values: &[f64], size: usize) -> Vec<f64> {
+    values.chunks_exact(size).map(mean).collect()
+}
+
+/// Jackknife error of `f` of the means of several series, given each
+/// series' bins.
+pub fn jackknife(bins: &[Vec<f64>], f: impl Fn(&[f64]) -> f64) -> f64 {
+    let n = bins.first().map_or(0, Vec::len);
+    let totals: Vec<f64> = bins.iter().map(|b| b.iter().sum()).collect();
+    let mut means = vec![0.0; bins.len()];
+    let leave_one_out: Vec<f64> = (0..n)
+        .map(|k| {
+            for ((m, b), total) in means.iter_mut().zip(bins).zip(&totals) {
+                *m = (total - b[k]) / (n - 1) as f64;
+            }
+            f(&means)
+        })
+        .collect();
+    let n = n as f64;
+    let m = mean(&leave_one_out);
+    ((n - 1.0) / n * leave_one_out.iter().map(|v| (v - m).powi(2)).sum::<f64>()).sqrt()
+}
+
+/// Bootstrap error of `f` of the means of several series from `samples`
+/// resamples of the bins with replacement, the same bins for every series.
+pub fn bootstrap(bins: &[Vec<f64>], samples: usize, rng: &mut StdRng, f: impl Fn(&[f64]) -> f64) -> f64 {
+    let n = bins.first().map_or(0, Vec::len);
+    if n == 0 {
+        return f64::NAN;
+    }
+    let mut picks = vec![0; n];
+    let mut means = vec![0.0; bins.len()];
+    let values: Vec<f64> = (0..samples)
+        .map(|_| {
+            for p in picks.iter_mut() {
+                *p = rng.random_range(0..n);
+            }
+            for (m, b) in means.iter_mut().zip(bins) {
+                *m = picks.iter().map(|&k| b[k]).sum::<f64>() / n as f64;
+            }
+            f(&means)
+        })
+        .collect();
+    let m = mean(&values);
+    (values.iter().map(|v| (v - m).powi(2)).sum::<f64>() / (samples - 1) as f64).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_distr::StandardNormal;
+
+    fn relative(a: f64, b: f64) -> f64 {
+        (a - b).abs() / b.abs()
+    }
+
+    #[test]
+    fn independent_samples_have_tau_one_half_and_the_standard_error() {
+        let (n, sigma) = (20_000, 2.0);
+        let mut rng = StdRng::seed_from_u64(1);
+        let values: Vec<f64> = (0..n).map(|_| 1.0 + sigma * rng.sample::<f64, _>(StandardNormal)).collect();
+
+        let tau = autocorrelation(&values);
+        assert!(tau.closed);
+        assert!((tau.tau - 0.5).abs() < 3.0 * tau.error, "τ_int = {} ± {}", tau.tau, tau.error);
+
+        let expected = sigma / (n as f64).sqrt();
+        let singles = [values.clone()];
+        assert!(relative(jackknife(&singles, |m| m[0]), standard_error(&values)) < 1e-9);
+        assert!(relative(standard_error(&values), expected) < 0.03);
+        let bins = [bin(&values, 10)];
+        assert!(relative(jackknife(&bins, |m| m[0]), expected) < 0.1);
+        assert!(relative(bootstrap(&bins, 2000, &mut rng, |m| m[0]), expected) < 0.1);
+    }
+
+    #[test]
+    fn ar1_series_has_the_exact_tau_int() {
+        // x' = ρ x + √(1 - ρ²) ε keeps unit variance, with Γ(t)/Γ(0) = ρ^t
+        // and τ_int = (1 + ρ) / (2 (1 - ρ)).
+        let (n, rho) = (200_000, 0.8);
+        let exact = (1.0 + rho) / (2.0 * (1.0 - rho));
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut x: f64 = rng.sample(StandardNormal);
+        let values: Vec<f64> = (0..n)
+            .map(|_| {
+                x = rho * x + (1.0 - rho * rho).sqrt() * rng.sample::<f64, _>(StandardNormal);
+                x
+            })
+            .collect();
+
+        let tau = autocorrelation(&values);
+        assert!(tau.closed);
+        assert!((tau.tau - exact).abs() < 3.0 * tau.error, "τ_int = {} ± {}, exact {}", tau.tau, tau.error, exact);
+
+        // Bins much longer than τ_int carry the full error σ √(2 τ_int / N),
+        // which the naive error underestimates.
+        let expected = (2.0 * exact / n as f64).sqrt();
+        let bins = [bin(&values, 100)];
+        assert!(relative(jackknife(&bins, |m| m[0]), expected) < 0.1);
+        assert!(standard_error(&values) < 0.5 * expected);
+    }
+}
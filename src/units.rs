@@ -0,0 +1,85 @@
+//! Mapping between physical units and the dimensionless lattice.
+//!
+//! The classical field energy H = ∫ dᵈx [(∇φ)²/2 + m²φ²/2] on a hypercubic
+//! lattice of spacing `a` in d dimensions, with the field written as
+//! φ = ζ √(ħc) a^((1-d)/2) · φ̂ in terms of the dimensionless lattice field
+//! φ̂ (φ = ζ √(ħc)/a · φ̂ for d = 3), becomes
+//!
+//! ```text
+//! H = ζ² ħc/a · S[φ̂],   S = Σ_x [ (m a)² φ̂_x² / 2 + Σ_μ (φ̂_x+μ - φ̂_x)² / 2 ]
+//! ```
+//!
+//! so the Boltzmann weight exp(-H/k_B T) is exp(-β S) with
+//!
+//! ```text
+//! β = ζ² ħc / (a k_B T)
+//! ```
+//!
+//! At the Hawking temperature of a black hole of radius r_s this is
+//! β = 4π ζ² r_s/a: a lattice as fine as the horizon samples at β = 4π.
+//! `mass_sq` is (m a)², already in lattice units.
+
+use serde::{Deserialize, Serialize};
+
+use physics_core::config::{positive, Validate};
+use physics_core::constants::si::{C, HBAR, K_B};
+
+/// The `units` table of the config.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Units {
+    /// Lattice spacing a (m).
+    pub spacing: f64,
+    /// Field normalization ζ in φ = ζ √(ħc) a^((1-d)/2) · φ̂.
+    pub field_scale: f64,
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Units { spacing: 1e-6, field_scale: 1.0 }
+    }
+}
+
+impl Validate for Units {
+    fn validate(&self) -> Result<(), String> {
+        positive("units.spacing", self.spacing)?;
+        positive("units.field_scale", self.field_scale)
+    }
+}
+
+impl Units {
+    /// Energy of one unit of lattice action, ζ² ħc/a (J).
+    pub fn energy_scale(&self) -> f64 {
+        self.field_scale.powi(2) * HBAR * C / self.spacing
+    }
+
+    /// Lattice β at physical temperature `temperature` (K).
+    pub fn beta(&self, temperature: f64) -> f64 {
+        self.energy_scale() / (K_B * temperature)
+    }
+}
+
+/// The `scan` table of the config: Schwarzschild radii to run the lattice
+/// at, one after the other, at their Hawking temperatures.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scan {
+    /// Radii r_s (m); empty runs the single radius `rs`.
+    pub radii: Vec<f64>,
+    pub file: String,
+}
+
+impl Default for Scan {
+    fn default() -> Self {
+        Scan { radii: Vec::new(), file: "beta_scan.csv".into() }
+    }
+}
+
+impl Validate for Scan {
+    fn validate(&self) -> Result<(), String> {
+        for (i, &rs) in self.radii.iter().enumerate() {
+            positive(&format!("scan.radii[{}]", i), rs)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,528 @@
+//! Markov-chain updates of the scalar lattice.
+//!
+//! Every [`Updater`] leaves the Boltzmann weight exp(-β S) invariant, with
+//! the lattice action
+//!
+//! ```text
+//! S = Σ_x [ V(φ_x) + Σ_μ |φ_x+μ - φ_x|² / 2 ],   V(φ) = m² |φ|² / 2 + W(φ)
+//! ```
+//!
+//! of the model's [`crate::action::Action`]. The config's `updater` picks
+//! one per run:
+//!
+//! * `metropolis`: random-site updates with a uniform proposal, whose width
+//!   is tuned towards `metropolis.target_acceptance` during the first
+//!   `metropolis.adapt_sweeps` sweeps and fixed afterwards;
+//! * `heat_bath`: every site drawn from the Gaussian conditional
+//!   distribution of the quadratic part of S, accepted with probability
+//!   min(1, exp(-β ΔW)), so always for the free field;
+//! * `over_relaxation`: `over_relaxation.sweeps` reflections of every site
+//!   about the mean of that Gaussian, which leave the quadratic part
+//!   unchanged and are accepted like the heat-bath, followed by one
+//!   heat-bath sweep to change the action;
+//! * `hmc`: Hybrid Monte Carlo, a leapfrog trajectory of the whole field
+//!   with Gaussian momenta and a Metropolis test on the energy error.
+//!
+//! With `parallel.checkerboard` the three local updaters visit the sites
+//! in red/black order over threads, see [`crate::checkerboard`]; Metropolis
+//! then sweeps each colour in turn instead of picking random sites.
+//!
+//! Each updater counts its accepted proposals.
+
+use rand::{Rng, RngCore};
+use rand_distr::StandardNormal;
+use serde::{Deserialize, Serialize};
+
+use physics_core::config::{at_least, positive, Validate};
+use crate::checkerboard::{Checkerboard, SiteUpdate};
+use crate::{Config, Lattice};
+
+/// The `updater` key of the config.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdaterKind {
+    #[default]
+    Metropolis,
+    HeatBath,
+    OverRelaxation,
+    Hmc,
+}
+
+/// The `metropolis` table of the config.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetropolisSettings {
+    /// Initial half-width of the uniform proposal.
+    pub step: f64,
+    /// Acceptance rate the step is tuned towards.
+    pub target_acceptance: f64,
+    /// Sweeps during which the step is tuned; 0 keeps it fixed.
+    pub adapt_sweeps: usize,
+}
+
+impl Default for MetropolisSettings {
+    fn default() -> Self {
+        MetropolisSettings { step: 0.1, target_acceptance: 0.5, adapt_sweeps: 100 }
+    }
+}
+
+impl Validate for MetropolisSettings {
+    fn validate(&self) -> Result<(), String> {
+        positive("metropolis.step", self.step)?;
+        if !(self.target_acceptance > 0.0 && self.target_acceptance < 1.0) {
+            return Err(format!(
+                "metropolis.target_acceptance must be between 0 and 1, got {}",
+                self.target_acceptance
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The `over_relaxation` table of the config.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OverRelaxationSettings {
+    /// Reflection sweeps per heat-bath sweep.
+    pub sweeps: usize,
+}
+
+impl Default for OverRelaxationSettings {
+    fn default() -> Self {
+        OverRelaxationSettings { sweeps: 3 }
+    }
+}
+
+/// The `hmc` table of the config.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HmcSettings {
+    /// Leapfrog steps per trajectory.
+    pub steps: usize,
+    /// Leapfrog step in units of 1/ω_max, the period scale of the stiffest
+    /// lattice mode; the integrator is unstable from 2 on.
+    pub step_size: f64,
+    /// Each trajectory draws its step uniformly within this fraction of
+    /// `step_size`, so that no mode keeps returning to where it started.
+    pub jitter: f64,
+}
+
+impl Default for HmcSettings {
+    fn default() -> Self {
+        HmcSettings { steps: 10, step_size: 0.5, jitter: 0.2 }
+    }
+}
+
+impl Validate for HmcSettings {
+    fn validate(&self) -> Result<(), String> {
+        at_least("hmc.steps", self.steps, 1)?;
+        positive("hmc.step_size", self.step_size)?;
+        if !(0.0..1.0).contains(&self.jitter) {
+            return Err(format!("hmc.jitter must be at least 0 and below 1, got {}", self.jitter));
+        }
+        if self.step_size * (1.0 + self.jitter) >= 2.0 {
+            return Err(format!(
+                "hmc.step_size × (1 + hmc.jitter) must be below 2 for a stable leapfrog, got {}",
+                self.step_size * (1.0 + self.jitter)
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// One way of generating the next configuration of the Markov chain.
+pub trait Updater {
+    fn name(&self) -> &'static str;
+
+    /// Update the whole lattice once.
+    fn sweep(&mut self, lattice: &mut Lattice);
+
+    /// Fraction of proposals accepted so far.
+    fn acceptance_rate(&self) -> f64;
+
+    /// Counters and tuned parameters, for a checkpoint.
+    fn state(&self) -> UpdaterState;
+
+    /// Continue from a checkpointed `state`.
+    fn restore(&mut self, state: &UpdaterState);
+}
+
+/// Counters and tuned parameters of an updater, kept in a checkpoint;
+/// each updater uses the fields it has.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct UpdaterState {
+    pub accepted: usize,
+    pub proposed: usize,
+    /// Metropolis proposal width and the sweeps it has been tuned for.
+    pub step: f64,
+    pub tuned_sweeps: usize,
+    /// Checkerboard passes so far.
+    pub passes: u64,
+}
+
+/// The updater `cfg` asks for.
+pub fn from_config(cfg: &Config) -> Box<dyn Updater> {
+    let order = || {
+        if cfg.parallel.checkerboard {
+            Order::Checkerboard(Box::new(Checkerboard::new(&cfg.parallel, cfg.seed)))
+        } else {
+            Order::Sequential
+        }
+    };
+    match cfg.updater {
+        UpdaterKind::Metropolis => Box::new(Metropolis::new(&cfg.metropolis, order())),
+        UpdaterKind::HeatBath => Box::new(HeatBath { order: order(), accepted: 0, proposed: 0 }),
+        UpdaterKind::OverRelaxation => Box::new(OverRelaxation {
+            sweeps: cfg.over_relaxation.sweeps,
+            order: order(),
+            accepted: 0,
+            proposed: 0,
+        }),
+        UpdaterKind::Hmc => Box::new(Hmc::new(&cfg.hmc)),
+    }
+}
+
+/// Order in which a local updater visits the sites.
+pub enum Order {
+    /// One site after the other, drawing from the lattice's generator.
+    Sequential,
+    Checkerboard(Box<Checkerboard>),
+}
+
+impl Order {
+    /// Offer every site to `update` once and set those it accepts; in
+    /// sequential order `random_sites` picks each site at random instead.
+    /// Returns the number of accepted proposals.
+    fn pass(&mut self, lattice: &mut Lattice, random_sites: bool, update: impl SiteUpdate) -> usize {
+        match self {
+            Order::Sequential => {
+                // The generator is moved out for the duration of the pass so
+                // that `update` can read the lattice while drawing from it.
+                let mut rng = lattice.rng.clone();
+                let sites = lattice.sites();
+                let mut new = vec![0.0; lattice.components()];
+                let mut accepted = 0;
+                for step in 0..sites {
+                    let i = if random_sites { rng.random_range(0..sites) } else { step };
+                    if update(lattice, i, &mut rng, &mut new) {
+                        lattice.set_site(i, &new);
+                        accepted += 1;
+                    }
+                }
+                lattice.rng = rng;
+                accepted
+            }
+            Order::Checkerboard(checkerboard) => checkerboard.pass(lattice, update),
+        }
+    }
+
+    fn passes(&self) -> u64 {
+        match self {
+            Order::Sequential => 0,
+            Order::Checkerboard(checkerboard) => checkerboard.passes(),
+        }
+    }
+
+    fn set_passes(&mut self, passes: u64) {
+        if let Order::Checkerboard(checkerboard) = self {
+            checkerboard.set_passes(passes);
+        }
+    }
+}
+
+/// Coefficient of |φ_x|²/2 in the quadratic part of the action: the mass
+/// plus one per bond.
+fn stiffness(lattice: &Lattice) -> f64 {
+    lattice.action.mass_sq() + 2.0 * lattice.dim() as f64
+}
+
+fn rate(accepted: usize, proposed: usize) -> f64 {
+    if proposed == 0 {
+        0.0
+    } else {
+        accepted as f64 / proposed as f64
+    }
+}
+
+/// Random-site Metropolis with an adaptive proposal width.
+pub struct Metropolis {
+    order: Order,
+    step: f64,
+    target: f64,
+    adapt_sweeps: usize,
+    sweeps: usize,
+    accepted: usize,
+    proposed: usize,
+}
+
+impl Metropolis {
+    pub fn new(settings: &MetropolisSettings, order: Order) -> Self {
+        Metropolis {
+            order,
+            step: settings.step,
+            target: settings.target_acceptance,
+            adapt_sweeps: settings.adapt_sweeps,
+            sweeps: 0,
+            accepted: 0,
+            proposed: 0,
+        }
+    }
+}
+
+impl Updater for Metropolis {
+    fn name(&self) -> &'static str {
+        "metropolis"
+    }
+
+    fn sweep(&mut self, lattice: &mut Lattice) {
+        let beta = lattice.beta();
+        let sites = lattice.sites();
+        let step = self.step;
+        let accepted = self.order.pass(lattice, true, |lattice: &Lattice, i, rng: &mut dyn RngCore, new: &mut [f64]| {
+            let old = lattice.site(i);
+            let old_e = lattice.local_action(i, old);
+
+            for (n, o) in new.iter_mut().zip(old) {
+                *n = o + rng.random_range(-step..step);
+            }
+            let new_e = lattice.local_action(i, new);
+
+            let d_e = new_e - old_e;
+            d_e <= 0.0 || rng.random::<f64>() <= (-beta * d_e).exp()
+        });
+        self.accepted += accepted;
+        self.proposed += sites;
+
+        if self.sweeps < self.adapt_sweeps {
+            // Scale the width by the ratio of observed to target acceptance,
+            // at most a factor of two per sweep.
+            let factor = (rate(accepted, sites) / self.target).clamp(0.5, 2.0);
+            self.step *= factor;
+            self.sweeps += 1;
+            if self.sweeps == self.adapt_sweeps {
+                println!("metropolis: step tuned to {:.3e} after {} sweeps", self.step, self.sweeps);
+                // Only the fixed-step chain counts towards the reported rate.
+                self.accepted = 0;
+                self.proposed = 0;
+            }
+        }
+    }
+
+    fn acceptance_rate(&self) -> f64 {
+        rate(self.accepted, self.proposed)
+    }
+
+    fn state(&self) -> UpdaterState {
+        UpdaterState {
+            accepted: self.accepted,
+            proposed: self.proposed,
+            step: self.step,
+            tuned_sweeps: self.sweeps,
+            passes: self.order.passes(),
+        }
+    }
+
+    fn restore(&mut self, state: &UpdaterState) {
+        self.accepted = state.accepted;
+        self.proposed = state.proposed;
+        self.step = state.step;
+        self.sweeps = state.tuned_sweeps;
+        self.order.set_passes(state.passes);
+    }
+}
+
+/// Accept a change of the non-Gaussian part of the potential by ΔW with
+/// probability min(1, exp(-β ΔW)).
+fn accept_interaction(lattice: &Lattice, i: usize, new: &[f64], rng: &mut dyn RngCore) -> bool {
+    let d_w = lattice.action.interaction(new) - lattice.action.interaction(lattice.site(i));
+    d_w <= 0.0 || rng.random::<f64>() <= (-lattice.beta() * d_w).exp()
+}
+
+/// Propose every site in turn from the Gaussian conditional distribution of
+/// the quadratic part of the action given its neighbours, of mean Σ_n φ_n / k
+/// and variance 1/(β k) per component with k = m² + 2d. Returns the
+/// number of accepted proposals.
+fn heat_bath_sweep(lattice: &mut Lattice, order: &mut Order) -> usize {
+    let k = stiffness(lattice);
+    let sigma = 1.0 / (lattice.beta() * k).sqrt();
+    order.pass(lattice, false, |lattice: &Lattice, i, rng: &mut dyn RngCore, new: &mut [f64]| {
+        lattice.neighbor_sum(i, new);
+        for v in new.iter_mut() {
+            let noise: f64 = rng.sample(StandardNormal);
+            *v = *v / k + sigma * noise;
+        }
+        accept_interaction(lattice, i, new, rng)
+    })
+}
+
+/// Gaussian heat-bath.
+pub struct HeatBath {
+    order: Order,
+    accepted: usize,
+    proposed: usize,
+}
+
+impl Updater for HeatBath {
+    fn name(&self) -> &'static str {
+        "heat_bath"
+    }
+
+    fn sweep(&mut self, lattice: &mut Lattice) {
+        self.accepted += heat_bath_sweep(lattice, &mut self.order);
+        self.proposed += lattice.sites();
+    }
+
+    fn acceptance_rate(&self) -> f64 {
+        rate(self.accepted, self.proposed)
+    }
+
+    fn state(&self) -> UpdaterState {
+        UpdaterState { accepted: self.accepted, proposed: self.proposed, passes: self.order.passes(), ..Default::default() }
+    }
+
+    fn restore(&mut self, state: &UpdaterState) {
+        self.accepted = state.accepted;
+        self.proposed = state.proposed;
+        self.order.set_passes(state.passes);
+    }
+}
+
+/// Reflections φ_x → 2 φ̄_x - φ_x about the conditional Gaussian mean φ̄_x,
+/// which move far through configuration space at fixed quadratic action,
+/// plus a heat-bath sweep for ergodicity.
+pub struct OverRelaxation {
+    sweeps: usize,
+    order: Order,
+    accepted: usize,
+    proposed: usize,
+}
+
+impl Updater for OverRelaxation {
+    fn name(&self) -> &'static str {
+        "over_relaxation"
+    }
+
+    fn sweep(&mut self, lattice: &mut Lattice) {
+        let k = stiffness(lattice);
+        for _ in 0..self.sweeps {
+            self.accepted += self.order.pass(lattice, false, |lattice: &Lattice, i, rng: &mut dyn RngCore, new: &mut [f64]| {
+                lattice.neighbor_sum(i, new);
+                for (v, o) in new.iter_mut().zip(lattice.site(i)) {
+                    *v = 2.0 * *v / k - o;
+                }
+                accept_interaction(lattice, i, new, rng)
+            });
+            self.proposed += lattice.sites();
+        }
+        self.accepted += heat_bath_sweep(lattice, &mut self.order);
+        self.proposed += lattice.sites();
+    }
+
+    fn acceptance_rate(&self) -> f64 {
+        rate(self.accepted, self.proposed)
+    }
+
+    fn state(&self) -> UpdaterState {
+        UpdaterState { accepted: self.accepted, proposed: self.proposed, passes: self.order.passes(), ..Default::default() }
+    }
+
+    fn restore(&mut self, state: &UpdaterState) {
+        self.accepted = state.accepted;
+        self.proposed = state.proposed;
+        self.order.set_passes(state.passes);
+    }
+}
+
+/// Hybrid Monte Carlo with a leapfrog integrator of the fictitious
+/// Hamiltonian H = Σ π²/2 + β S.
+pub struct Hmc {
+    steps: usize,
+    step_size: f64,
+    jitter: f64,
+    accepted: usize,
+    proposed: usize,
+}
+
+impl Hmc {
+    pub fn new(settings: &HmcSettings) -> Self {
+        Hmc { steps: settings.steps, step_size: settings.step_size, jitter: settings.jitter, accepted: 0, proposed: 0 }
+    }
+
+    /// π -= eps β ∂S/∂φ at every site; `momenta` has the layout of the
+    /// field.
+    fn kick(lattice: &Lattice, momenta: &mut [f64], eps: f64) {
+        let beta = lattice.beta();
+        let bonds = 2.0 * lattice.dim() as f64;
+        let n = lattice.components();
+        let mut sum = vec![0.0; n];
+        let mut grad = vec![0.0; n];
+        for (i, p) in momenta.chunks_exact_mut(n).enumerate() {
+            let phi = lattice.site(i);
+            lattice.neighbor_sum(i, &mut sum);
+            lattice.action.gradient(phi, &mut grad);
+            for a in 0..n {
+                let force = grad[a] + bonds * phi[a] - sum[a];
+                p[a] -= eps * beta * force;
+            }
+        }
+    }
+
+    fn hamiltonian(lattice: &Lattice, momenta: &[f64]) -> f64 {
+        let kinetic: f64 = momenta.iter().map(|p| 0.5 * p * p).sum();
+        kinetic + lattice.beta() * lattice.action()
+    }
+}
+
+impl Updater for Hmc {
+    fn name(&self) -> &'static str {
+        "hmc"
+    }
+
+    fn sweep(&mut self, lattice: &mut Lattice) {
+        // The stiffest mode, momentum π in every direction, oscillates at
+        // ω_max² = β (m² + 4d) about φ = 0; a quartic term stiffens it
+        // further, which the step size has to leave room for.
+        let mass_sq = lattice.action.mass_sq().max(0.0);
+        let omega_max = (lattice.beta() * (mass_sq + 4.0 * lattice.dim() as f64)).sqrt();
+        let jitter = 1.0 + self.jitter * (2.0 * lattice.rng.random::<f64>() - 1.0);
+        let eps = jitter * self.step_size / omega_max;
+
+        let old_field = lattice.field.clone();
+        let mut momenta = vec![0.0; lattice.field.len()];
+        for p in momenta.iter_mut() {
+            *p = lattice.rng.sample(StandardNormal);
+        }
+        let h_old = Self::hamiltonian(lattice, &momenta);
+
+        Self::kick(lattice, &mut momenta, 0.5 * eps);
+        for step in 0..self.steps {
+            for (phi, p) in lattice.field.iter_mut().zip(&momenta) {
+                *phi += eps * p;
+            }
+            let kick = if step + 1 < self.steps { eps } else { 0.5 * eps };
+            Self::kick(lattice, &mut momenta, kick);
+        }
+
+        let d_h = Self::hamiltonian(lattice, &momenta) - h_old;
+        self.proposed += 1;
+        // A NaN energy error (a diverging trajectory) is rejected.
+        if d_h <= 0.0 || lattice.rng.random::<f64>() < (-d_h).exp() {
+            self.accepted += 1;
+        } else {
+            lattice.field = old_field;
+        }
+    }
+
+    fn acceptance_rate(&self) -> f64 {
+        rate(self.accepted, self.proposed)
+    }
+
+    fn state(&self) -> UpdaterState {
+        UpdaterState { accepted: self.accepted, proposed: self.proposed, ..Default::default() }
+    }
+
+    fn restore(&mut self, state: &UpdaterState) {
+        self.accepted = state.accepted;
+        self.proposed = state.proposed;
+    }
+}
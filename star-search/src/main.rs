@@ -1,272 +1,332 @@
-use std::fs::File;
-use std::io::Write;
 use std::f64::consts::PI;
-use lazy_static::lazy_static;
-use ndarray::{Array3};
-use ndarray_npy::write_npy;
-
-// Physical constants
-const C: f64 = 2.99792458e8;
-const HBAR: f64 = 1.054571817e-34;
-const G: f64 = 6.67430e-11;
-const K_B: f64 = 1.380649e-23;
-const MU0: f64 = 4.0 * PI * 1e-7; // Vacuum permeability
-const EPS0: f64 = 1.0/(MU0*C*C);
-
-// Parameters
-lazy_static! {
-    static ref G_A_GAMMA: f64 = 1e-14; // Increased coupling slightly
-    static ref PHOTON_TO_NEUTRINO_COEFF: f64 = 1e-10; 
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use physics_core::cli;
+use physics_core::config::{at_least, non_negative, positive, GridConfig, RunConfig, Validate};
+use physics_core::eos::EosSettings;
+use physics_core::error::{check_finite, SimError};
+use physics_core::gw::{GwBackground, GwSettings, Mode};
+use physics_core::io::{write_npy, CsvWriter};
+use physics_core::diffusion::{CgSettings, DiffusionScheme, Diffuser};
+use physics_core::ledger::{Conservation, Process};
+use physics_core::species::{Mixture, Reaction, Species, Splitting};
+use physics_core::stability::Stability;
+use physics_core::{Boundaries, Grid3};
+use serde::{Deserialize, Serialize};
+
+// Parameters (defaults are the original constants; pass a TOML or JSON file
+// with `--config` to override them)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    g_a_gamma: f64, // Increased coupling slightly
+    photon_to_neutrino_coeff: f64,
+    q: f64,
+    grid: GridConfig,
+    dt: f64,        // 1.0e-17// Larger dt
+    steps: usize,   // More steps for evolution
+
+    photon_init: f64,
+    axion_init: f64,
+    neutrino_init: f64,
+
+    energy_init: f64,
+    eos: EosSettings,
+
+    d_ph: f64,
+    d_ax: f64,
+    d_nu: f64,
+    d_e: f64,
+
+    lambda_nu: f64,
+    alpha_expansion: f64,
+    gw: GwSettings,
+
+    photon_bc: Boundaries,
+    axion_bc: Boundaries,
+    neutrino_bc: Boundaries,
+    energy_bc: Boundaries,
+    photon_scheme: DiffusionScheme,
+    axion_scheme: DiffusionScheme,
+    neutrino_scheme: DiffusionScheme,
+    energy_scheme: DiffusionScheme,
+    splitting: Splitting,
+    conservation: Conservation,
+    stability: Stability,
+    cg: CgSettings,
+    out_dir: PathBuf,
+    results_file: String,
+    data_dir: String, // npy snapshots, relative to out_dir
 }
 
-const Q: f64 = 1.001;
-const LAMBDA: f64 = 0.01; 
-const NX: usize = 20;
-const NY: usize = 20;
-const NZ: usize = 20;
-const DX: f64 = 0.5e-15;
-const DT: f64 = 5.391247 * 10e-18; // 1.0e-17// Larger DT
-const STEPS: usize = 1000; // More steps for evolution
-
-const PHOTON_INIT: f64 = 1e10;
-const AXION_INIT: f64 = 1e-20;
-const NEUTRINO_INIT: f64 = 1e10;
-
-const ENERGY_INIT: f64 = 3.2e35; 
-const EPSILON_CRIT: f64 = 1.6e35; 
-const DELTA: f64 = 0.2e35; 
-
-const D_PH: f64 = 1e-3;
-const D_AX: f64 = 1e-3;
-const D_NU: f64 = 1e-3;
-const D_E: f64 = 1e-3;
-
-const LAMBDA_NU: f64 = 1e-5;
-const ALPHA_EXPANSION: f64 = 1e-5;
-const GW_STR: f64 = 1e-21;
-const GW_FREQ: f64 = 1e3;
-
-fn axion_photon_conversion(n_ax: f64, n_ph: f64) -> f64 {
-    let saturation = 1.0 + n_ph / 1e33;
-    (*G_A_GAMMA * n_ax) / saturation
-}
-
-fn photon_neutrino_conversion(n_ph: f64) -> f64 {
-    let saturation = 1.0 + n_ph / 1e33;
-    (*PHOTON_TO_NEUTRINO_COEFF * n_ph) / saturation
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            g_a_gamma: 1e-14,
+            photon_to_neutrino_coeff: 1e-10,
+            q: 1.001,
+            grid: GridConfig::new(20, 20, 20, 0.5e-15),
+            dt: 5.391247 * 10e-18,
+            steps: 1000,
+            photon_init: 1e10,
+            axion_init: 1e-20,
+            neutrino_init: 1e10,
+            energy_init: 3.2e35,
+            eos: EosSettings::Crossover { epsilon_crit: 1.6e35, delta: 0.2e35, c_s2_qgp: 1.0 / 3.0, c_s2_hadron: 0.15 },
+            d_ph: 1e-3,
+            d_ax: 1e-3,
+            d_nu: 1e-3,
+            d_e: 1e-3,
+            lambda_nu: 1e-5,
+            alpha_expansion: 1e-5,
+            gw: GwSettings {
+                modes: vec![Mode { plus: 1e-21, frequency: 1e3, phase: -0.5 * PI, ..Mode::default() }],
+                chirps: Vec::new(),
+            },
+            photon_bc: Boundaries::neumann(),
+            axion_bc: Boundaries::neumann(),
+            neutrino_bc: Boundaries::neumann(),
+            energy_bc: Boundaries::neumann(),
+            photon_scheme: DiffusionScheme::BackwardEuler,
+            axion_scheme: DiffusionScheme::BackwardEuler,
+            neutrino_scheme: DiffusionScheme::BackwardEuler,
+            energy_scheme: DiffusionScheme::BackwardEuler,
+            splitting: Splitting::Lie,
+            conservation: Conservation::default(),
+            stability: Stability::default(),
+            cg: CgSettings::default(),
+            out_dir: PathBuf::from("."),
+            results_file: "results.csv".into(),
+            data_dir: "data".into(),
+        }
+    }
 }
 
-struct Field {
-    photon_density: Vec<f64>,
-    axion_density: Vec<f64>,
-    neutrino_density: Vec<f64>,
-    energy_density: Vec<f64>,
-    efield_x: Vec<f64>,
-    efield_y: Vec<f64>,
-    efield_z: Vec<f64>,
-    bfield_x: Vec<f64>,
-    bfield_y: Vec<f64>,
-    bfield_z: Vec<f64>,
+impl Validate for Config {
+    fn validate(&self) -> Result<(), String> {
+        non_negative("g_a_gamma", self.g_a_gamma)?;
+        non_negative("photon_to_neutrino_coeff", self.photon_to_neutrino_coeff)?;
+        positive("q", self.q)?;
+        positive("dt", self.dt)?;
+        at_least("steps", self.steps, 1)?;
+        positive("photon_init", self.photon_init)?;
+        non_negative("axion_init", self.axion_init)?;
+        non_negative("neutrino_init", self.neutrino_init)?;
+        non_negative("energy_init", self.energy_init)?;
+        self.eos.validate()?;
+        non_negative("d_ph", self.d_ph)?;
+        non_negative("d_ax", self.d_ax)?;
+        non_negative("d_nu", self.d_nu)?;
+        non_negative("d_e", self.d_e)?;
+        non_negative("lambda_nu", self.lambda_nu)?;
+        non_negative("alpha_expansion", self.alpha_expansion)?;
+        self.gw.validate()?;
+        at_least("grid.nx", self.grid.nx, 2)?; // the torsion pattern divides by nx-1
+        at_least("grid.ny", self.grid.ny, 2)?;
+        at_least("grid.nz", self.grid.nz, 2)?;
+        self.conservation.validate()?;
+        self.stability.validate()?;
+        self.cg.validate()?;
+        self.grid.validate()
+    }
 }
 
-impl Field {
-    fn new() -> Self {
-        let size = NX*NY*NZ;
-        Field {
-            photon_density: vec![PHOTON_INIT; size],
-            axion_density: vec![AXION_INIT; size],
-            neutrino_density: vec![NEUTRINO_INIT; size],
-            energy_density: vec![ENERGY_INIT; size],
-            // Initialize E and B fields with a small perturbation
-            efield_x: vec![0.0; size],
-            efield_y: vec![0.0; size],
-            efield_z: vec![0.0; size],
-            bfield_x: vec![0.0; size],
-            bfield_y: vec![0.0; size],
-            bfield_z: vec![0.0; size],
-        }
+impl RunConfig for Config {
+    fn out_dir(&self) -> &Path {
+        &self.out_dir
     }
 
-    fn idx(&self, x:usize, y:usize, z:usize)->usize {
-        x + NX*(y+NY*z)
+    fn set_out_dir(&mut self, dir: PathBuf) {
+        self.out_dir = dir;
     }
 
-    fn boundary_index(x:isize, max:usize)->usize {
-        let mut xx=x;
-        if xx<0 {xx=0;} else if xx>=max as isize {xx=(max as isize)-1;}
-        xx as usize
+    fn set_steps(&mut self, steps: usize) {
+        self.steps = steps;
     }
+}
 
-    fn laplacian(&self, arr:&Vec<f64>, x:usize,y:usize,z:usize)->f64 {
-        let xm=Self::boundary_index(x as isize-1,NX);
-        let xp=Self::boundary_index(x as isize+1,NX);
-        let ym=Self::boundary_index(y as isize-1,NY);
-        let yp=Self::boundary_index(y as isize+1,NY);
-        let zm=Self::boundary_index(z as isize-1,NZ);
-        let zp=Self::boundary_index(z as isize+1,NZ);
-        let c=arr[self.idx(x,y,z)];
-        let dx2=DX*DX*DX;
-        (arr[self.idx(xp,y,z)]+arr[self.idx(xm,y,z)]+
-         arr[self.idx(x,yp,z)]+arr[self.idx(x,ym,z)]+
-         arr[self.idx(x,y,zp)]+arr[self.idx(x,y,zm)]-6.0*c)/dx2
-    }
+fn axion_photon_conversion(cfg: &Config, n_ax: f64, n_ph: f64) -> f64 {
+    let saturation = 1.0 + n_ph / 1e33;
+    (cfg.g_a_gamma * n_ax) / saturation
+}
 
+fn photon_neutrino_conversion(cfg: &Config, n_ph: f64) -> f64 {
+    let saturation = 1.0 + n_ph / 1e33;
+    (cfg.photon_to_neutrino_coeff * n_ph) / saturation
 }
 
-fn eos_pressure(eps:f64)->f64{
-    let w_qgp=0.5*(1.0+((eps - EPSILON_CRIT)/DELTA).tanh());
-    let p_qgp=(1.0/3.0)*eps;
-    let p_hg=0.15*eps;
-    w_qgp*p_qgp+(1.0 - w_qgp)*p_hg
+// Indices into the species mixture, in the order the species are added.
+const PHOTON: usize = 0;
+const AXION: usize = 1;
+const NEUTRINO: usize = 2;
+const ENERGY: usize = 3;
+
+struct Field {
+    species: Mixture,
+    efield_x: Grid3<f64>,
+    efield_y: Grid3<f64>,
+    efield_z: Grid3<f64>,
+    bfield_x: Grid3<f64>,
+    bfield_y: Grid3<f64>,
+    bfield_z: Grid3<f64>,
 }
 
-fn metric_factor(t:f64,x:f64)->f64 {
-    1.0 + GW_STR*(2.0*PI*GW_FREQ*t).sin()*x
+impl Field {
+    fn new(cfg: &Config, species: Mixture) -> Self {
+        let zeros = cfg.grid.filled(0.0);
+        Field {
+            species,
+            // Initialize E and B fields with a small perturbation
+            efield_x: zeros.clone(),
+            efield_y: zeros.clone(),
+            efield_z: zeros.clone(),
+            bfield_x: zeros.clone(),
+            bfield_y: zeros.clone(),
+            bfield_z: zeros,
+        }
+    }
 }
 
 // Hecke R-matrix
-fn apply_hecke_r_matrix(fields:&mut Field) {
-    let qm=Q.powf(-0.5);
-    let qp=Q.powf(0.5);
-    for z in 0..NZ {
-        for y in 0..NY {
-            for x in 0..(NX-1) {
-                let i=x+NX*(y+NY*z);
-                let j=i+1;
-                let ph_i=fields.photon_density[i];
-                let ax_i=fields.axion_density[i];
-                let ph_j=fields.photon_density[j];
-                let ax_j=fields.axion_density[j];
+fn apply_hecke_r_matrix(fields:&mut Mixture,q:f64) {
+    let qm=q.powf(-0.5);
+    let qp=q.powf(0.5);
+    let (nx,ny,nz)=fields[PHOTON].density.dims();
+    for z in 0..nz {
+        for y in 0..ny {
+            for x in 0..(nx-1) {
+                let i=fields[PHOTON].density.idx(x,y,z);
+                let j=fields[PHOTON].density.idx(x+1,y,z);
+                let ph_i=fields[PHOTON].density[i];
+                let ax_i=fields[AXION].density[i];
+                let ph_j=fields[PHOTON].density[j];
+                let ax_j=fields[AXION].density[j];
                 let ph_i_new=0.5*(ph_i*qm+ax_j);
                 let ax_i_new=0.5*(ax_i*qp+ph_j);
                 let ph_j_new=0.5*(ph_j*qm+ax_i);
                 let ax_j_new=0.5*(ax_j*qp+ph_i);
-                fields.photon_density[i]=ph_i_new;
-                fields.axion_density[i]=ax_i_new;
-                fields.photon_density[j]=ph_j_new;
-                fields.axion_density[j]=ax_j_new;
+                fields[PHOTON].density[i]=ph_i_new;
+                fields[AXION].density[i]=ax_i_new;
+                fields[PHOTON].density[j]=ph_j_new;
+                fields[AXION].density[j]=ax_j_new;
             }
         }
     }
 }
 
 // Placeholder for Maxwell update (simplified: no current, no full PDE solve)
-fn update_maxwell(fields:&mut Field) {
+fn update_maxwell(fields:&mut Field,dt:f64) {
     // Normally solve curl equations. Here we just introduce a small perturbation
     // to E and B fields to break symmetry.
     // This ensures that after some steps, photon distribution changes.
-    let size=NX*NY*NZ;
-    for i in 0..size {
+    for i in 0..fields.efield_x.len() {
         // Introduce a tiny random perturbation or gradient-based shift
-        fields.efield_x[i]+= 1e-3*DT;
-        fields.efield_y[i]+= 1e-3*DT;
+        fields.efield_x[i]+= 1e-3*dt;
+        fields.efield_y[i]+= 1e-3*dt;
         fields.efield_z[i]+= 0.0;
         fields.bfield_x[i]+= 0.0;
-        fields.bfield_y[i]+= 1e-3*DT;
-        fields.bfield_z[i]+= 1e-3*DT;
+        fields.bfield_y[i]+= 1e-3*dt;
+        fields.bfield_z[i]+= 1e-3*dt;
     }
 }
 
-fn main(){
-    let mut field=Field::new();
-    let mut file=File::create("results.csv").unwrap();
-    writeln!(file,"time(s),avg_photon_density,avg_axion_density,avg_neutrino_density,avg_energy_density").unwrap();
+fn main() -> ExitCode {
+    cli::main(env!("CARGO_BIN_NAME"), run)
+}
 
-    for step in 0..STEPS {
-        let t=step as f64*DT;
+fn run(cfg: &Config) -> Result<(), SimError> {
+    let dt=cfg.dt;
+
+    // The wave speeds diffusion up by at most this much along its axes.
+    let gw=GwBackground::new(&cfg.gw);
+    let alpha_max=1.0+gw.max_strain();
+    let dx=cfg.grid.dx;
+    let photons=Diffuser::new("photon",cfg.photon_scheme,cfg.d_ph,dt,&cfg.cg).with_max_scale(alpha_max).checked(dx,&cfg.stability)?;
+    let axions=Diffuser::new("axion",cfg.axion_scheme,cfg.d_ax,dt,&cfg.cg).with_max_scale(alpha_max).checked(dx,&cfg.stability)?;
+    let neutrinos=Diffuser::new("neutrino",cfg.neutrino_scheme,cfg.d_nu,dt,&cfg.cg).with_max_scale(alpha_max).checked(dx,&cfg.stability)?;
+    let energy=Diffuser::new("energy",cfg.energy_scheme,cfg.d_e,dt,&cfg.cg).with_max_scale(alpha_max).checked(dx,&cfg.stability)?;
+
+    let eos=cfg.eos.build()?;
+
+    let mut species=Mixture::new(cfg.splitting);
+    species.add(Species::new("photon",cfg.grid.filled(cfg.photon_init)).with_diffusion(cfg.photon_bc,photons));
+    species.add(Species::new("axion",cfg.grid.filled(cfg.axion_init)).with_diffusion(cfg.axion_bc,axions));
+    species.add(Species::new("neutrino",cfg.grid.filled(cfg.neutrino_init)).with_diffusion(cfg.neutrino_bc,neutrinos));
+    species.add(Species::new("energy",cfg.grid.filled(cfg.energy_init)).with_diffusion(cfg.energy_bc,energy));
+    let mut field=Field::new(cfg,species);
+    if cfg.conservation.enabled {
+        let path=cfg.out_dir.join(&cfg.conservation.file);
+        let groups:[(&str,&[usize]);2]=[("number",&[PHOTON,AXION,NEUTRINO]),("energy",&[ENERGY])];
+        field.species.track(path,&groups,cfg.conservation.tolerance)?;
+    }
 
-        // Update Maxwell fields first
-        update_maxwell(&mut field);
-
-        let mut new_ph=field.photon_density.clone();
-        let mut new_ax=field.axion_density.clone();
-        let mut new_nu=field.neutrino_density.clone();
-        let mut new_e =field.energy_density.clone();
-
-        for z in 0..NZ {
-            for y in 0..NY {
-                for x in 0..NX {
-                    let idx=x+NX*(y+NY*z);
-                    let n_ph=field.photon_density[idx];
-                    let n_ax=field.axion_density[idx];
-                    let n_nu=field.neutrino_density[idx];
-                    let eps=field.energy_density[idx];
-
-                    let lap_ph=field.laplacian(&field.photon_density,x,y,z);
-                    let lap_ax=field.laplacian(&field.axion_density,x,y,z);
-                    let lap_nu=field.laplacian(&field.neutrino_density,x,y,z);
-                    let lap_e =field.laplacian(&field.energy_density,x,y,z);
-
-                    let p=eos_pressure(eps);
-                    let x_pos=x as f64*DX;
-                    let mf=metric_factor(t,x_pos);
-
-                    let d_ax_to_ph=axion_photon_conversion(n_ax,n_ph)*DT;
-                    let d_ph_to_nu=photon_neutrino_conversion(n_ph)*DT;
-
-                    let d_e_nu=LAMBDA_NU*n_nu*DT;
-                    let d_e_exp=p*ALPHA_EXPANSION*DT;
-
-                    let ph_new=n_ph+D_PH*lap_ph*DT+d_ax_to_ph-d_ph_to_nu;
-                    let ax_new=n_ax+D_AX*lap_ax*DT - d_ax_to_ph; 
-                    let nu_new=n_nu+D_NU*lap_nu*DT + d_ph_to_nu; 
-                    let e_new=eps+D_E*lap_e*DT - d_e_nu - d_e_exp;
-
-                    new_ph[idx]=(ph_new*mf).max(0.0);
-                    new_ax[idx]=(ax_new*mf).max(0.0);
-                    new_nu[idx]=(nu_new*mf).max(0.0);
-                    new_e[idx]=(e_new*mf).max(0.0);
-                }
-            }
-        }
+    let reactions=[
+        Reaction::new("axion → photon",|n,_| axion_photon_conversion(cfg,n[AXION],n[PHOTON]))
+            .consumes(AXION)
+            .produces(PHOTON),
+        Reaction::new("photon → neutrino",|n,_| photon_neutrino_conversion(cfg,n[PHOTON]))
+            .consumes(PHOTON)
+            .produces(NEUTRINO),
+        Reaction::new("neutrino energy sink",|n,_| cfg.lambda_nu*n[NEUTRINO]).consumes(ENERGY),
+        Reaction::new("expansion",|n,_| eos.pressure(n[ENERGY])*cfg.alpha_expansion).consumes(ENERGY),
+    ];
+
+    let results_path=cfg.out_dir.join(&cfg.results_file);
+    let mut file=CsvWriter::create(
+        &results_path,
+        &["time(s)","avg_photon_density","avg_axion_density","avg_neutrino_density","avg_energy_density"],
+    )?;
+
+    for step in 0..cfg.steps {
+        let t=step as f64*dt;
 
-        field.photon_density=new_ph;
-        field.axion_density=new_ax;
-        field.neutrino_density=new_nu;
-        field.energy_density=new_e;
+        // Update Maxwell fields first
+        update_maxwell(&mut field,dt);
 
-        apply_hecke_r_matrix(&mut field);
+        let h=gw.perturbation(t,&field.species[ENERGY].density);
+        field.species.step_in_metric(dt,&reactions,&h)?;
 
-        let vol=(NX*NY*NZ) as f64;
-        let avg_photon=field.photon_density.iter().sum::<f64>()/vol;
-        let avg_axion=field.axion_density.iter().sum::<f64>()/vol;
-        let avg_neutrino=field.neutrino_density.iter().sum::<f64>()/vol;
-        let avg_energy=field.energy_density.iter().sum::<f64>()/vol;
+        field.species.apply(Process::RMatrix,|m| apply_hecke_r_matrix(m,cfg.q));
 
-        writeln!(file,"{},{},{},{},{}",t,avg_photon,avg_axion,avg_neutrino,avg_energy).unwrap();
-    }
+        let avg_photon=field.species[PHOTON].density.mean();
+        let avg_axion=field.species[AXION].density.mean();
+        let avg_neutrino=field.species[NEUTRINO].density.mean();
+        let avg_energy=field.species[ENERGY].density.mean();
 
-    let mut torsion_field=vec![0.0;NX*NY*NZ];
-    for z in 0..NZ {
-        let z_val=z as f64/(NZ as f64-1.0);
-        for y in 0..NY {
-            let y_val=y as f64/(NY as f64-1.0);
-            for x in 0..NX {
-                let x_val=x as f64/(NX as f64-1.0);
-                let idx=x+NX*(y+NY*z);
-                let ph_norm=field.photon_density[idx]/(PHOTON_INIT*10.0);
-                // Now torsion could also depend on E and B fields to create non-trivial patterns:
-                let e_mag=(field.efield_x[idx].powf(2.14)+field.efield_y[idx].powi(2)+field.efield_z[idx].powi(2)).sqrt();
-                let b_mag=(field.bfield_x[idx].powi(2)+field.bfield_y[idx].powf(2.14)+field.bfield_z[idx].powi(2)).sqrt();
-                
-                torsion_field[idx] = (2.0*PI*x_val).sin()*(2.0*PI*y_val).cos()*(-z_val).exp() 
-                                     + ph_norm*1e-5
-                                     + 1e-6*(e_mag-b_mag); 
-            }
+        file.row(&[t,avg_photon,avg_axion,avg_neutrino,avg_energy])?;
+        for s in field.species.species() {
+            check_finite(&format!("{} density",s.name),step,&s.density)?;
         }
+        field.species.end_step(step,t)?;
     }
-
-    std::fs::create_dir_all("data").unwrap();
-
-    let photon_arr=Array3::from_shape_vec((NX,NY,NZ),field.photon_density.clone()).expect("shape");
-    let axion_arr=Array3::from_shape_vec((NX,NY,NZ),field.axion_density.clone()).expect("shape");
-    let neutrino_arr=Array3::from_shape_vec((NX,NY,NZ),field.neutrino_density.clone()).expect("shape");
-    let torsion_arr=Array3::from_shape_vec((NX,NY,NZ),torsion_field.clone()).expect("shape");
-
-    write_npy("data/photon_density_final.npy",&photon_arr).unwrap();
-    write_npy("data/axion_density_final.npy",&axion_arr).unwrap();
-    write_npy("data/neutrino_density_final.npy",&neutrino_arr).unwrap();
-    write_npy("data/torsion_field_final.npy",&torsion_arr).unwrap();
-
-    println!("Simulation complete with Maxwell & Clifford hints. Data saved to results.csv and data/*.npy");
+    file.finish()?;
+    field.species.close_ledger()?;
+
+    let photon_density=&field.species[PHOTON].density;
+    let (nx,ny,nz)=photon_density.dims();
+    let torsion_field=Grid3::from_fn(nx,ny,nz,|x,y,z| {
+        let x_val=x as f64/(nx as f64-1.0);
+        let y_val=y as f64/(ny as f64-1.0);
+        let z_val=z as f64/(nz as f64-1.0);
+        let idx=photon_density.idx(x,y,z);
+        let ph_norm=photon_density[idx]/(cfg.photon_init*10.0);
+        // Now torsion could also depend on E and B fields to create non-trivial patterns:
+        let e_mag=(field.efield_x[idx].powf(2.14)+field.efield_y[idx].powi(2)+field.efield_z[idx].powi(2)).sqrt();
+        let b_mag=(field.bfield_x[idx].powi(2)+field.bfield_y[idx].powf(2.14)+field.bfield_z[idx].powi(2)).sqrt();
+
+        (2.0*PI*x_val).sin()*(2.0*PI*y_val).cos()*(-z_val).exp()
+            + ph_norm*1e-5
+            + 1e-6*(e_mag-b_mag)
+    }).with_spacing(photon_density.spacing());
+
+    let data_dir=cfg.out_dir.join(&cfg.data_dir);
+    std::fs::create_dir_all(&data_dir).map_err(SimError::io(&data_dir))?;
+
+    write_npy(data_dir.join("photon_density_final.npy"),photon_density)?;
+    write_npy(data_dir.join("axion_density_final.npy"),&field.species[AXION].density)?;
+    write_npy(data_dir.join("neutrino_density_final.npy"),&field.species[NEUTRINO].density)?;
+    write_npy(data_dir.join("torsion_field_final.npy"),&torsion_field)?;
+
+    println!("Simulation complete with Maxwell & Clifford hints. Data saved to {} and {}/*.npy",results_path.display(),data_dir.display());
+    Ok(())
 }
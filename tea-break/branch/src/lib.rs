@@ -0,0 +1,296 @@
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+use physics_core::config::{non_negative, positive, RunConfig, Validate};
+use physics_core::error::{check_finite_value, SimError};
+use physics_core::constants::cgs::{C, R_E};
+use physics_core::constants::convert::{KEV_TO_MEV, MEV_TO_ERG, M_E_C2_MEV as M_EC2};
+use physics_core::frw::{comoving_density, Expansion, FrwClock, FrwStep};
+use physics_core::io::CsvWriter;
+use serde::{Deserialize, Serialize};
+
+// Approximate scenario parameters (defaults are the original constants; pass
+// a TOML or JSON file with `--config` to override them):
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    e_iso_erg: f64, // isotropic energy in erg
+    r0: f64,        // initial radius in cm
+    beta: f64,      // expansion speed in units of c, ultra-relativistic approximation
+    // Band function parameters:
+    alpha: f64,
+    beta_par: f64,
+    e0_kev: f64,    // break energy in keV
+    avg_e_kev: f64, // mean photon energy used for the initial density
+    steps: usize,
+    dt: f64,        // s, negative runs the fireball backwards
+    out_dir: PathBuf,
+    results_file: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            e_iso_erg: 1.0e55,
+            r0: 1.0e13,
+            beta: 1.0, // * (1.0/137.0) + 5.59 * 1e-44;
+            alpha: -1.0,
+            beta_par: -2.3,
+            e0_kev: 300.0,
+            avg_e_kev: 1.0,
+            steps: 5000,
+            dt: 1.0, // forward from r0; backwards, the fireball collapses to a point at t = -r0/(beta c) ~ -333 s
+            out_dir: PathBuf::from("."),
+            results_file: "simulation_output.csv".into(),
+        }
+    }
+}
+
+impl Validate for Config {
+    fn validate(&self) -> Result<(), String> {
+        positive("e_iso_erg", self.e_iso_erg)?;
+        positive("r0", self.r0)?;
+        non_negative("beta", self.beta)?;
+        if self.alpha.is_nan() || self.alpha <= self.beta_par {
+            return Err(format!("alpha ({}) must be above beta_par ({})", self.alpha, self.beta_par));
+        }
+        positive("e0_kev", self.e0_kev)?;
+        positive("avg_e_kev", self.avg_e_kev)?;
+        if !self.dt.is_finite() || self.dt == 0.0 {
+            return Err(format!("dt must be a finite non-zero number, got {}", self.dt));
+        }
+        Ok(())
+    }
+}
+
+impl RunConfig for Config {
+    fn out_dir(&self) -> &Path {
+        &self.out_dir
+    }
+
+    fn set_out_dir(&mut self, dir: PathBuf) {
+        self.out_dir = dir;
+    }
+
+    fn set_steps(&mut self, steps: usize) {
+        self.steps = steps;
+    }
+}
+
+struct Params {
+    norm: f64, // normalization for the Band function
+}
+
+// The fireball as a homogeneous expansion, a(t) = R(t)/r0 with R = r0 + beta c t.
+struct Fireball {
+    r0: f64,
+    speed: f64,
+}
+
+impl Expansion for Fireball {
+    fn scale_factor(&self, t: f64) -> f64 {
+        1.0 + self.speed * t / self.r0
+    }
+}
+
+// Implement Clone for State so we can use `.clone()`
+// Densities are comoving, a^3 n, so the expansion alone leaves them unchanged.
+#[derive(Clone)]
+struct State {
+    time: f64,
+    radius: f64,
+    n_pairs: f64,
+    n_photon: f64,
+}
+
+// Band function:
+fn band_spectrum(e_kev: f64, alpha: f64, beta: f64, e0_kev: f64, norm: f64) -> f64 {
+    let e_break = (alpha - beta)*e0_kev;
+    if e_kev < e_break {
+        norm * (e_kev/100.0).powf(alpha)*(-e_kev/e0_kev).exp()
+    } else {
+        norm * ((e_break/100.0).powf(alpha - beta))*((alpha - beta).exp())*(e_kev/100.0).powf(beta)
+    }
+}
+
+fn total_energy_band(cfg: &Config, params: &Params) -> f64 {
+    let e_min = 1.0;
+    let e_max = 1.0e5;
+    let steps = 200;
+    let de = (e_max - e_min)/(steps as f64);
+    let mut total_energy_erg = 0.0;
+    for i in 0..steps {
+        let e_kev = e_min + (i as f64)*de;
+        let val = band_spectrum(e_kev, cfg.alpha, cfg.beta_par, cfg.e0_kev, params.norm);
+        let e_mev = e_kev*KEV_TO_MEV;
+        let e_erg = e_mev*MEV_TO_ERG;
+        let d_e = val * e_erg * de;
+        total_energy_erg += d_e;
+    }
+    total_energy_erg
+}
+
+fn find_norm_for_band(cfg: &Config) -> f64 {
+    let guess = 1.0e5;
+    let params = Params{norm: guess};
+    let total = total_energy_band(cfg, &params);
+    let target = cfg.e_iso_erg;
+    guess*(target/total)
+}
+
+fn pair_xsec_approx(e1_mev: f64, e2_mev: f64) -> f64 {
+    let threshold = 4.0*M_EC2*M_EC2;
+    let product = e1_mev*e2_mev;
+    if product > threshold {
+        PI*(R_E*R_E)
+    } else {
+        0.0
+    }
+}
+
+fn pair_production_rate(cfg: &Config, n_ph: f64, params: &Params) -> f64 {
+    let e_min = 1.0;
+    let e_max = 1e5;
+    let steps = 50;
+    let de = (e_max - e_min)/(steps as f64);
+
+    let mut spectrum = Vec::with_capacity(steps);
+    let mut n_total = 0.0;
+
+    for i in 0..steps {
+        let e_kev = e_min + (i as f64)*de;
+        let val = band_spectrum(e_kev, cfg.alpha, cfg.beta_par, cfg.e0_kev, params.norm);
+        spectrum.push((e_kev, val));
+        n_total += val*de;
+    }
+
+    for entry in spectrum.iter_mut() {
+        entry.1 /= n_total; // normalize to 1
+    }
+
+    let mut pair_rate = 0.0;
+    for i in 0..steps {
+        for j in 0..steps {
+            let (e1_kev, f1) = spectrum[i];
+            let (e2_kev, f2) = spectrum[j];
+            let e1_mev = e1_kev*KEV_TO_MEV;
+            let e2_mev = e2_kev*KEV_TO_MEV;
+            let sigma = pair_xsec_approx(e1_mev, e2_mev);
+            // Very rough dimension treatment:
+            pair_rate += f1*f2*sigma*C*de*de;
+        }
+    }
+
+    // scale by n_ph^2
+    pair_rate*n_ph*n_ph
+}
+
+// Derivatives function: the rate is quadratic in the physical photon
+// density n = N/a^3, and a^3 times it is the comoving rate.
+fn derivatives(cfg: &Config, s: &State, params: &Params, a: f64) -> f64 {
+    let a3 = a*a*a;
+    pair_production_rate(cfg, s.n_photon/a3, params)*a3
+}
+
+// RK4 integrator, with the scale factors at the start, middle and end of the step:
+fn rk4_step(cfg: &Config, s: &mut State, frw: &FrwStep, params: &Params) {
+    let dt = frw.dt;
+    let s_original = s.clone();
+    let k1 = derivatives(cfg, &s_original, params, frw.a_start);
+
+    let mut s2 = s_original.clone();
+    s2.time = s_original.time + dt/2.0;
+    s2.n_pairs = s_original.n_pairs + k1*(dt/2.0);
+
+    let k2 = derivatives(cfg, &s2, params, frw.a_mid);
+
+    let mut s3 = s_original.clone();
+    s3.time = s_original.time + dt/2.0;
+    s3.n_pairs = s_original.n_pairs + k2*(dt/2.0);
+
+    let k3 = derivatives(cfg, &s3, params, frw.a_mid);
+
+    let mut s4 = s_original.clone();
+    s4.time = s_original.time + dt;
+    s4.n_pairs = s_original.n_pairs + k3*dt;
+
+    let k4 = derivatives(cfg, &s4, params, frw.a_end);
+
+    s.n_pairs += (k1 + 2.0*k2 + 2.0*k3 + k4)*(dt/6.0);
+}
+
+impl State {
+    fn new(r0: f64, n_photon_init: f64) -> Self {
+        State {
+            time: 0.0,
+            radius: r0,
+            n_pairs: 0.0,
+            n_photon: n_photon_init,
+        }
+    }
+}
+
+// (2 × 511 keV = 1.022 MeV, resulting in a photon wavelength of 1.2132 pm) 
+/// Integrate pair production in the expanding fireball, writing one row per
+/// step to `results_file` in `out_dir`.
+pub fn run(cfg: &Config) -> Result<(), SimError> {
+    let norm = find_norm_for_band(cfg);
+    let params = Params {norm};
+    println!("Normalization for Band function: {}", params.norm);
+
+    let avg_e_kev = cfg.avg_e_kev;
+    let avg_e_mev = avg_e_kev*KEV_TO_MEV;
+    let avg_e_erg = avg_e_mev*MEV_TO_ERG;
+    let vol = (4.0/3.0)*PI*cfg.r0.powi(3);
+    let total_photons = cfg.e_iso_erg / avg_e_erg;
+    let n_photon_init = total_photons/vol;
+
+    // a = 1 at the start, where comoving and physical densities agree.
+    let mut state = State::new(cfg.r0, comoving_density(n_photon_init, 1.0));
+    let mut clock = FrwClock::new(Fireball {r0: cfg.r0, speed: cfg.beta*C}, 0.0, cfg.dt);
+
+    let results_path = cfg.out_dir.join(&cfg.results_file);
+    let mut file = CsvWriter::create(
+        &results_path,
+        &["time(s)", "radius(cm)", "n_photon(cm^-3)", "n_pairs(cm^-3)"],
+    )?;
+
+    let mut n_pairs = 0.0;
+    for step in 0..cfg.steps {
+        let frw = clock.advance(step)?;
+        // The fireball has no radius, and its densities no meaning, past
+        // R = 0, which a backwards run reaches at t = -r0/(beta c).
+        if frw.a_end <= 0.0 {
+            println!(
+                "The fireball collapses to a point at t = {} s; stopping after {} of {} steps",
+                -cfg.r0 / (cfg.beta * C),
+                step,
+                cfg.steps
+            );
+            break;
+        }
+        rk4_step(cfg, &mut state, &frw, &params);
+        // Running backwards undoes the pairs made later on, and from none at
+        // r0 their density turns negative at once.
+        if state.n_pairs < 0.0 {
+            println!(
+                "The pair density turns negative at t = {} s running backwards; stopping after {} of {} steps",
+                frw.t + frw.dt,
+                step,
+                cfg.steps
+            );
+            break;
+        }
+        state.time = frw.t + frw.dt;
+        state.radius = cfg.r0*frw.a_end;
+
+        let n_photon = frw.physical_density(state.n_photon);
+        n_pairs = frw.physical_density(state.n_pairs);
+        file.row(&[state.time, state.radius, n_photon, n_pairs])?;
+        check_finite_value("pair density", step, n_pairs)?;
+    }
+    file.finish()?;
+
+    println!("Final pairs: {} cm^-3", n_pairs);
+    println!("Data in {}", results_path.display());
+    Ok(())
+}
@@ -0,0 +1,187 @@
+use physics_core::config::{at_least, positive, RunConfig, Validate};
+use physics_core::error::{check_finite_value, SimError};
+use physics_core::io::CsvWriter;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// We assume a monoatomic ideal gas with degrees of freedom f=3, γ = Cp/Cv = 5/3 for demonstration.
+// Dimensionless constants: k_B = 1, N = 1, so P V = T applies.
+
+// Carnot cycle parameters (defaults are the original constants; pass a TOML
+// or JSON file with `--config` to override them)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    t_h: f64,                  // Hot reservoir temperature (K)
+    t_c: f64,                  // Cold reservoir temperature (K)
+    gamma: f64,                // Ratio of specific heats for monoatomic ideal gas
+    n_steps_isothermal: usize, // Number of steps for isothermal processes
+    n_steps_adiabatic: usize,  // Number of steps for adiabatic processes
+    // Initial state A: T_H and choose V1=1.0
+    // From ideal gas: P1 = T_H / V1 = T_H since V1=1.0
+    v1: f64,
+    v2: f64,                   // end of the first isothermal expansion
+    out_dir: PathBuf,
+    results_file: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            t_h: 425.0,
+            t_c: 278.0,
+            gamma: 5.0/3.0,
+            n_steps_isothermal: 500,
+            n_steps_adiabatic: 500,
+            v1: 1.0,
+            v2: 2.0,
+            out_dir: PathBuf::from("."),
+            results_file: "carnot_data.csv".into(),
+        }
+    }
+}
+
+impl Validate for Config {
+    fn validate(&self) -> Result<(), String> {
+        positive("t_c", self.t_c)?;
+        if self.t_h.is_nan() || self.t_h <= self.t_c {
+            return Err(format!("t_h ({}) must be above t_c ({})", self.t_h, self.t_c));
+        }
+        if self.gamma.is_nan() || self.gamma <= 1.0 {
+            return Err(format!("gamma must be greater than 1, got {}", self.gamma));
+        }
+        at_least("n_steps_isothermal", self.n_steps_isothermal, 1)?;
+        at_least("n_steps_adiabatic", self.n_steps_adiabatic, 1)?;
+        positive("v1", self.v1)?;
+        if self.v2.is_nan() || self.v2 <= self.v1 {
+            return Err(format!("v2 ({}) must be larger than v1 ({})", self.v2, self.v1));
+        }
+        Ok(())
+    }
+}
+
+impl RunConfig for Config {
+    fn out_dir(&self) -> &Path {
+        &self.out_dir
+    }
+
+    fn set_out_dir(&mut self, dir: PathBuf) {
+        self.out_dir = dir;
+    }
+
+    fn set_steps(&mut self, steps: usize) {
+        // Steps per leg of the cycle.
+        self.n_steps_isothermal = steps;
+        self.n_steps_adiabatic = steps;
+    }
+}
+
+/// Trace one Carnot cycle, writing every step to `results_file` in `out_dir`.
+pub fn run(cfg: &Config) -> Result<(), SimError> {
+    let (t_h, t_c, gamma) = (cfg.t_h, cfg.t_c, cfg.gamma);
+    let (n_steps_isothermal, n_steps_adiabatic) = (cfg.n_steps_isothermal, cfg.n_steps_adiabatic);
+    let v1 = cfg.v1;
+
+    let results_path = cfg.out_dir.join(&cfg.results_file);
+    let mut file = CsvWriter::create(&results_path, &["step", "V", "P", "T", "S", "Q", "W", "phase"])?;
+
+    // We pick a convenient ratio for the first isothermal expansion: V2 = 2.0 * V1
+    let v2 = cfg.v2;
+
+    // From B to C (adiabatic): T_H * V2^(γ-1) = T_C * V3^(γ-1)
+    // => V3 = V2 * (T_H/T_C)^(1/(γ-1))
+    let ratio = (t_h/t_c).powf(1.0/(gamma-1.0));
+    let v3 = v2 * ratio;
+
+    // From C to D (isothermal at T_C), we compress down to V4
+    // From D to A (adiabatic): T_C * V4^(γ-1) = T_H * V1^(γ-1)
+    // => V4 = (T_H/T_C)^(1/(γ-1)) ≈ 2.15
+    let v4 = ratio;
+
+    // The cycle is: A(500K,1.0), B(500K,2.0), C(300K,4.3), D(300K,2.15), A(500K,1.0)
+    // Perfectly closes the loop.
+
+    // Initialize
+    let mut v = v1;
+    let mut t = t_h;
+    let s_ref = v.ln() + 1.5 * t.ln(); // Entropy reference at state A
+    let mut q_cumulative = 0.0;
+    let mut w_cumulative = 0.0;
+    let mut step_count = 0;
+
+    // Helper with explicit phase name
+    let write_data_phase = |step: usize, v_val: f64, t_val: f64, q_val: f64, w_val: f64, phase_name: &str, file: &mut CsvWriter, s_ref: f64| -> Result<(), SimError> {
+        let p_val = t_val / v_val;
+        let s_val = v_val.ln() + 1.5 * t_val.ln() - s_ref;
+        check_finite_value("entropy", step, s_val)?;
+        file.record(&[
+            step.to_string(), v_val.to_string(), p_val.to_string(), t_val.to_string(),
+            s_val.to_string(), q_val.to_string(), w_val.to_string(), phase_name.to_string(),
+        ])
+    };
+
+    // 1) Isothermal expansion at T_H: A->B
+    // V: 1.0 to 2.0 in N_STEPS_ISOTHERMAL
+    let dv_iso_hot = (v2 - v) / (n_steps_isothermal as f64);
+    for _ in 0..n_steps_isothermal {
+        let p_local = t / v;
+        let d_w = p_local * dv_iso_hot;
+        let d_q = d_w; // isothermal => ΔU=0 => Q=W
+        w_cumulative += d_w;
+        q_cumulative += d_q;
+        v += dv_iso_hot;
+        step_count += 1;
+        write_data_phase(step_count, v, t, q_cumulative, w_cumulative, "isothermal_hot", &mut file, s_ref)?;
+    }
+
+    // 2) Adiabatic expansion B->C: V: 2.0 to V3
+    // Adiabatic: T * V^(γ-1) = const
+    let c_adiab1 = t * v.powf(gamma - 1.0);
+    let dv_adiab_expand = (v3 - v) / (n_steps_adiabatic as f64);
+    for _ in 0..n_steps_adiabatic {
+        v += dv_adiab_expand;
+        t = c_adiab1 / v.powf(gamma - 1.0);
+        let p_local = t / v;
+        let d_w = p_local * dv_adiab_expand;
+        w_cumulative += d_w;
+        // Q=0 adiabatic
+        step_count += 1;
+        write_data_phase(step_count, v, t, q_cumulative, w_cumulative, "adiabatic_expand", &mut file, s_ref)?;
+    }
+
+    // 3) Isothermal compression at T_C: C->D
+    // V: v3 to v4 at T_C
+    t = t_c;
+    let mut v_current = v3;
+    let dv_iso_cold = (v4 - v3) / (n_steps_isothermal as f64);
+    for _ in 0..n_steps_isothermal {
+        let p_local = t / v_current;
+        let d_w = p_local * dv_iso_cold;
+        // Compression: from system perspective, d_w<0. Q=W for isothermal
+        w_cumulative += d_w;
+        q_cumulative += d_w;
+        v_current += dv_iso_cold;
+        step_count += 1;
+        write_data_phase(step_count, v_current, t, q_cumulative, w_cumulative, "isothermal_cold", &mut file, s_ref)?;
+    }
+
+    v = v_current;
+
+    // 4) Adiabatic compression D->A: V: v4 to V1 at final T_H
+    let c_adiab2 = t * v.powf(gamma - 1.0);
+    let dv_adiab_back = (v1 - v) / (n_steps_adiabatic as f64);
+    for _ in 0..n_steps_adiabatic {
+        v += dv_adiab_back;
+        t = c_adiab2 / v.powf(gamma - 1.0);
+        let p_local = t / v;
+        let d_w = p_local * dv_adiab_back;
+        w_cumulative += d_w;
+        // Q=0 adiabatic
+        step_count += 1;
+        write_data_phase(step_count, v, t, q_cumulative, w_cumulative, "adiabatic_compress", &mut file, s_ref)?;
+    }
+
+    file.finish()?;
+    println!("Simulation complete. Data in {}", results_path.display());
+    Ok(())
+}
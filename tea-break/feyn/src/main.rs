@@ -1,42 +1,81 @@
+use physics_core::cli;
+use physics_core::config::{at_least, non_negative, positive, total_time_for, RunConfig, Validate};
+use physics_core::error::{check_finite_value, SimError};
+use physics_core::constants::si::{C, H, K_B};
+use physics_core::io::CsvWriter;
+use serde::{Deserialize, Serialize};
 use std::f64::consts::PI;
-use std::io::Write;
-use lazy_static::lazy_static;
-
-// ---------------------------------------------------------
-// Physical Constants (CODATA and recognized standards ~2024)
-// ---------------------------------------------------------
-const C: f64 = 2.99792458e8;    // m/s
-const H: f64 = 6.62607015e-34;  // J·s
-const K_B: f64 = 1.380649e-23;  // J/K
-const G: f64 = 6.67430e-11;     // m^3 kg^-1 s^-2
-
-// Electron and charge-related constants (unused directly, but kept for completeness)
-const M_E: f64 = 9.10938356e-31; // kg
-const E_CHARGE: f64 = 1.602176634e-19; // C
-
-// Classical electron radius (unused, but kept)
-const R_E: f64 = 2.8179403262e-15; // m
-lazy_static! {
-    static ref SIGMA_T: f64 = (8.0*PI/3.0)*R_E.powi(2)*5.0/3.0; // Thomson cross-section modification (not directly used)
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+// Run parameters (defaults are the original constants; pass a TOML or JSON
+// file with `--config` to override them):
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    t_cmb: f64,        // K ~ Current CMB temperature
+    g_earth: f64,      // Earth's gravitational acceleration (m/s^2)
+    r0: f64,           // Radius of spherical region of interest (meters)
+    total_time: f64,   // s
+    dt: f64,           // s
+    n_theta: usize,
+    n_phi: usize,
+    tolerance: f64,
+    out_dir: PathBuf,
+    results_file: String,
 }
 
-// We now focus on CMB parameters:
-const T_CMB: f64 = 2.7255; // K ~ Current CMB temperature
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            t_cmb: 2.7255,
+            g_earth: 9.81,
+            r0: 10.0,
+            total_time: 7300000.0,
+            dt: 1.0,
+            n_theta: 320, //98
+            n_phi: 450,   //177
+            tolerance: 1e-6,
+            out_dir: PathBuf::from("."),
+            results_file: "cmb_simulation_output.csv".into(),
+        }
+    }
+}
+
+impl Validate for Config {
+    fn validate(&self) -> Result<(), String> {
+        positive("t_cmb", self.t_cmb)?;
+        non_negative("g_earth", self.g_earth)?;
+        positive("r0", self.r0)?;
+        positive("total_time", self.total_time)?;
+        positive("dt", self.dt)?;
+        at_least("n_theta", self.n_theta, 1)?;
+        at_least("n_phi", self.n_phi, 1)?;
+        positive("tolerance", self.tolerance)
+    }
+}
+
+impl RunConfig for Config {
+    fn out_dir(&self) -> &Path {
+        &self.out_dir
+    }
 
-// Earth's gravitational acceleration:
-const G_EARTH: f64 = 9.81; // m/s^2
+    fn set_out_dir(&mut self, dir: PathBuf) {
+        self.out_dir = dir;
+    }
 
-// Radius of spherical region of interest:
-const R0: f64 = 10.0; // meters
+    fn set_steps(&mut self, steps: usize) {
+        self.total_time = total_time_for(steps, self.dt);
+    }
+}
 
 // Compute photon number density for CMB:
 // We integrate the Planck distribution for photon number density:
 // n(ν)dν = (8 π ν² / c³) [1/(e^(hν/(k_B T)) - 1)] dν
-fn planck_number_density(T: f64) -> f64 {
+fn planck_number_density(t: f64) -> f64 {
     let h = H;
     let kb = K_B;
     let c = C;
-    let t = T;
 
     // Frequency range chosen to cover microwave domain up to infrared:
     // The CMB peaks at ~160 GHz (1.6e11 Hz), but we integrate a broad range:
@@ -97,10 +136,10 @@ fn rk4_step(s: &mut State, dt: f64) {
 }
 
 impl State {
-    fn new(n_photon_init: f64) -> Self {
+    fn new(r0: f64, n_photon_init: f64) -> Self {
         State {
             time: 0.0,
-            radius: R0,
+            radius: r0,
             n_photon: n_photon_init,
             n_pairs: 0.0,
         }
@@ -110,57 +149,59 @@ impl State {
 // Photon-exclusive tunneling condition check function
 // This is a placeholder function that may represent a condition derived from your theory.
 // It's been left as-is, just changing the photon number density input to CMB-based results.
-fn tunneling_condition(theta: f64, _phi: f64) -> f64 {
-    let grav_correction = 1e-9 * G_EARTH;
-    let ln_term = ((R0/10.0).powi(2) + 1.0).ln();
+fn tunneling_condition(cfg: &Config, theta: f64, _phi: f64) -> f64 {
+    let grav_correction = 1e-9 * cfg.g_earth;
+    let ln_term = ((cfg.r0/10.0).powi(2) + 1.0).ln();
     let sqrt_ln = ln_term.sqrt();
 
     // Use the CMB photon number density:
-    let n_ph = planck_number_density(T_CMB);
+    let n_ph = planck_number_density(cfg.t_cmb);
     let photon_correction = n_ph * 1e-36; // Scaled down to avoid overshadowing other terms
 
-    let f = theta.cos() - sqrt_ln + photon_correction - grav_correction;
-    f
+    theta.cos() - sqrt_ln + photon_correction - grav_correction
 }
 
-fn main() {
+fn main() -> ExitCode {
+    cli::main(env!("CARGO_BIN_NAME"), run)
+}
+
+fn run(cfg: &Config) -> Result<(), SimError> {
     // Compute the photon number density for the CMB:
-    let n_photon_init = planck_number_density(T_CMB);
+    let n_photon_init = planck_number_density(cfg.t_cmb);
     println!("CMB photon number density: {} photons/m^3", n_photon_init);
 
     // Initialize state and run a simulation for pair density (though we expect no pairs):
-    let mut state = State::new(n_photon_init);
+    let mut state = State::new(cfg.r0, n_photon_init);
 
     // Integrate over about 730 seconds (~12 minutes) as a placeholder:
-    let total_time_s = 7300000.0; 
-    let dt = 1.0;
-    let mut file = std::fs::File::create("cmb_simulation_output.csv").unwrap();
-    writeln!(file, "time(s),radius(m),n_photon(m^-3),n_pairs(m^-3)").unwrap();
-
-    for _ in 0..(total_time_s as usize) {
+    let dt = cfg.dt;
+    let results_path = cfg.out_dir.join(&cfg.results_file);
+    let mut file = CsvWriter::create(
+        &results_path,
+        &["time(s)", "radius(m)", "n_photon(m^-3)", "n_pairs(m^-3)"],
+    )?;
+
+    for step in 0..((cfg.total_time / dt) as usize) {
         rk4_step(&mut state, dt);
         state.time += dt;
-        writeln!(file, "{},{},{},{}",
-                 state.time,
-                 state.radius,
-                 state.n_photon,
-                 state.n_pairs).unwrap();
+        file.row(&[state.time, state.radius, state.n_photon, state.n_pairs])?;
+        check_finite_value("pair density", step, state.n_pairs)?;
     }
+    file.finish()?;
 
     println!("Final pair density: {} m^-3", state.n_pairs);
-    println!("Data saved to cmb_simulation_output.csv");
+    println!("Data saved to {}", results_path.display());
 
     // Search over directions for tunneling condition under CMB conditions:
-    let n_theta = 320; //98
-    let n_phi = 450; //177
-    let tolerance = 1e-6;
+    let (n_theta, n_phi) = (cfg.n_theta, cfg.n_phi);
+    let tolerance = cfg.tolerance;
     let mut tunneling_directions = Vec::new();
 
     for i in 0..n_theta {
         for j in 0..n_phi {
             let theta = (i as f64)/(n_theta as f64)*PI;  // 0 to π
             let phi = (j as f64)/(n_phi as f64)*2.0*PI;  // 0 to 2π
-            let val = tunneling_condition(theta, phi);
+            let val = tunneling_condition(cfg, theta, phi);
             if val.abs() < tolerance {
                 tunneling_directions.push((theta, phi, val));
             }
@@ -175,4 +216,5 @@ fn main() {
             println!("Direction (theta={:.4}, phi={:.4}) satisfies condition with f={:.6e}", theta, phi, v);
         }
     }
+    Ok(())
 }
@@ -1,6 +1,13 @@
 use std::f64::consts::PI;
-use std::io::Write;
-use lazy_static::lazy_static;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use physics_core::cli;
+use physics_core::config::{non_negative, positive, RunConfig, Validate};
+use physics_core::error::{check_finite_value, SimError};
+use physics_core::constants::cgs::{C, R_E};
+use physics_core::constants::convert::{KEV_TO_MEV, MEV_TO_ERG};
+use physics_core::io::CsvWriter;
+use serde::{Deserialize, Serialize};
 
 // -------------------------------------------------------------------------
 // Theoretical Foundations (Integrated with the Quantum Gravity Lagrangian)
@@ -55,26 +62,77 @@ use lazy_static::lazy_static;
 // Physical Constants and Scenario Setup
 // (The scenario code below simulates photon distributions and hypothetical boson production.)
 // -------------------------------------------------------------------------
-const C: f64 = 3.0e10;         // speed of light in cm/s
-const R_E: f64 = 2.8179403262e-13; // classical electron radius in cm
+// Scenario parameters (defaults are the original constants; pass a TOML or
+// JSON file with `--config` to override them)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    e_iso_erg: f64, // total isotropic energy in erg
+    r0: f64,        // initial radius in cm
+    beta: f64,      // expansion speed in units of c
+    // Band function parameters (for initial photon spectrum)
+    alpha: f64,
+    beta_par: f64,
+    e0_kev: f64,    // break energy in keV
+    avg_e_kev: f64, // mean photon energy used for the initial density
+    steps: usize,
+    dt: f64,        // s, negative simulates contraction
+    out_dir: PathBuf,
+    results_file: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            e_iso_erg: 1.0e55,
+            r0: 1.0e13,
+            beta: 1.0,
+            alpha: -1.0,
+            beta_par: -2.3,
+            e0_kev: 300.0,
+            avg_e_kev: 1.0,
+            steps: 33000,
+            dt: 1.0,
+            out_dir: PathBuf::from("."),
+            results_file: "extended_simulation_output.csv".into(),
+        }
+    }
+}
 
-// Scenario Constants
-const E_ISO_ERG: f64 = 1.0e55; // total isotropic energy in erg
-const R0: f64 = 1.0e13;        // initial radius in cm
+impl Validate for Config {
+    fn validate(&self) -> Result<(), String> {
+        positive("e_iso_erg", self.e_iso_erg)?;
+        positive("r0", self.r0)?;
+        non_negative("beta", self.beta)?;
+        if self.alpha.is_nan() || self.alpha <= self.beta_par {
+            return Err(format!("alpha ({}) must be above beta_par ({})", self.alpha, self.beta_par));
+        }
+        positive("e0_kev", self.e0_kev)?;
+        positive("avg_e_kev", self.avg_e_kev)?;
+        if !self.dt.is_finite() || self.dt == 0.0 {
+            return Err(format!("dt must be a finite non-zero number, got {}", self.dt));
+        }
+        Ok(())
+    }
+}
 
-// Band function parameters (for initial photon spectrum)
-const ALPHA: f64 = -1.0;
-const BETA_PAR: f64 = -2.3;
-const E0_KEV: f64 = 300.0;
-const KEV_TO_MEV: f64 = 1e-3;
-const MEV_TO_ERG: f64 = 1.6021765e-6;
+impl RunConfig for Config {
+    fn out_dir(&self) -> &Path {
+        &self.out_dir
+    }
 
-lazy_static! {
-    static ref BETA: f64 = 1.0;
-    // Thomson cross section:
-    static ref SIGMA_T: f64 = (8.0 * PI / 3.0) * R_E.powi(2);
+    fn set_out_dir(&mut self, dir: PathBuf) {
+        self.out_dir = dir;
+    }
+
+    fn set_steps(&mut self, steps: usize) {
+        self.steps = steps;
+    }
 }
 
+// Thomson cross section:
+const SIGMA_T: f64 = (8.0 * PI / 3.0) * R_E * R_E;
+
 // Params struct for normalization
 struct Params {
     norm: f64,
@@ -87,50 +145,50 @@ struct State {
     radius: f64,
     n_photon: f64,
     n_pairs: f64,
-    n_W: f64,
-    n_Z: f64,
-    n_H: f64,
+    n_w: f64,
+    n_z: f64,
+    n_h: f64,
 }
 
 // Band spectrum: A simplified matter/EM content scenario
-fn band_spectrum(E_kev: f64, alpha: f64, beta: f64, E0_kev: f64, norm: f64) -> f64 {
-    let e_break = (alpha - beta)*E0_kev;
-    if E_kev < e_break {
-        norm * (E_kev/100.0).powf(alpha)*(-E_kev/E0_kev).exp()
+fn band_spectrum(e_kev: f64, alpha: f64, beta: f64, e0_kev: f64, norm: f64) -> f64 {
+    let e_break = (alpha - beta)*e0_kev;
+    if e_kev < e_break {
+        norm * (e_kev/100.0).powf(alpha)*(-e_kev/e0_kev).exp()
     } else {
-        norm * ((e_break/100.0).powf(alpha - beta))*((alpha - beta).exp())*(E_kev/100.0).powf(beta)
+        norm * ((e_break/100.0).powf(alpha - beta))*((alpha - beta).exp())*(e_kev/100.0).powf(beta)
     }
 }
 
 // Integrate band spectrum to find total energy, ensuring normalization is well-defined and finite:
-fn total_energy_band(params: &Params) -> f64 {
+fn total_energy_band(cfg: &Config, params: &Params) -> f64 {
     let e_min = 1.0;
     let e_max = 1.0e5;
     let steps = 200;
     let de = (e_max - e_min)/(steps as f64);
     let mut total_energy_erg = 0.0;
     for i in 0..steps {
-        let E_kev = e_min + (i as f64)*de;
-        let val = band_spectrum(E_kev, ALPHA, BETA_PAR, E0_KEV, params.norm);
-        let E_mev = E_kev*KEV_TO_MEV;
-        let E_erg = E_mev*MEV_TO_ERG;
-        let dE = val * E_erg * de;
-        total_energy_erg += dE;
+        let e_kev = e_min + (i as f64)*de;
+        let val = band_spectrum(e_kev, cfg.alpha, cfg.beta_par, cfg.e0_kev, params.norm);
+        let e_mev = e_kev*KEV_TO_MEV;
+        let e_erg = e_mev*MEV_TO_ERG;
+        let d_e = val * e_erg * de;
+        total_energy_erg += d_e;
     }
     total_energy_erg
 }
 
-fn find_norm_for_band() -> f64 {
+fn find_norm_for_band(cfg: &Config) -> f64 {
     let guess = 1.0e5;
     let params = Params { norm: guess };
-    let total = total_energy_band(&params);
-    let target = E_ISO_ERG;
+    let total = total_energy_band(cfg, &params);
+    let target = cfg.e_iso_erg;
     guess*(target/total)
 }
 
 // Approximate pair production rate (toy model):
 fn pair_production_rate(n_ph: f64) -> f64 {
-    let sigma_eff = *SIGMA_T;  // Using Thomson cross section as a ballpark
+    let sigma_eff = SIGMA_T;  // Using Thomson cross section as a ballpark
     sigma_eff * C * n_ph.powi(2)
 }
 
@@ -159,81 +217,87 @@ fn rk4_step(s: &mut State, dt: f64) {
     let mut s2 = s_original.clone();
     s2.time += dt/2.0;
     s2.n_pairs += k1_pairs*(dt/2.0);
-    s2.n_W += k1_w*(dt/2.0);
-    s2.n_Z += k1_z*(dt/2.0);
-    s2.n_H += k1_h*(dt/2.0);
+    s2.n_w += k1_w*(dt/2.0);
+    s2.n_z += k1_z*(dt/2.0);
+    s2.n_h += k1_h*(dt/2.0);
 
     let (k2_pairs, k2_w, k2_z, k2_h) = derivatives(&s2);
 
     let mut s3 = s_original.clone();
     s3.time += dt/2.0;
     s3.n_pairs += k2_pairs*(dt/2.0);
-    s3.n_W += k2_w*(dt/2.0);
-    s3.n_Z += k2_z*(dt/2.0);
-    s3.n_H += k2_h*(dt/2.0);
+    s3.n_w += k2_w*(dt/2.0);
+    s3.n_z += k2_z*(dt/2.0);
+    s3.n_h += k2_h*(dt/2.0);
 
     let (k3_pairs, k3_w, k3_z, k3_h) = derivatives(&s3);
 
     let mut s4 = s_original.clone();
     s4.time += dt;
     s4.n_pairs += k3_pairs*dt;
-    s4.n_W += k3_w*dt;
-    s4.n_Z += k3_z*dt;
-    s4.n_H += k3_h*dt;
+    s4.n_w += k3_w*dt;
+    s4.n_z += k3_z*dt;
+    s4.n_h += k3_h*dt;
 
     let (k4_pairs, k4_w, k4_z, k4_h) = derivatives(&s4);
 
     s.n_pairs += (k1_pairs + 2.0*k2_pairs + 2.0*k3_pairs + k4_pairs)*dt/6.0;
-    s.n_W += (k1_w + 2.0*k2_w + 2.0*k3_w + k4_w)*dt/6.0;
-    s.n_Z += (k1_z + 2.0*k2_z + 2.0*k3_z + k4_z)*dt/6.0;
-    s.n_H += (k1_h + 2.0*k2_h + 2.0*k3_h + k4_h)*dt/6.0;
+    s.n_w += (k1_w + 2.0*k2_w + 2.0*k3_w + k4_w)*dt/6.0;
+    s.n_z += (k1_z + 2.0*k2_z + 2.0*k3_z + k4_z)*dt/6.0;
+    s.n_h += (k1_h + 2.0*k2_h + 2.0*k3_h + k4_h)*dt/6.0;
 }
 
 impl State {
-    fn new(n_photon_init: f64) -> Self {
+    fn new(r0: f64, n_photon_init: f64) -> Self {
         // Starting state: finite photon density, zero W/Z/H
         // No singularities are triggered at initialization.
         State {
             time: 0.0,
-            radius: R0,
+            radius: r0,
             n_pairs: 0.0,
             n_photon: n_photon_init,
-            n_W: 0.0,
-            n_Z: 0.0,
-            n_H: 0.0,
+            n_w: 0.0,
+            n_z: 0.0,
+            n_h: 0.0,
         }
     }
 }
 
-fn main() {
-    let norm = find_norm_for_band();
+fn main() -> ExitCode {
+    cli::main(env!("CARGO_BIN_NAME"), run)
+}
+
+fn run(cfg: &Config) -> Result<(), SimError> {
+    let norm = find_norm_for_band(cfg);
     let params = Params { norm };
     println!("Normalization for Band function: {}", params.norm);
 
     // Estimate initial photon number density:
-    let avg_E_kev = 1.0; // chosen for scaling
-    let avg_E_mev = avg_E_kev*KEV_TO_MEV;
-    let avg_E_erg = avg_E_mev*MEV_TO_ERG;
-    let vol = (4.0/3.0)*PI*R0.powi(3);
-    let total_photons = E_ISO_ERG / avg_E_erg;
+    let avg_e_kev = cfg.avg_e_kev; // chosen for scaling
+    let avg_e_mev = avg_e_kev*KEV_TO_MEV;
+    let avg_e_erg = avg_e_mev*MEV_TO_ERG;
+    let vol = (4.0/3.0)*PI*cfg.r0.powi(3);
+    let total_photons = cfg.e_iso_erg / avg_e_erg;
     let n_photon_init = total_photons/vol;
 
-    let mut state = State::new(n_photon_init);
+    let mut state = State::new(cfg.r0, n_photon_init);
 
-    let total_time = 33000;
-    let dt = 1.0; // Negative dt scenario simulates contraction
+    let dt = cfg.dt; // Negative dt scenario simulates contraction
 
-    let mut file = std::fs::File::create("extended_simulation_output.csv").unwrap();
-    writeln!(file, "time(s),radius(cm),n_photon(cm^-3),n_pairs(cm^-3),n_W,n_Z,n_H").unwrap();
+    let results_path = cfg.out_dir.join(&cfg.results_file);
+    let mut file = CsvWriter::create(
+        &results_path,
+        &["time(s)", "radius(cm)", "n_photon(cm^-3)", "n_pairs(cm^-3)", "n_W", "n_Z", "n_H"],
+    )?;
 
-    for _ in 0..(total_time as usize) {
+    for step in 0..cfg.steps {
         state.time += dt;
-        state.radius = R0 + *BETA*C*state.time;
+        state.radius = cfg.r0 + cfg.beta*C*state.time;
 
         // Regularity Axiom in Action:
         // If radius approaches zero or a problematic regime, one would impose a cutoff or renormalization:
-        // Here, we just allow scale factor changes but in a QG scenario, we'd check for divergence and stop if necessary.
-        let scale = (R0/state.radius).powi(4);
+        // Here, we just allow scale factor changes and stop below if a density diverges.
+        let scale = (cfg.r0/state.radius).powi(4);
         state.n_photon = n_photon_init*scale;
 
         // If n_photon or other fields become too large, in a full model we would renormalize or handle boundaries:
@@ -241,21 +305,17 @@ fn main() {
 
         rk4_step(&mut state, dt);
 
-        writeln!(file, "{},{},{},{},{},{},{}",
-                 state.time,
-                 state.radius,
-                 state.n_photon,
-                 state.n_pairs,
-                 state.n_W,
-                 state.n_Z,
-                 state.n_H).unwrap();
+        file.row(&[state.time, state.radius, state.n_photon, state.n_pairs, state.n_w, state.n_z, state.n_h])?;
+        check_finite_value("photon density", step, state.n_photon)?;
+        check_finite_value("pair density", step, state.n_pairs)?;
     }
+    file.finish()?;
 
     println!("Final pairs: {} cm^-3", state.n_pairs);
-    println!("Final W density: {} cm^-3", state.n_W);
-    println!("Final Z density: {} cm^-3", state.n_Z);
-    println!("Final H density: {} cm^-3", state.n_H);
-    println!("Data in extended_simulation_output.csv");
+    println!("Final W density: {} cm^-3", state.n_w);
+    println!("Final Z density: {} cm^-3", state.n_z);
+    println!("Final H density: {} cm^-3", state.n_h);
+    println!("Data in {}", results_path.display());
 
 // -------------------------------------------------------------------------
 // Post-processing and Visual Representation (from the given visualization script):
@@ -268,9 +328,9 @@ fn main() {
 // - Future work would incorporate metric and curvature computations, providing a direct 
 //   link between field evolution and the integrability of the action.
 // -------------------------------------------------------------------------
+    Ok(())
 }
 
-
 /*
 use std::f64::consts::PI;
 use std::io::Write;
@@ -0,0 +1,232 @@
+use physics_core::config::{non_negative, positive, total_time_for, GridConfig, RunConfig, Validate};
+use physics_core::error::{check_finite, SimError};
+use physics_core::constants::si::{C, H, K_B, SIGMA_T};
+use physics_core::cosmology::{Background, CosmologySettings};
+use physics_core::io::CsvWriter;
+use physics_core::diffusion::{CgSettings, DiffusionScheme, Diffuser};
+use physics_core::frw::{comoving_density, FrwClock};
+use physics_core::stability::Stability;
+use physics_core::{Boundaries, Grid3};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+
+// Run parameters. The defaults reproduce the original hard-coded run; pass a
+// TOML or JSON file with `--config` to override any of them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // Cosmological parameters for recombination era (approx):
+    cosmology: CosmologySettings, // expansion history
+    a_rec: f64,      // Scale factor at recombination (z ~ 1100)
+    t_rec: f64,      // CMB temperature at recombination (K)
+    // Axion-photon coupling upper limit (rough):
+    // g_{aγ} < 10^-11 GeV^-1 ~ 10^-20 J^-1 for demonstration
+    g_agamma: f64,   // J^-1 (approximate order)
+    b_field: f64,    // Cosmic magnetic field upper limit (T)
+    // Electron density at recombination (approx.):
+    // Just after recombination: n_e might be around 10^6 m^-3
+    n_e: f64,
+    grid: GridConfig, // Grid parameters, comoving m (small for demonstration)
+    dt: f64,          // small timestep in seconds
+    total_time: f64,  // simulate a very short time (s)
+    photon_bc: Boundaries,
+    photon_scheme: DiffusionScheme,
+    stability: Stability,
+    cg: CgSettings,
+    out_dir: PathBuf,
+    results_file: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            cosmology: CosmologySettings::default(),
+            a_rec: 1.0/1100.0,
+            t_rec: 3000.0,
+            g_agamma: 1e-20,
+            b_field: 1e-9,
+            n_e: 1e6,
+            grid: GridConfig::new(50, 50, 50, 1100.0), // 1 meter cells at recombination for demonstration (not realistic)
+            dt: 1e-9,
+            total_time: 1e-5,
+            photon_bc: Boundaries::periodic(),
+            photon_scheme: DiffusionScheme::BackwardEuler,
+            stability: Stability::default(),
+            cg: CgSettings::default(),
+            out_dir: PathBuf::from("."),
+            results_file: "recombination_output.csv".into(),
+        }
+    }
+}
+
+impl Validate for Config {
+    fn validate(&self) -> Result<(), String> {
+        self.cosmology.validate()?;
+        positive("a_rec", self.a_rec)?;
+        positive("t_rec", self.t_rec)?;
+        non_negative("g_agamma", self.g_agamma)?;
+        non_negative("b_field", self.b_field)?;
+        positive("n_e", self.n_e)?;
+        positive("dt", self.dt)?;
+        positive("total_time", self.total_time)?;
+        self.stability.validate()?;
+        self.cg.validate()?;
+        self.grid.validate()
+    }
+}
+
+impl RunConfig for Config {
+    fn out_dir(&self) -> &Path {
+        &self.out_dir
+    }
+
+    fn set_out_dir(&mut self, dir: PathBuf) {
+        self.out_dir = dir;
+    }
+
+    fn set_steps(&mut self, steps: usize) {
+        self.total_time = total_time_for(steps, self.dt);
+    }
+}
+
+// Comoving number densities a^3 n, so expansion alone leaves them unchanged.
+struct Field3D {
+    photons: Grid3<f64>,
+    photon_bc: Boundaries,
+    exotic: Grid3<f64>,  // Axion-like particle number density
+}
+
+impl Field3D {
+    fn new(grid: &GridConfig, photon_bc: Boundaries, photon_init: f64) -> Self {
+        Field3D {
+            photons: grid.filled(photon_init),
+            photon_bc,
+            exotic: grid.filled(0.0),
+        }
+    }
+}
+
+// Planck distribution approximation for photon number density at temperature T
+fn planck_number_density(t: f64) -> f64 {
+    // Integrate number density of photons from Planck's law:
+    // n_ph = ∫ (8πν²/c³) [1/(exp(hν/kT)-1)] dν from 0 to ∞
+    // Known result: n_ph = 20.28 * (T[K])^3 / cm^3 for CMB at low frequencies
+    // Convert to SI: n_ph ≈ 2.03e8 * (T/K)^3 m^-3
+    // This is a known standard result: n_ph = (16π (k_B T)^3) / (c^3 h^3) ζ(3)
+    // ζ(3)≈1.2020569. Let's just compute directly:
+    let zeta3 = 1.202056903159594;
+    (16.0 * PI * (K_B * t).powi(3) * zeta3) / (C.powi(3) * H.powi(3))
+}
+
+// Axion-photon conversion rate:
+// For simplicity, assume a small conversion probability per unit time depending on B, g_{aγ}.
+// Realistically, the conversion requires coherence length L. Set L ~ dx (the cell size) for demonstration.
+// Rate ~ (g_{aγ} * B)^2 * c / L  (this is order-of-magnitude, from P ~ ((g_{aγ} B L)/c)^2)
+// If L=dx, rate ~ (g_{aγ}^2 * B^2 * C / dx). This is extremely small.
+fn axion_conversion_rate(cfg: &Config, dx: f64) -> f64 {
+    (cfg.g_agamma.powi(2) * cfg.b_field.powi(2) * C) / dx
+}
+
+// Diffusion coefficient from Thomson scattering:
+// D ~ c * l / 3, l=1/(n_e σ_T)
+// We'll scale it down for computational tractability:
+fn diffusion_coefficient(n_e: f64) -> f64 {
+    let mean_free_path = 1.0/(n_e*SIGMA_T); 
+    let d = C * mean_free_path / 3.0; 
+    // This is enormous (~ 1.5e14 m?), to make simulation stable on meter scale, reduce:
+    // We pretend our box represents a scaled version. Let’s just use the real D and accept that
+    // in a 50 m box this doesn't represent the full Universe. Physically realistic scaling is huge.
+    d
+}
+
+// Free electrons are no longer made or lost after recombination, so their
+// density dilutes from n_e at a_rec as n_e(a) = n_e (a_rec/a)^3.
+fn electron_density(cfg: &Config, a: f64) -> f64 {
+    cfg.n_e * (cfg.a_rec / a).powi(3)
+}
+
+/// Evolve the photon and exotic densities around recombination for
+/// `total_time`, writing the lattice averages to `results_file` in `out_dir`.
+pub fn run(cfg: &Config) -> Result<(), SimError> {
+    let dt = cfg.dt;
+
+    // Metric factor: For a FLRW metric, g_{μν}=diag(1,-a²,-a²,-a²). The
+    // grid is comoving, so a(t) enters the diffusion and the cell's physical
+    // size; over a short run it barely moves from a_rec.
+    let background = Background::new(&cfg.cosmology)?;
+    let t_start = background.time(cfg.a_rec);
+    let mut clock = FrwClock::new(background, t_start, dt);
+    let a = clock.scale_factor();
+    let photon_init = planck_number_density(cfg.t_rec);
+
+    println!("Initial CMB photon number density at recombination: {:.3e} photons/m^3", photon_init);
+
+    let mut field = Field3D::new(&cfg.grid, cfg.photon_bc, comoving_density(photon_init, a));
+
+    // D at a_rec; the mean free path grows as n_e(a) falls, so D(a) =
+    // d_coef (a/a_rec)^3, on the comoving grid times 1/a^2. That grows with
+    // a, so the stability check takes it at the end of the run.
+    let d_coef = diffusion_coefficient(cfg.n_e);
+    let d_scale = |a: f64| diffusion_coefficient(electron_density(cfg, a)) / d_coef / (a * a);
+    let steps = (cfg.total_time/dt) as usize;
+    let a_final = clock.expansion().scale_factor(t_start + steps as f64 * dt);
+    let photons = Diffuser::new("photon", cfg.photon_scheme, d_coef, dt, &cfg.cg)
+        .with_max_scale(d_scale(a_final))
+        .checked(field.photons.spacing(), &cfg.stability)?;
+
+    let results_path = cfg.out_dir.join(&cfg.results_file);
+    let mut file = CsvWriter::create(
+        &results_path,
+        &["time(s)", "average_n_photon(m^-3)", "average_n_exotic(m^-3)"],
+    )?;
+
+    for step in 0..steps {
+        let frw = clock.advance(step)?;
+        let axion_rate = axion_conversion_rate(cfg, frw.physical_length(field.photons.spacing()));
+        let mut new_photons = field.photons.clone();
+        let mut new_exotic = field.exotic.clone();
+        let diff_ph = photons.increment_scaled(&field.photons, &field.photon_bc, d_scale(frw.a_mid))?;
+
+        let (nx, ny, nz) = field.photons.dims();
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    let idx = field.photons.idx(x,y,z);
+                    let n_ph = field.photons[idx];
+                    let n_ex = field.exotic[idx];
+
+                    // Photon diffusion:
+                    let dn_ph = diff_ph[idx];
+                    let new_n_ph = n_ph + dn_ph;
+
+                    // Axion (exotic matter) formation: extremely small
+                    // d(n_ex)/dt ~ n_ph * axion_rate
+                    // This is a gross simplification; in reality it's more complicated.
+                    // We show it is negligible:
+                    let dn_ex = n_ph * axion_rate * dt;
+                    let new_n_ex = n_ex + dn_ex;
+
+                    new_photons[idx] = new_n_ph;
+                    new_exotic[idx] = new_n_ex;
+                }
+            }
+        }
+
+        field.photons = new_photons;
+        field.photon_bc.apply_sponge(&mut field.photons, dt);
+        field.exotic = new_exotic;
+
+        let avg_n_ph = frw.physical_density(field.photons.mean());
+        let avg_n_ex = frw.physical_density(field.exotic.mean());
+
+        let time = step as f64 * dt;
+        file.row(&[time, avg_n_ph, avg_n_ex])?;
+        check_finite("photon density", step, &field.photons)?;
+        check_finite("exotic density", step, &field.exotic)?;
+    }
+    file.finish()?;
+
+    println!("3D simulation completed. Results in {}", results_path.display());
+    Ok(())
+}
@@ -8,7 +8,7 @@ import matplotlib.animation as animation
 # Part 1: Plotting the provided averaged data
 ########################################
 
-results_file = '3d_sim_output.csv'
+results_file = 'recombination_output.csv'
 
 times = []
 avg_photon = []